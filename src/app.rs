@@ -1,14 +1,45 @@
+use crate::components::image_canvas::{ContourOverlay, ImageLayer, TextOverlay};
+use crate::image_pipeline::FrameImagePipeline;
 use crate::message::Message;
+use crate::model::attributes::{attribute_f64, attribute_text};
+use crate::model::cine;
+use crate::model::dose_report;
+use crate::model::export::{self, DEFAULT_FILE_TEMPLATE};
+use crate::model::functional_groups;
+use crate::model::fusion;
 use crate::model::loader::load_dicom;
-use crate::model::{DicomEntry, TreeNodeKey, TreeViewMode};
-use crate::views::{image_panel, metadata_panel, tree_panel};
+use crate::model::presentation_export::{
+    build_presentation_state, save_presentation_state, PresentedAnnotation, PresentedImage,
+};
+use crate::model::segmentation::ImageRef;
+use crate::model::thumbnail::{self, Thumbnail, ThumbnailJob};
+use crate::model::{
+    AnnotationConfig, CtDoseSummary, DicomEntry, DicomView, DisplaySettings, DocumentPreview,
+    Draft, EncapsulatedDocument, ExportMode, ExportScale, ExportScope, ExportSource, FramePreview,
+    Fusion, Histogram, HistogramScope, Measurement, MeasurementTool, MprAxis, MprSession, MprView,
+    OverlayStyle, PaperSpeed, PresentationState, Reslice, Shape, StructureSet, TreeNodeKey,
+    TreeViewMode, ViewTransform, Volume, WaveformGain, Window,
+};
+use crate::utils::csv_line;
+use crate::views::{
+    document_panel, histogram_panel, image_panel, metadata_panel, mpr_panel,
+    structured_report_panel, thumbnail_strip, tree_panel, waveform_panel, DosePanel, ExportPanel,
+    FusionPanel, HistogramPanel, MeasurementPanel, OverlayPanel, RoiLegend, SegmentLegend,
+    WaveformPanel,
+};
+use iced::keyboard::{self, Key};
 use iced::widget::text::Wrapping;
 use iced::widget::{button, column, container, row, scrollable, text};
-use iced::{application, Alignment, Element, Length, Task, Theme};
+use iced::{
+    application, Alignment, Color, Element, Length, Point, Rectangle, Size, Subscription, Task,
+    Theme,
+};
 use rfd::AsyncFileDialog;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
 
 const APP_TITLE: &str = "Dicomancer";
+const ULTRASOUND_REGION_COLOR: Color = Color::from_rgb(1.0, 0.8, 0.2);
 
 pub fn run() -> iced::Result {
     let _ = env_logger::Builder::from_default_env()
@@ -17,7 +48,14 @@ pub fn run() -> iced::Result {
 
     application(APP_TITLE, App::update, App::view)
         .theme(App::theme)
-        .run()
+        .subscription(App::subscription)
+        .run_with(|| {
+            let app = App {
+                annotation_config: AnnotationConfig::load(),
+                ..App::default()
+            };
+            (app, Task::none())
+        })
 }
 
 #[derive(Default)]
@@ -26,6 +64,50 @@ pub struct App {
     selected_instance: Option<usize>,
     collapsed_nodes: BTreeSet<TreeNodeKey>,
     tree_view_mode: TreeViewMode,
+    hovered_pixel: Option<Point>,
+    active_tool: Option<MeasurementTool>,
+    draft: Option<Draft>,
+    measurements: Vec<Measurement>,
+    next_measurement_id: usize,
+    show_histogram: bool,
+    histogram_log_scale: bool,
+    histogram_scope: HistogramScope,
+    histogram: Option<Histogram>,
+    overlay_styles: BTreeMap<u16, OverlayStyle>,
+    overlay_layers: Vec<ImageLayer>,
+    /// Segments switched off, by segmentation SOP Instance UID and segment number.
+    hidden_segments: BTreeSet<(String, u16)>,
+    /// Outlines on the displayed frame: visible RT contours, ultrasound
+    /// regions and presentation state graphics.
+    contour_overlays: Vec<ContourOverlay>,
+    /// Ultrasound region names and presentation state text on the displayed
+    /// frame.
+    presentation_labels: Vec<TextOverlay>,
+    /// Presentation state applied per image SOP Instance UID; `None` shows the
+    /// image without one. Images get the newest applicable state the first
+    /// time they are shown.
+    presentation_choices: BTreeMap<String, Option<String>>,
+    /// Contours on the displayed frame, by structure set SOP Instance UID and ROI number.
+    roi_contour_counts: BTreeMap<(String, u32), usize>,
+    /// ROIs switched off, by structure set SOP Instance UID and ROI number.
+    hidden_rois: BTreeSet<(String, u32)>,
+    annotation_config: AnnotationConfig,
+    hide_annotations: bool,
+    /// Whether to outline the ultrasound regions of the displayed frame.
+    show_ultrasound_regions: bool,
+    /// Multiplex group of the displayed waveform.
+    waveform_group: usize,
+    paper_speed: PaperSpeed,
+    waveform_gain: WaveformGain,
+    mpr: Option<MprSession>,
+    /// PET series fused onto images of the same frame of reference.
+    fusion: Option<Fusion>,
+    /// Thumbnails by [`thumbnail::key`], requested once per loaded instance.
+    thumbnails: BTreeMap<String, Thumbnail>,
+    export_mode: ExportMode,
+    export_scale: ExportScale,
+    /// File name template for batch exports; empty uses the default template.
+    export_template: String,
     last_error: Option<String>,
 }
 
@@ -46,12 +128,16 @@ impl App {
             ),
             Message::FilesLoaded(results) => {
                 let mut errors = Vec::new();
+                let mut documents = Vec::new();
                 for result in results {
                     match result {
                         Ok(entry) => {
+                            documents.extend(entry.view.document.clone().filter(|document| {
+                                matches!(document.preview, DocumentPreview::Pending)
+                            }));
                             let index = self.entries.len();
                             self.entries.push(entry);
-                            self.selected_instance = Some(index);
+                            self.select_instance(index);
                         }
                         Err(err) => errors.push(err),
                     }
@@ -68,11 +154,56 @@ impl App {
                     self.last_error = Some(errors.join("\n"));
                 }
 
+                Task::batch([self.request_thumbnails(), Self::render_documents(documents)])
+            }
+            Message::ThumbnailReady(key, result) => {
+                let thumbnail = match result {
+                    Ok(handle) => Thumbnail::Ready(handle),
+                    Err(err) => {
+                        log::warn!("Unable to render thumbnail for {key}: {err}");
+                        Thumbnail::Failed
+                    }
+                };
+                self.thumbnails.insert(key, thumbnail);
+                Task::none()
+            }
+            Message::DocumentRendered(sop_instance_uid, preview) => {
+                if let DocumentPreview::Unavailable(reason) = &preview {
+                    log::warn!("{sop_instance_uid}: no document preview ({reason})");
+                }
+                for entry in &mut self.entries {
+                    if let Some(document) = entry.view.document.as_mut().filter(|document| {
+                        document.sop_instance_uid == sop_instance_uid
+                            && matches!(document.preview, DocumentPreview::Pending)
+                    }) {
+                        Arc::make_mut(document).preview = preview.clone();
+                    }
+                }
                 Task::none()
             }
             Message::SelectInstance(index) => {
                 if index < self.entries.len() {
-                    self.selected_instance = Some(index);
+                    self.select_instance(index);
+                }
+                Task::none()
+            }
+            Message::OpenReferencedInstance(sop_instance_uid, frame) => {
+                match self
+                    .entries
+                    .iter()
+                    .position(|entry| entry.sop_instance_uid == sop_instance_uid)
+                {
+                    Some(index) => {
+                        self.select_instance(index);
+                        if let Some(frame) = frame {
+                            self.select_frame(frame);
+                        }
+                    }
+                    None => {
+                        self.last_error = Some(format!(
+                            "Referenced instance {sop_instance_uid} is not loaded"
+                        ))
+                    }
                 }
                 Task::none()
             }
@@ -88,7 +219,1210 @@ impl App {
                 }
                 Task::none()
             }
+            Message::ImageHovered(position) => {
+                self.hovered_pixel = position;
+                Task::none()
+            }
+            Message::ImagePressed(point) => {
+                if let Some(shape) = self.draft.as_mut().and_then(|draft| draft.press(point)) {
+                    self.add_measurement(shape);
+                }
+                Task::none()
+            }
+            Message::ImageDragged(point) => {
+                self.hovered_pixel = Some(point);
+                if let Some(draft) = &mut self.draft {
+                    draft.drag(point);
+                }
+                Task::none()
+            }
+            Message::ImageReleased(point) => {
+                if let Some(shape) = self.draft.as_mut().and_then(|draft| draft.release(point)) {
+                    self.add_measurement(shape);
+                }
+                Task::none()
+            }
+            Message::SelectTool(tool) => {
+                self.active_tool = tool;
+                self.draft = tool.map(Draft::new);
+                Task::none()
+            }
+            Message::DeleteMeasurement(id) => {
+                self.measurements.retain(|measurement| measurement.id != id);
+                if self.histogram_scope == HistogramScope::Roi(id) {
+                    self.histogram_scope = HistogramScope::Frame;
+                    self.refresh_histogram();
+                }
+                Task::none()
+            }
+            Message::ExportDoseCsv => {
+                let csv = self.dose_csv();
+                Task::perform(
+                    async move {
+                        let Some(handle) = AsyncFileDialog::new()
+                            .set_file_name("ct_dose.csv")
+                            .add_filter("CSV", &["csv"])
+                            .save_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        let path = handle.path().to_path_buf();
+                        std::fs::write(&path, csv)
+                            .map(|_| Some(path.clone()))
+                            .map_err(|err| {
+                                format!("{}: failed to write CSV ({err})", path.display())
+                            })
+                    },
+                    Message::DoseCsvExported,
+                )
+            }
+            Message::DoseCsvExported(result) => {
+                match result {
+                    Ok(Some(path)) => log::info!("Exported CT dose to {}", path.display()),
+                    Ok(None) => {}
+                    Err(err) => self.last_error = Some(err),
+                }
+                Task::none()
+            }
+            Message::SaveDocument => {
+                let Some(document) = self
+                    .selected_entry()
+                    .and_then(|entry| entry.view.document.clone())
+                else {
+                    return Task::none();
+                };
+                Task::perform(
+                    async move {
+                        let extension = document.kind.extension();
+                        let Some(handle) = AsyncFileDialog::new()
+                            .set_file_name(document.file_name())
+                            .add_filter(document.kind.name(), &[extension])
+                            .save_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        let path = handle.path().to_path_buf();
+                        std::fs::write(&path, &document.data)
+                            .map(|_| Some(path.clone()))
+                            .map_err(|err| {
+                                format!("{}: failed to write document ({err})", path.display())
+                            })
+                    },
+                    Message::DocumentSaved,
+                )
+            }
+            Message::DocumentSaved(result) => {
+                match result {
+                    Ok(Some(path)) => log::info!("Saved document to {}", path.display()),
+                    Ok(None) => {}
+                    Err(err) => self.last_error = Some(err),
+                }
+                Task::none()
+            }
+            Message::SelectWaveformGroup(group) => {
+                self.waveform_group = group;
+                Task::none()
+            }
+            Message::SetPaperSpeed(speed) => {
+                self.paper_speed = speed;
+                Task::none()
+            }
+            Message::SetWaveformGain(gain) => {
+                self.waveform_gain = gain;
+                Task::none()
+            }
+            Message::ExportMeasurements => {
+                let csv = self.measurements_csv();
+                Task::perform(
+                    async move {
+                        let Some(handle) = AsyncFileDialog::new()
+                            .set_file_name("measurements.csv")
+                            .add_filter("CSV", &["csv"])
+                            .save_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        let path = handle.path().to_path_buf();
+                        std::fs::write(&path, csv)
+                            .map(|_| Some(path.clone()))
+                            .map_err(|err| {
+                                format!("{}: failed to write CSV ({err})", path.display())
+                            })
+                    },
+                    Message::MeasurementsExported,
+                )
+            }
+            Message::MeasurementsExported(result) => {
+                match result {
+                    Ok(Some(path)) => log::info!("Exported measurements to {}", path.display()),
+                    Ok(None) => {}
+                    Err(err) => self.last_error = Some(err),
+                }
+                Task::none()
+            }
+            Message::SetExportMode(mode) => {
+                self.export_mode = mode;
+                Task::none()
+            }
+            Message::SetExportTemplate(template) => {
+                self.export_template = template;
+                Task::none()
+            }
+            Message::SetExportScale(scale) => {
+                self.export_scale = scale;
+                Task::none()
+            }
+            Message::ExportImages(scope) => self.export_images(scope),
+            Message::ExportCine(scope) => self.export_cine(scope),
+            Message::ImagesExported(result) => {
+                match result {
+                    Ok(0) => {}
+                    Ok(count) => log::info!("Exported {count} image(s)"),
+                    Err(err) => self.last_error = Some(err),
+                }
+                Task::none()
+            }
+            Message::ExportPresentationState => self.export_presentation_state(),
+            Message::PresentationStateExported(result) => {
+                match result {
+                    Ok(Some(path)) => {
+                        log::info!("Saved presentation state to {}", path.display())
+                    }
+                    Ok(None) => {}
+                    Err(err) => self.last_error = Some(err),
+                }
+                Task::none()
+            }
+            Message::SetWindow(window) => {
+                self.apply_window(window);
+                Task::none()
+            }
+            Message::ResetWindow => {
+                let default = self
+                    .selected_entry()
+                    .and_then(|entry| entry.view.frame.as_ref())
+                    .and_then(|frame| frame.default_window);
+                if let Some(window) = default {
+                    self.apply_window(window);
+                }
+                Task::none()
+            }
+            Message::SetColorMap(color_map) => {
+                let Some(entry) = self
+                    .selected_instance
+                    .and_then(|index| self.entries.get_mut(index))
+                else {
+                    return Task::none();
+                };
+                entry.view.color_map = color_map;
+                let window = entry
+                    .view
+                    .window
+                    .or_else(|| entry.view.frame.as_ref()?.default_window);
+                if let Some(window) = window {
+                    self.apply_window(window);
+                }
+                Task::none()
+            }
+            Message::ToggleHistogram => {
+                self.show_histogram = !self.show_histogram;
+                self.refresh_histogram();
+                Task::none()
+            }
+            Message::SetHistogramLogScale(log_scale) => {
+                self.histogram_log_scale = log_scale;
+                Task::none()
+            }
+            Message::SetHistogramScope(scope) => {
+                self.histogram_scope = scope;
+                self.refresh_histogram();
+                Task::none()
+            }
+            Message::SelectFrame(frame_index) => {
+                self.select_frame(frame_index);
+                Task::none()
+            }
+            Message::SetOverlayVisible(group, visible) => {
+                self.overlay_style_mut(group).visible = visible;
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SetSegmentVisible(segmentation, segment, visible) => {
+                let key = (segmentation, segment);
+                if visible {
+                    self.hidden_segments.remove(&key);
+                } else {
+                    self.hidden_segments.insert(key);
+                }
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SetRoiVisible(structure_set, roi, visible) => {
+                let key = (structure_set, roi);
+                if visible {
+                    self.hidden_rois.remove(&key);
+                } else {
+                    self.hidden_rois.insert(key);
+                }
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SetUltrasoundRegionsVisible(visible) => {
+                self.show_ultrasound_regions = visible;
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SelectPresentationState(choice) => {
+                let Some(sop_instance_uid) = self
+                    .selected_entry()
+                    .map(|entry| entry.sop_instance_uid.clone())
+                else {
+                    return Task::none();
+                };
+                self.presentation_choices.insert(sop_instance_uid, choice);
+                self.apply_presentation_state();
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SetOverlayColor(group, color) => {
+                self.overlay_style_mut(group).color = color;
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::ToggleAnnotations => {
+                self.hide_annotations = !self.hide_annotations;
+                Task::none()
+            }
+            Message::TransformView(transform) => {
+                self.transform_view(transform);
+                Task::none()
+            }
+            Message::OpenMpr => {
+                let Some(series) = self
+                    .selected_entry()
+                    .map(|entry| entry.series_instance_uid.clone())
+                else {
+                    return Task::none();
+                };
+                let instances = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.series_instance_uid == series)
+                    .filter_map(|entry| {
+                        Some((entry.view.source.clone(), entry.view.pixels.clone()?))
+                    })
+                    .collect::<Vec<_>>();
+                Task::perform(
+                    async move { Volume::assemble(instances).map(Arc::new) },
+                    Message::MprVolumeAssembled,
+                )
+            }
+            Message::MprVolumeAssembled(result) => match result {
+                Ok(volume) => {
+                    let title = self
+                        .selected_entry()
+                        .and_then(|entry| attribute_text(&entry.view.source, "SeriesDescription"))
+                        .unwrap_or_else(|| String::from("Series"));
+                    self.mpr = Some(MprSession::new(volume, title));
+                    self.refresh_mpr()
+                }
+                Err(err) => {
+                    self.last_error = Some(format!("Unable to build volume: {err}"));
+                    Task::none()
+                }
+            },
+            Message::MprResliced(generation, result) => {
+                let Some(session) = self
+                    .mpr
+                    .as_mut()
+                    .filter(|session| session.generation == generation)
+                else {
+                    return Task::none();
+                };
+                match result {
+                    Ok(views) => session.views = views,
+                    Err(err) => self.last_error = Some(format!("Unable to reslice volume: {err}")),
+                }
+                Task::none()
+            }
+            Message::SetFusionSeries(series) => {
+                let Some(series) = series else {
+                    self.fusion = None;
+                    self.refresh_overlay_layers();
+                    return Task::none();
+                };
+                let Some(frame_of_reference) = self
+                    .selected_entry()
+                    .and_then(|entry| attribute_text(&entry.view.source, "FrameOfReferenceUID"))
+                else {
+                    return Task::none();
+                };
+                let instances = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.series_instance_uid == series)
+                    .filter_map(|entry| {
+                        Some((entry.view.source.clone(), entry.view.pixels.clone()?))
+                    })
+                    .collect::<Vec<_>>();
+                self.fusion = Some(Fusion::new(series.clone(), frame_of_reference));
+                self.refresh_overlay_layers();
+                Task::perform(
+                    async move { fusion::assemble_pet(instances).map(Arc::new) },
+                    move |result| Message::FusionVolumeAssembled(series.clone(), result),
+                )
+            }
+            Message::FusionVolumeAssembled(series, result) => {
+                let Some(fusion) = self
+                    .fusion
+                    .as_mut()
+                    .filter(|fusion| fusion.series_instance_uid == series)
+                else {
+                    return Task::none();
+                };
+                match result {
+                    Ok(volume) => fusion.set_volume(volume),
+                    Err(err) => {
+                        self.fusion = None;
+                        self.last_error = Some(format!("Unable to fuse PET series: {err}"));
+                    }
+                }
+                self.refresh_overlay_layers();
+                Task::none()
+            }
+            Message::SetFusionOpacity(opacity) => {
+                if let Some(fusion) = &mut self.fusion {
+                    fusion.opacity = opacity;
+                    self.refresh_overlay_layers();
+                }
+                Task::none()
+            }
+            Message::SetFusionColorMap(color_map) => {
+                if let Some(fusion) = &mut self.fusion {
+                    fusion.color_map = color_map;
+                    self.refresh_overlay_layers();
+                }
+                Task::none()
+            }
+            Message::SetFusionUpper(upper) => {
+                if let Some(fusion) = &mut self.fusion {
+                    fusion.window = Window::from_bounds(fusion.window.lower(), upper);
+                    self.refresh_overlay_layers();
+                }
+                Task::none()
+            }
+            Message::CloseMpr => {
+                self.mpr = None;
+                Task::none()
+            }
+            Message::MoveMprCrosshair(axis, point) => {
+                if let Some(session) = &mut self.mpr {
+                    if let Some(view) = session.view(axis) {
+                        session.geometry.center = view.reslice.to_patient(point);
+                    }
+                }
+                self.refresh_mpr()
+            }
+            Message::ScrollMpr(axis, steps) => {
+                if let Some(session) = &mut self.mpr {
+                    let plane = session.geometry.plane(&session.volume, axis);
+                    let distance = steps as f64 * session.volume.min_spacing();
+                    session.geometry.center = std::array::from_fn(|component| {
+                        session.geometry.center[component] - plane.normal[component] * distance
+                    });
+                }
+                self.refresh_mpr()
+            }
+            Message::SetMprSlab(slab) => {
+                if let Some(session) = &mut self.mpr {
+                    session.slab = slab;
+                }
+                self.refresh_mpr()
+            }
+            Message::SetMprProjection(projection) => {
+                if let Some(session) = &mut self.mpr {
+                    session.projection = projection;
+                }
+                self.refresh_mpr()
+            }
+            Message::SetMprYaw(yaw) => {
+                if let Some(session) = &mut self.mpr {
+                    session.geometry.yaw = yaw;
+                }
+                self.refresh_mpr()
+            }
+            Message::SetMprPitch(pitch) => {
+                if let Some(session) = &mut self.mpr {
+                    session.geometry.pitch = pitch;
+                }
+                self.refresh_mpr()
+            }
+            Message::ResetMpr => {
+                if let Some(session) = &mut self.mpr {
+                    *session = MprSession::new(session.volume.clone(), session.title.clone());
+                }
+                self.refresh_mpr()
+            }
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            Key::Character("h") if modifiers.is_empty() => Some(Message::ToggleAnnotations),
+            _ => None,
+        })
+    }
+
+    fn select_frame(&mut self, frame_index: u32) {
+        let presentation_state = self.active_presentation_state();
+        let Some(entry) = self
+            .selected_instance
+            .and_then(|index| self.entries.get_mut(index))
+        else {
+            return;
+        };
+        let view = &mut entry.view;
+        let Some(pixels) = view.pixels.clone() else {
+            return;
+        };
+        if frame_index >= view.frame_count || frame_index == view.frame_index {
+            return;
+        }
+
+        // Frame-specific presentation state windows replace the window of the
+        // previous frame, as does the frame's own Frame VOI LUT while the
+        // previous frame's is unchanged; otherwise the current window carries over.
+        let mut window = view.window;
+        let previous_default = view.frame.as_ref().and_then(|frame| frame.default_window);
+        if window == previous_default
+            && functional_groups::varies_per_frame(&view.source, "FrameVOILUTSequence")
+        {
+            window = None;
+        }
+        if let Some(state) = &presentation_state {
+            let sop_instance_uid = &entry.sop_instance_uid;
+            let next = state.window(sop_instance_uid, frame_index);
+            if next.is_some() && next != state.window(sop_instance_uid, view.frame_index) {
+                window = next;
+            }
+            view.displayed_area = state.displayed_area(sop_instance_uid, frame_index);
+        }
+        match FrameImagePipeline::render_frame(
+            &view.source,
+            &pixels,
+            frame_index,
+            window,
+            view.color_map,
+        ) {
+            Ok(mut rendered) => {
+                rendered.preview.handle =
+                    FrameImagePipeline::orient(rendered.preview.handle, view.transform);
+                view.frame_index = frame_index;
+                view.window =
+                    window.or(rendered.data.as_ref().and_then(|data| data.default_window));
+                view.image = Some(rendered.preview);
+                view.frame = rendered.data;
+                view.frame_groups = functional_groups::frame_groups(&view.source, frame_index);
+            }
+            Err(err) => {
+                self.last_error =
+                    Some(format!("Unable to render frame {}: {err}", frame_index + 1));
+                return;
+            }
+        }
+        self.draft = self.active_tool.map(Draft::new);
+        self.histogram_scope = HistogramScope::Frame;
+        self.refresh_histogram();
+        self.refresh_overlay_layers();
+    }
+
+    /// Reslices the three MPR planes in the background; results of older
+    /// requests are dropped when they arrive after newer ones.
+    fn refresh_mpr(&mut self) -> Task<Message> {
+        let Some(session) = &mut self.mpr else {
+            return Task::none();
+        };
+        session.generation += 1;
+        let generation = session.generation;
+        let volume = session.volume.clone();
+        let (geometry, slab, projection, window) = (
+            session.geometry,
+            session.slab,
+            session.projection,
+            session.window,
+        );
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    MprAxis::ALL
+                        .into_iter()
+                        .map(|axis| {
+                            let reslice = Reslice::new(&volume, &geometry, axis, slab, projection);
+                            let preview = FramePreview {
+                                handle: FrameImagePipeline::render_reslice(&reslice, window),
+                                columns: reslice.width,
+                                rows: reslice.height,
+                            };
+                            MprView { reslice, preview }
+                        })
+                        .collect()
+                })
+                .await
+                .map_err(|err| err.to_string())
+            },
+            move |result| Message::MprResliced(generation, result),
+        )
+    }
+
+    fn transform_view(&mut self, transform: ViewTransform) {
+        let Some(entry) = self
+            .selected_instance
+            .and_then(|index| self.entries.get_mut(index))
+        else {
+            return;
+        };
+        let view = &mut entry.view;
+        let Some(preview) = &mut view.image else {
+            return;
+        };
+        preview.handle = FrameImagePipeline::orient(preview.handle.clone(), transform);
+        view.transform = view.transform.then(transform);
+        self.refresh_overlay_layers();
+    }
+
+    fn overlay_style_mut(&mut self, group: u16) -> &mut OverlayStyle {
+        self.overlay_styles
+            .entry(group)
+            .or_insert_with(|| OverlayStyle::for_group(group))
+    }
+
+    fn refresh_overlay_layers(&mut self) {
+        self.overlay_layers.clear();
+        self.contour_overlays.clear();
+        self.presentation_labels.clear();
+        self.roi_contour_counts.clear();
+        let presentation_state = self.active_presentation_state();
+        let Some(view) = self.selected_entry().map(|entry| &entry.view) else {
+            return;
+        };
+        let Some(frame) = &view.frame else {
+            return;
+        };
+        let bounds = Rectangle::new(
+            Point::ORIGIN,
+            Size::new(frame.columns as f32, frame.rows as f32),
+        );
+        let mut layers = Vec::new();
+        if let Some(fusion) = self
+            .fusion
+            .as_ref()
+            .filter(|fusion| fusion.applies_to(view))
+        {
+            if let (Some(volume), Some(plane)) = (&fusion.volume, &frame.plane) {
+                layers.push(ImageLayer {
+                    handle: FrameImagePipeline::orient(
+                        FrameImagePipeline::fusion_handle(
+                            volume,
+                            plane,
+                            frame.rows,
+                            frame.columns,
+                            fusion.window,
+                            fusion.color_map,
+                            fusion.opacity,
+                        ),
+                        view.transform,
+                    ),
+                    bounds,
+                });
+            }
+        }
+        layers.extend(frame.overlays.iter().filter_map(|bitmap| {
+            let style = self
+                .overlay_styles
+                .get(&bitmap.group)
+                .copied()
+                .unwrap_or_else(|| OverlayStyle::for_group(bitmap.group));
+            style.visible.then(|| ImageLayer {
+                handle: FrameImagePipeline::orient(
+                    FrameImagePipeline::overlay_handle(bitmap, style.color.to_color()),
+                    view.transform,
+                ),
+                bounds: Rectangle::new(
+                    Point::new(bitmap.origin.1 as f32, bitmap.origin.0 as f32),
+                    Size::new(bitmap.columns as f32, bitmap.rows as f32),
+                ),
+            })
+        }));
+
+        let image = ImageRef::displayed(view);
+        for segmentation in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.view.segmentation.as_deref())
+        {
+            for mask in segmentation.masks_for(&image, frame.rows, frame.columns) {
+                let key = (segmentation.sop_instance_uid.clone(), mask.segment);
+                let Some(segment) = segmentation.segment(mask.segment) else {
+                    continue;
+                };
+                if self.hidden_segments.contains(&key) {
+                    continue;
+                }
+                layers.push(ImageLayer {
+                    handle: FrameImagePipeline::orient(
+                        FrameImagePipeline::segment_handle(&mask, segment.to_color()),
+                        view.transform,
+                    ),
+                    bounds,
+                });
+            }
+        }
+
+        let mut contours = Vec::new();
+        let mut counts = BTreeMap::new();
+        for structure_set in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.view.structure_set.as_deref())
+        {
+            for outline in structure_set.outlines_on(&image) {
+                let key = (structure_set.sop_instance_uid.clone(), outline.roi);
+                let Some(roi) = structure_set.roi(outline.roi) else {
+                    continue;
+                };
+                *counts.entry(key.clone()).or_insert(0) += 1;
+                if !self.hidden_rois.contains(&key) {
+                    contours.push(ContourOverlay {
+                        points: outline.points,
+                        closed: outline.closed,
+                        color: roi.to_color(),
+                    });
+                }
+            }
+        }
+        let mut labels = Vec::new();
+        if self.show_ultrasound_regions {
+            for region in &frame.ultrasound_regions {
+                let outline = region.outline();
+                labels.push(TextOverlay {
+                    position: Point::new(outline[0].x + 2.0, outline[0].y + 2.0),
+                    text: region.name(),
+                    color: ULTRASOUND_REGION_COLOR,
+                });
+                contours.push(ContourOverlay {
+                    points: outline,
+                    closed: true,
+                    color: ULTRASOUND_REGION_COLOR,
+                });
+            }
+        }
+        if let (Some(state), Some(entry)) = (presentation_state, self.selected_entry()) {
+            let (graphics, texts) =
+                state.annotations(&entry.sop_instance_uid, view.frame_index, bounds.size());
+            contours.extend(graphics.into_iter().map(|graphic| ContourOverlay {
+                points: graphic.points,
+                closed: graphic.closed,
+                color: graphic.color,
+            }));
+            labels.extend(texts.into_iter().map(|text| TextOverlay {
+                position: text.position,
+                text: text.text,
+                color: text.color,
+            }));
         }
+        self.overlay_layers = layers;
+        self.contour_overlays = contours;
+        self.presentation_labels = labels;
+        self.roi_contour_counts = counts;
+    }
+
+    /// ROIs of the selected structure set, or of every loaded structure set
+    /// that applies to the displayed image, for the ROI table.
+    fn roi_legend(&self) -> Vec<RoiLegend<'_>> {
+        let Some(view) = self.selected_entry().map(|entry| &entry.view) else {
+            return Vec::new();
+        };
+        let structure_sets: Vec<&StructureSet> = match view.structure_set.as_deref() {
+            Some(structure_set) => vec![structure_set],
+            None => {
+                let image = ImageRef::displayed(view);
+                self.entries
+                    .iter()
+                    .filter_map(|entry| entry.view.structure_set.as_deref())
+                    .filter(|structure_set| structure_set.applies_to(&image))
+                    .collect()
+            }
+        };
+        structure_sets
+            .into_iter()
+            .flat_map(|structure_set| {
+                structure_set.rois.iter().map(move |roi| {
+                    let key = (structure_set.sop_instance_uid.clone(), roi.number);
+                    RoiLegend {
+                        structure_set: &structure_set.sop_instance_uid,
+                        roi,
+                        visible: !self.hidden_rois.contains(&key),
+                        on_frame: self.roi_contour_counts.get(&key).copied().unwrap_or(0),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Segments of every loaded segmentation that applies to the displayed
+    /// image, for the legend.
+    fn segment_legend(&self) -> Vec<SegmentLegend<'_>> {
+        let Some(view) = self.selected_entry().map(|entry| &entry.view) else {
+            return Vec::new();
+        };
+        let image = ImageRef::displayed(view);
+        let mut legend = Vec::new();
+        for segmentation in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.view.segmentation.as_deref())
+            .filter(|segmentation| segmentation.applies_to(&image))
+        {
+            let on_frame = segmentation.segments_on(&image);
+            for segment in &segmentation.segments {
+                legend.push(SegmentLegend {
+                    segmentation: &segmentation.sop_instance_uid,
+                    segment,
+                    visible: !self
+                        .hidden_segments
+                        .contains(&(segmentation.sop_instance_uid.clone(), segment.number)),
+                    on_frame: on_frame.contains(&segment.number),
+                });
+            }
+        }
+        legend
+    }
+
+    /// Loaded presentation states that reference the image, newest first.
+    fn presentation_states_for(&self, sop_instance_uid: &str) -> Vec<&PresentationState> {
+        let mut states = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.view.presentation_state.as_deref())
+            .filter(|state| state.applies_to(sop_instance_uid))
+            .collect::<Vec<_>>();
+        states.sort_by(|a, b| b.created.cmp(&a.created));
+        states
+    }
+
+    /// Presentation state applied to the displayed image.
+    fn active_presentation_state(&self) -> Option<Arc<PresentationState>> {
+        let entry = self.selected_entry()?;
+        let choice = self
+            .presentation_choices
+            .get(&entry.sop_instance_uid)?
+            .as_deref()?;
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.view.presentation_state.as_ref())
+            .find(|state| state.sop_instance_uid == choice)
+            .cloned()
+    }
+
+    /// Orients, windows and crops the displayed image as its presentation
+    /// state specifies, or restores the defaults without one.
+    fn apply_presentation_state(&mut self) {
+        let state = self.active_presentation_state();
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let view = &entry.view;
+        let sop_instance_uid = entry.sop_instance_uid.clone();
+        let target = state
+            .as_ref()
+            .map(|state| state.transform)
+            .unwrap_or_default();
+        let window = match &state {
+            Some(state) => state.window(&sop_instance_uid, view.frame_index),
+            None => view.frame.as_ref().and_then(|frame| frame.default_window),
+        };
+        self.transform_view(view.transform.inverse().then(target));
+        if let Some(window) = window {
+            self.apply_window(window);
+        }
+        if let Some(entry) = self
+            .selected_instance
+            .and_then(|index| self.entries.get_mut(index))
+        {
+            entry.view.displayed_area = state
+                .and_then(|state| state.displayed_area(&sop_instance_uid, entry.view.frame_index));
+        }
+    }
+
+    fn selected_entry(&self) -> Option<&DicomEntry> {
+        self.selected_instance
+            .and_then(|index| self.entries.get(index))
+    }
+
+    fn apply_window(&mut self, window: Window) {
+        let Some(entry) = self
+            .selected_instance
+            .and_then(|index| self.entries.get_mut(index))
+        else {
+            return;
+        };
+        let view = &mut entry.view;
+        if let (Some(frame), Some(preview)) = (&view.frame, &mut view.image) {
+            preview.handle = FrameImagePipeline::orient(
+                FrameImagePipeline::render_windowed(frame, window, view.color_map),
+                view.transform,
+            );
+            view.window = Some(window);
+        }
+    }
+
+    fn refresh_histogram(&mut self) {
+        self.histogram = None;
+        if !self.show_histogram {
+            return;
+        }
+        let Some(frame) = self
+            .selected_entry()
+            .and_then(|entry| entry.view.frame.as_ref())
+        else {
+            return;
+        };
+        self.histogram = match self.histogram_scope {
+            HistogramScope::Frame => Histogram::for_frame(frame),
+            HistogramScope::Roi(id) => self
+                .measurements
+                .iter()
+                .find(|measurement| measurement.id == id)
+                .and_then(|measurement| Histogram::for_roi(frame, &measurement.shape)),
+        };
+    }
+
+    fn select_instance(&mut self, index: usize) {
+        self.selected_instance = Some(index);
+        if let Some(entry) = self.entries.get(index) {
+            if !self
+                .presentation_choices
+                .contains_key(&entry.sop_instance_uid)
+            {
+                if let Some(state) = self
+                    .presentation_states_for(&entry.sop_instance_uid)
+                    .first()
+                {
+                    let choice = Some(state.sop_instance_uid.clone());
+                    self.presentation_choices
+                        .insert(entry.sop_instance_uid.clone(), choice);
+                    self.apply_presentation_state();
+                }
+            }
+        }
+        self.hovered_pixel = None;
+        self.waveform_group = 0;
+        self.draft = self.active_tool.map(Draft::new);
+        self.histogram_scope = HistogramScope::Frame;
+        self.refresh_histogram();
+        self.refresh_overlay_layers();
+    }
+
+    fn add_measurement(&mut self, shape: Shape) {
+        let Some(instance) = self.selected_instance else {
+            return;
+        };
+        let Some(view) = self.entries.get(instance).map(|entry| &entry.view) else {
+            return;
+        };
+        self.next_measurement_id += 1;
+        self.measurements.push(Measurement::new(
+            self.next_measurement_id,
+            instance,
+            view.frame_index,
+            shape,
+            view.frame.as_ref(),
+        ));
+    }
+
+    /// Renders PDF pages of newly loaded documents in the background, since
+    /// running `pdftoppm` would otherwise hold up loading.
+    fn render_documents(documents: Vec<Arc<EncapsulatedDocument>>) -> Task<Message> {
+        Task::batch(documents.into_iter().map(|document| {
+            let sop_instance_uid = document.sop_instance_uid.clone();
+            Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || document.render_preview())
+                        .await
+                        .unwrap_or_else(|err| DocumentPreview::Unavailable(err.to_string()))
+                },
+                move |preview| Message::DocumentRendered(sop_instance_uid.clone(), preview),
+            )
+        }))
+    }
+
+    /// Renders thumbnails of newly loaded instances in the background.
+    fn request_thumbnails(&mut self) -> Task<Message> {
+        let jobs = self
+            .entries
+            .iter()
+            .filter_map(ThumbnailJob::for_entry)
+            .filter(|job| !self.thumbnails.contains_key(&job.key))
+            .collect::<Vec<_>>();
+        let tasks = jobs.into_iter().map(|job| {
+            self.thumbnails.insert(job.key.clone(), Thumbnail::Pending);
+            Task::perform(
+                async move {
+                    let key = job.key.clone();
+                    let result = tokio::task::spawn_blocking(move || job.load_or_render())
+                        .await
+                        .map_err(|err| err.to_string())
+                        .and_then(|result| result);
+                    (key, result)
+                },
+                |(key, result)| Message::ThumbnailReady(key, result),
+            )
+        });
+        Task::batch(tasks.collect::<Vec<_>>())
+    }
+
+    fn export_images(&self, scope: ExportScope) -> Task<Message> {
+        let Some(view) = self.selected_entry().map(|entry| &entry.view) else {
+            return Task::none();
+        };
+        let Some(pixels) = view.pixels.clone() else {
+            return Task::none();
+        };
+        let mode = self.export_mode;
+        let template = self.export_template.clone();
+        let display = self.display_settings(view);
+
+        if scope == ExportScope::Frame {
+            let object = view.source.clone();
+            let frame_index = view.frame_index;
+            let file_name = export::file_name(&template, &object, frame_index, view.frame_count);
+            return Task::perform(
+                async move {
+                    let Some(handle) = AsyncFileDialog::new()
+                        .set_file_name(file_name)
+                        .add_filter("PNG", &["png"])
+                        .add_filter("JPEG", &["jpg", "jpeg"])
+                        .add_filter("TIFF", &["tif", "tiff"])
+                        .save_file()
+                        .await
+                    else {
+                        return Ok(0);
+                    };
+                    let path = handle.path().to_path_buf();
+                    export::render_export(&object, &pixels, frame_index, mode, &display)
+                        .map_err(|err| format!("{}: {err}", path.display()))?
+                        .save(&path)
+                        .map(|_| 1)
+                },
+                Message::ImagesExported,
+            );
+        }
+
+        let sources = self.export_sources(scope);
+        Task::perform(
+            async move {
+                let Some(handle) = AsyncFileDialog::new().pick_folder().await else {
+                    return Ok(0);
+                };
+                export::export_batch(&sources, handle.path(), &template, mode, &display)
+            },
+            Message::ImagesExported,
+        )
+    }
+
+    /// Writes the frames of the displayed object or series as an animation.
+    fn export_cine(&self, scope: ExportScope) -> Task<Message> {
+        let Some(entry) = self.selected_entry() else {
+            return Task::none();
+        };
+        let display = self.display_settings(&entry.view);
+        let sources = self.export_sources(scope);
+        let file_name = match attribute_text(&entry.view.source, "SeriesDescription") {
+            Some(description) => format!("{description}.gif"),
+            None => String::from("cine.gif"),
+        };
+        Task::perform(
+            async move {
+                let Some(handle) = AsyncFileDialog::new()
+                    .set_file_name(file_name)
+                    .add_filter("GIF", &["gif"])
+                    .add_filter("APNG", &["png", "apng"])
+                    .save_file()
+                    .await
+                else {
+                    return Ok(0);
+                };
+                cine::export_cine(&sources, handle.path(), &display)
+            },
+            Message::ImagesExported,
+        )
+    }
+
+    /// Saves the displayed series, as currently shown, as a presentation state.
+    /// Images whose rescale differs from the displayed image are left out, as
+    /// a presentation state holds a single Modality LUT.
+    fn export_presentation_state(&mut self) -> Task<Message> {
+        let Some((index, entry)) = self
+            .selected_instance
+            .and_then(|index| Some((index, self.entries.get(index)?)))
+        else {
+            return Task::none();
+        };
+        let Some(rescale) = entry.view.frame.as_ref().map(|frame| &frame.rescale) else {
+            self.last_error = Some(String::from("The displayed instance has no image"));
+            return Task::none();
+        };
+        // The displayed image comes first; its rescale and polarity apply to all.
+        let mut series = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(other, candidate)| {
+                *other != index && candidate.series_instance_uid == entry.series_instance_uid
+            })
+            .collect::<Vec<_>>();
+        series.insert(0, (index, entry));
+        let mut images = Vec::new();
+        let mut skipped = 0;
+        for (instance, other) in series {
+            let view = &other.view;
+            let Some(frame) = view.frame.as_ref() else {
+                continue;
+            };
+            if frame.rescale.slope != rescale.slope || frame.rescale.intercept != rescale.intercept
+            {
+                skipped += 1;
+                continue;
+            }
+            images.push(PresentedImage {
+                object: &view.source,
+                frame,
+                frame_count: view.frame_count,
+                window: view.window.or(frame.default_window),
+                displayed_area: view.displayed_area,
+                annotations: self
+                    .measurements
+                    .iter()
+                    .filter(|measurement| measurement.instance == instance)
+                    .map(|measurement| PresentedAnnotation {
+                        frame_index: measurement.frame,
+                        shape: measurement.shape.clone(),
+                        label: measurement.result.summary_short(),
+                    })
+                    .collect(),
+            });
+        }
+        if skipped > 0 {
+            log::info!(
+                "Left {skipped} image(s) with a different rescale out of the presentation state"
+            );
+        }
+        let object = match build_presentation_state(&images, entry.view.transform) {
+            Ok(object) => object,
+            Err(err) => {
+                self.last_error = Some(err);
+                return Task::none();
+            }
+        };
+        Task::perform(
+            async move {
+                let Some(handle) = AsyncFileDialog::new()
+                    .set_file_name("presentation_state.dcm")
+                    .add_filter("DICOM", &["dcm"])
+                    .save_file()
+                    .await
+                else {
+                    return Ok(None);
+                };
+                let path = handle.path().to_path_buf();
+                save_presentation_state(&object, &path).map(|_| Some(path))
+            },
+            Message::PresentationStateExported,
+        )
+    }
+
+    /// Rendering state of `view` that exports reproduce.
+    fn display_settings(&self, view: &DicomView) -> DisplaySettings {
+        DisplaySettings {
+            window: view.window,
+            color_map: view.color_map,
+            transform: view.transform,
+            displayed_area: view.displayed_area,
+            overlays: self.overlay_styles.clone(),
+            scale: self.export_scale,
+            annotations: (!self.hide_annotations).then(|| {
+                let modality = attribute_text(&view.source, "Modality");
+                self.annotation_config
+                    .for_modality(modality.as_deref())
+                    .clone()
+            }),
+        }
+    }
+
+    /// The displayed object, or every loaded instance of its series ordered by
+    /// Instance Number.
+    fn export_sources(&self, scope: ExportScope) -> Vec<ExportSource> {
+        let Some(entry) = self.selected_entry() else {
+            return Vec::new();
+        };
+        let mut sources = self
+            .entries
+            .iter()
+            .filter(|other| match scope {
+                ExportScope::Series => other.series_instance_uid == entry.series_instance_uid,
+                ExportScope::Frame | ExportScope::Instance => std::ptr::eq(*other, entry),
+            })
+            .filter_map(|other| {
+                Some(ExportSource {
+                    object: other.view.source.clone(),
+                    pixels: other.view.pixels.clone()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        sources.sort_by(|a, b| {
+            let number = |source: &ExportSource| {
+                attribute_f64(&source.object, "InstanceNumber").unwrap_or(f64::INFINITY)
+            };
+            number(a).total_cmp(&number(b))
+        });
+        sources
+    }
+
+    fn measurements_csv(&self) -> String {
+        let mut header = vec!["File", "SOPInstanceUID"];
+        header.extend(Measurement::CSV_HEADER);
+        let mut csv = csv_line(&header);
+        for measurement in &self.measurements {
+            let Some(entry) = self.entries.get(measurement.instance) else {
+                continue;
+            };
+            let mut fields = vec![
+                entry.view.file_path.display().to_string(),
+                entry.sop_instance_uid.clone(),
+            ];
+            fields.extend(measurement.csv_fields());
+            csv.push_str(&csv_line(&fields));
+        }
+        csv
+    }
+
+    /// CT dose of every loaded dose report.
+    fn dose_summaries(&self) -> Vec<&CtDoseSummary> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.view.dose_summary.as_deref())
+            .collect()
+    }
+
+    fn dose_csv(&self) -> String {
+        let mut csv = csv_line(&dose_report::CSV_HEADER);
+        for row in dose_report::csv_rows(&self.dose_summaries()) {
+            csv.push_str(&csv_line(&row));
+        }
+        csv
     }
 
     pub fn view(&self) -> Element<'_, Message> {
@@ -114,19 +1448,133 @@ impl App {
             .padding(16)
             .width(Length::FillPortion(5));
 
-        let image_content = image_panel(selected_view);
-        let image_panel = container(image_content)
+        let selected_measurements = self
+            .measurements
+            .iter()
+            .filter(|measurement| {
+                Some(measurement.instance) == self.selected_instance
+                    && selected_view.is_some_and(|view| view.frame_index == measurement.frame)
+            })
+            .collect::<Vec<_>>();
+        let roi_ids = selected_measurements
+            .iter()
+            .filter(|measurement| measurement.result.stats.is_some())
+            .map(|measurement| measurement.id)
+            .collect();
+        let histogram_content = histogram_panel(HistogramPanel {
+            visible: self.show_histogram,
+            histogram: self.histogram.as_ref(),
+            window: selected_view.and_then(|view| view.window),
+            log_scale: self.histogram_log_scale,
+            scope: self.histogram_scope,
+            roi_ids,
+            unit: selected_view
+                .and_then(|view| view.frame.as_ref())
+                .and_then(|frame| frame.rescale.unit.as_deref()),
+        });
+        let report = selected_view.and_then(|view| view.structured_report.as_deref());
+        let document = selected_view.and_then(|view| view.document.as_deref());
+        let waveform = selected_view.and_then(|view| view.waveform.as_deref());
+        let image_content = match (report, document, waveform) {
+            (Some(report), ..) => structured_report_panel(
+                report,
+                selected_view
+                    .and_then(|view| view.dose_summary.as_deref())
+                    .map(|summary| DosePanel {
+                        summary,
+                        study: dose_report::study_totals(self.dose_summaries().into_iter().filter(
+                            |other| other.study_instance_uid == summary.study_instance_uid,
+                        ))
+                        .pop(),
+                    }),
+            ),
+            (None, Some(document), _) => document_panel(document),
+            (None, None, Some(waveform)) => waveform_panel(WaveformPanel {
+                waveform,
+                group: self.waveform_group,
+                speed: self.paper_speed,
+                gain: self.waveform_gain,
+            }),
+            (None, None, None) => image_panel(
+                selected_view,
+                self.hovered_pixel,
+                MeasurementPanel {
+                    active_tool: self.active_tool,
+                    draft: self.draft.as_ref(),
+                    measurements: selected_measurements,
+                    total: self.measurements.len(),
+                },
+                OverlayPanel {
+                    styles: &self.overlay_styles,
+                    layers: &self.overlay_layers,
+                    segments: self.segment_legend(),
+                    contours: &self.contour_overlays,
+                    labels: &self.presentation_labels,
+                    ultrasound_regions: self.show_ultrasound_regions,
+                    rois: self.roi_legend(),
+                    presentation_states: self
+                        .selected_entry()
+                        .map(|entry| self.presentation_states_for(&entry.sop_instance_uid))
+                        .unwrap_or_default(),
+                    presentation_state: self
+                        .selected_entry()
+                        .and_then(|entry| self.presentation_choices.get(&entry.sop_instance_uid))
+                        .and_then(|choice| choice.as_deref()),
+                },
+                FusionPanel {
+                    candidates: self
+                        .selected_entry()
+                        .map(|entry| fusion::candidates(&self.entries, entry))
+                        .unwrap_or_default(),
+                    fusion: self
+                        .fusion
+                        .as_ref()
+                        .filter(|fusion| selected_view.is_some_and(|view| fusion.applies_to(view))),
+                },
+                ExportPanel {
+                    mode: self.export_mode,
+                    scale: self.export_scale,
+                    template: &self.export_template,
+                    placeholder: DEFAULT_FILE_TEMPLATE,
+                },
+                (!self.hide_annotations).then(|| {
+                    let modality =
+                        selected_view.and_then(|view| attribute_text(&view.source, "Modality"));
+                    self.annotation_config.for_modality(modality.as_deref())
+                }),
+            ),
+        };
+        let mut image_column = column![image_content].spacing(8);
+        if selected_view.is_some_and(|view| view.frame.is_some()) {
+            image_column = image_column.push(histogram_content);
+        }
+        let image_panel = container(image_column)
             .padding(16)
             .width(Length::FillPortion(3))
             .height(Length::Fill)
             .align_x(Alignment::Center)
             .align_y(Alignment::Center);
 
-        let mut content = column![row![tree_panel, metadata_panel, image_panel]
-            .spacing(16)
-            .width(Length::Fill)
-            .height(Length::Fill)]
-        .spacing(16);
+        let panels = match &self.mpr {
+            Some(session) => row![
+                tree_panel,
+                container(mpr_panel(session))
+                    .padding(16)
+                    .width(Length::FillPortion(8))
+                    .height(Length::Fill)
+            ],
+            None => row![tree_panel, metadata_panel, image_panel],
+        };
+        let mut content = column![].spacing(16);
+        if !self.entries.is_empty() {
+            content = content.push(thumbnail_strip(
+                &self.entries,
+                &thumbnail::group_series(&self.entries),
+                &self.thumbnails,
+                self.selected_instance,
+            ));
+        }
+        content = content.push(panels.spacing(16).width(Length::Fill).height(Length::Fill));
 
         if let Some(error) = &self.last_error {
             content = content.push(text(error).size(16).wrapping(Wrapping::Word));
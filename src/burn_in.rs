@@ -0,0 +1,223 @@
+//! Draws the viewer's corner annotations, orientation labels and scale bar
+//! into exported images, laid out like the image canvas draws them.
+
+use crate::components::image_canvas::{
+    round_length, ANNOTATION_COLOR, ANNOTATION_LINE_HEIGHT, ANNOTATION_MARGIN, ANNOTATION_SIZE,
+    ORIENTATION_SIZE, SCALE_BAR_MAX_FRACTION,
+};
+use crate::model::{AnnotationContext, Corner, CornerTemplates, EdgeLabels};
+use iced::advanced::graphics::text::{cosmic_text, font_system};
+use iced::alignment::{Horizontal, Vertical};
+use image::RgbaImage;
+
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache};
+
+const SHADOW: [u8; 4] = [0, 0, 0, 204];
+
+/// Text and markings burned into one exported frame.
+pub struct BurnIn<'a> {
+    pub templates: &'a CornerTemplates,
+    pub context: AnnotationContext<'a>,
+    pub edge_labels: Option<EdgeLabels>,
+    /// Millimetres per exported pixel along the image rows, for the scale bar.
+    pub mm_per_pixel: Option<f64>,
+}
+
+impl BurnIn<'_> {
+    pub fn draw(&self, image: &mut RgbaImage) {
+        let mut fonts = match font_system().write() {
+            Ok(fonts) => fonts,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let mut painter = Painter {
+            fonts: fonts.raw(),
+            cache: SwashCache::new(),
+            image,
+        };
+
+        if let Some(labels) = &self.edge_labels {
+            let edges = [
+                (
+                    &labels.left,
+                    (ANNOTATION_MARGIN, height / 2.0),
+                    (Horizontal::Left, Vertical::Center),
+                ),
+                (
+                    &labels.right,
+                    (width - ANNOTATION_MARGIN, height / 2.0),
+                    (Horizontal::Right, Vertical::Center),
+                ),
+                (
+                    &labels.top,
+                    (width / 2.0, ANNOTATION_MARGIN),
+                    (Horizontal::Center, Vertical::Top),
+                ),
+                (
+                    &labels.bottom,
+                    (width / 2.0, height - ANNOTATION_MARGIN),
+                    (Horizontal::Center, Vertical::Bottom),
+                ),
+            ];
+            for (label, position, alignment) in edges {
+                painter.label(label, position, ORIENTATION_SIZE, alignment);
+            }
+        }
+
+        if let Some(mm_per_pixel) = self.mm_per_pixel {
+            painter.scale_bar(mm_per_pixel);
+        }
+
+        for corner in Corner::ALL {
+            let lines = self.templates.render(corner, &self.context);
+            let (x, horizontal) = match corner {
+                Corner::TopLeft | Corner::BottomLeft => (ANNOTATION_MARGIN, Horizontal::Left),
+                Corner::TopRight | Corner::BottomRight => {
+                    (width - ANNOTATION_MARGIN, Horizontal::Right)
+                }
+            };
+            let top = match corner {
+                Corner::TopLeft | Corner::TopRight => ANNOTATION_MARGIN,
+                Corner::BottomLeft | Corner::BottomRight => {
+                    height - ANNOTATION_MARGIN - lines.len() as f32 * ANNOTATION_LINE_HEIGHT
+                }
+            };
+            for (index, line) in lines.iter().enumerate() {
+                painter.label(
+                    line,
+                    (x, top + index as f32 * ANNOTATION_LINE_HEIGHT),
+                    ANNOTATION_SIZE,
+                    (horizontal, Vertical::Top),
+                );
+            }
+        }
+    }
+}
+
+struct Painter<'a> {
+    fonts: &'a mut FontSystem,
+    cache: SwashCache,
+    image: &'a mut RgbaImage,
+}
+
+impl Painter<'_> {
+    /// Draws one line of text with a dark shadow, aligned around `position`.
+    fn label(
+        &mut self,
+        content: &str,
+        position: (f32, f32),
+        size: f32,
+        (horizontal, vertical): (Horizontal, Vertical),
+    ) {
+        if content.is_empty() {
+            return;
+        }
+        let metrics = Metrics::new(size, size * 1.3);
+        let mut buffer = Buffer::new(self.fonts, metrics);
+        buffer.set_size(self.fonts, None, None);
+        buffer.set_text(
+            self.fonts,
+            content,
+            Attrs::new().family(Family::SansSerif),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(self.fonts, false);
+
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max);
+        let left = match horizontal {
+            Horizontal::Left => position.0,
+            Horizontal::Center => position.0 - width / 2.0,
+            Horizontal::Right => position.0 - width,
+        };
+        let top = match vertical {
+            Vertical::Top => position.1,
+            Vertical::Center => position.1 - metrics.line_height / 2.0,
+            Vertical::Bottom => position.1 - metrics.line_height,
+        };
+        let (left, top) = (left.round() as i32, top.round() as i32);
+
+        let [r, g, b, a] = ANNOTATION_COLOR.into_rgba8();
+        for (offset, [r, g, b, a]) in [(1, SHADOW), (0, [r, g, b, a])] {
+            let image = &mut *self.image;
+            buffer.draw(
+                self.fonts,
+                &mut self.cache,
+                cosmic_text::Color::rgba(r, g, b, a),
+                |x, y, w, h, color| {
+                    let rgba = [color.r(), color.g(), color.b(), color.a()];
+                    fill(image, left + offset + x, top + offset + y, w, h, rgba);
+                },
+            );
+        }
+    }
+
+    /// Draws a bar of a round length in millimetres above the bottom
+    /// orientation label, as the image canvas does.
+    fn scale_bar(&mut self, mm_per_pixel: f64) {
+        if mm_per_pixel <= 0.0 || !mm_per_pixel.is_finite() {
+            return;
+        }
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        let max_length = (width * SCALE_BAR_MAX_FRACTION) as f64 * mm_per_pixel;
+        let Some(length) = round_length(max_length) else {
+            return;
+        };
+        let bar = (length / mm_per_pixel) as f32;
+        let y = (height - ANNOTATION_MARGIN - ORIENTATION_SIZE - 10.0).round() as i32;
+        let left = ((width - bar) / 2.0).round() as i32;
+        let right = left + bar.round() as i32;
+        let tick = 4;
+
+        let [r, g, b, a] = ANNOTATION_COLOR.into_rgba8();
+        for (grow, color) in [(1, SHADOW), (0, [r, g, b, a])] {
+            let thickness = 1 + 2 * grow as u32;
+            fill(
+                self.image,
+                left - grow,
+                y - grow,
+                (right - left) as u32 + thickness,
+                thickness,
+                color,
+            );
+            for x in [left, right] {
+                fill(
+                    self.image,
+                    x - grow,
+                    y - tick - grow,
+                    thickness,
+                    tick as u32 + thickness,
+                    color,
+                );
+            }
+        }
+        self.label(
+            &format!("{length} mm"),
+            (width / 2.0, (y - tick - 2) as f32),
+            ANNOTATION_SIZE,
+            (Horizontal::Center, Vertical::Bottom),
+        );
+    }
+}
+
+/// Alpha-blends `color` over a rectangle, clipped to the image.
+fn fill(image: &mut RgbaImage, x: i32, y: i32, w: u32, h: u32, color: [u8; 4]) {
+    let alpha = color[3] as u32;
+    if alpha == 0 {
+        return;
+    }
+    let x_range = x.max(0)..(x + w as i32).min(image.width() as i32);
+    let y_range = y.max(0)..(y + h as i32).min(image.height() as i32);
+    for py in y_range {
+        for px in x_range.clone() {
+            let pixel = image.get_pixel_mut(px as u32, py as u32);
+            for channel in 0..3 {
+                let blended =
+                    (color[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha)) / 255;
+                pixel[channel] = blended as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use dicom::object::{DefaultDicomObject, FileMetaTable, InMemDicomObject};
+use dicom::transfer_syntax::entries::EXPLICIT_VR_LITTLE_ENDIAN;
+use flate2::read::DeflateDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+pub const DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN: &str = "1.2.840.10008.1.2.1.99";
+
+/// Reads a Deflated Explicit VR Little Endian file, which the dicom-rs
+/// parser does not inflate. Returns `None` for any other transfer syntax.
+///
+/// The object is re-labelled Explicit VR Little Endian, matching the inflated
+/// data set it holds in memory.
+pub fn open_deflated(path: &Path) -> Result<Option<DefaultDicomObject>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = BufReader::new(file);
+    let buffer = reader.fill_buf().map_err(|err| err.to_string())?;
+    if buffer.len() >= 132 && &buffer[128..132] == b"DICM" {
+        reader.consume(128);
+    } else if !buffer.starts_with(b"DICM") {
+        return Ok(None);
+    }
+
+    let mut meta = FileMetaTable::from_reader(&mut reader).map_err(|err| err.to_string())?;
+    if meta.transfer_syntax() != DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN {
+        return Ok(None);
+    }
+
+    let mut inflated = Vec::new();
+    DeflateDecoder::new(reader)
+        .read_to_end(&mut inflated)
+        .map_err(|err| format!("Failed to inflate the data set: {err}"))?;
+    let object = InMemDicomObject::read_dataset_with_ts(
+        inflated.as_slice(),
+        &EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+    )
+    .map_err(|err| format!("Failed to read the inflated data set: {err}"))?;
+    meta.set_transfer_syntax(&EXPLICIT_VR_LITTLE_ENDIAN);
+
+    Ok(Some(object.with_exact_meta(meta)))
+}
@@ -0,0 +1,1091 @@
+//! Native JPEG-LS (ITU-T T.87) decoding, registered with the dicom-rs
+//! transfer syntax registry in place of its CharLS-backed stubs.
+
+use dicom::encoding::adapters::{
+    DecodeError, DecodeResult, PixelDataObject, PixelDataReader, RawPixelData,
+};
+use dicom::encoding::{Codec, NeverAdapter, NeverPixelAdapter, TransferSyntax};
+use std::borrow::Cow;
+
+dicom::encoding::submit_transfer_syntax!(TransferSyntax::<
+    NeverAdapter,
+    JpegLsAdapter,
+    NeverPixelAdapter,
+>::new_ele(
+    "1.2.840.10008.1.2.4.80",
+    "JPEG-LS Lossless Image Compression",
+    Codec::EncapsulatedPixelData(Some(JpegLsAdapter), None),
+));
+
+dicom::encoding::submit_transfer_syntax!(TransferSyntax::<
+    NeverAdapter,
+    JpegLsAdapter,
+    NeverPixelAdapter,
+>::new_ele(
+    "1.2.840.10008.1.2.4.81",
+    "JPEG-LS Lossy (Near-Lossless) Image Compression",
+    Codec::EncapsulatedPixelData(Some(JpegLsAdapter), None),
+));
+
+/// Pixel data reader for the JPEG-LS transfer syntaxes.
+#[derive(Debug, Clone, Copy)]
+pub struct JpegLsAdapter;
+
+impl PixelDataReader for JpegLsAdapter {
+    fn decode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        dst: &mut Vec<u8>,
+    ) -> DecodeResult<()> {
+        let bits_allocated = src.bits_allocated().ok_or(DecodeError::MissingAttribute {
+            name: "BitsAllocated",
+        })?;
+        if bits_allocated != 8 && bits_allocated != 16 {
+            return Err(custom_error(format!(
+                "JPEG-LS with Bits Allocated {bits_allocated} is not supported"
+            )));
+        }
+        let frames = src.number_of_frames().unwrap_or(1);
+        if frame >= frames {
+            return Err(DecodeError::FrameRangeOutOfBounds);
+        }
+        let raw = src.raw_pixel_data().ok_or(DecodeError::NotEncapsulated)?;
+        let data = frame_fragments(&raw, frame, frames)?;
+
+        let image = decode(&data).map_err(custom_error)?;
+        let (rows, columns) = (src.rows(), src.cols());
+        if rows.is_some_and(|rows| rows as usize != image.height)
+            || columns.is_some_and(|columns| columns as usize != image.width)
+        {
+            return Err(custom_error(format!(
+                "JPEG-LS frame is {}×{}, but the image is {}×{}",
+                image.width,
+                image.height,
+                columns.unwrap_or(0),
+                rows.unwrap_or(0)
+            )));
+        }
+        if src
+            .samples_per_pixel()
+            .is_some_and(|samples| samples as usize != image.components)
+        {
+            return Err(custom_error(format!(
+                "JPEG-LS frame has {} components, but Samples per Pixel is {}",
+                image.components,
+                src.samples_per_pixel().unwrap_or(0)
+            )));
+        }
+
+        if bits_allocated == 8 {
+            dst.extend(image.samples.iter().map(|&sample| sample as u8));
+        } else {
+            dst.extend(image.samples.iter().flat_map(|sample| sample.to_le_bytes()));
+        }
+        Ok(())
+    }
+}
+
+fn custom_error(message: String) -> DecodeError {
+    DecodeError::Custom {
+        message,
+        source: None,
+    }
+}
+
+/// Bytes of one frame, joining fragments through the Basic Offset Table when
+/// a frame spans several of them.
+fn frame_fragments(raw: &RawPixelData, frame: u32, frames: u32) -> DecodeResult<Cow<'_, [u8]>> {
+    let fragments = &raw.fragments;
+    if fragments.len() == frames as usize {
+        return fragments
+            .get(frame as usize)
+            .map(|fragment| Cow::Borrowed(fragment.as_slice()))
+            .ok_or_else(|| custom_error(format!("Missing fragment for frame {frame}")));
+    }
+    if frames == 1 {
+        return Ok(Cow::Owned(fragments.concat()));
+    }
+
+    let start = raw
+        .offset_table
+        .get(frame as usize)
+        .copied()
+        .ok_or_else(|| custom_error(format!("Missing offset for frame {frame}")))?
+        as usize;
+    let end = raw
+        .offset_table
+        .get(frame as usize + 1)
+        .map(|&end| end as usize);
+    let mut offset = 0;
+    let mut data = Vec::new();
+    for fragment in fragments {
+        if end.is_some_and(|end| offset >= end) {
+            break;
+        }
+        if offset >= start {
+            data.extend_from_slice(fragment);
+        }
+        // Each fragment is preceded by an 8-byte item header.
+        offset += fragment.len() + 8;
+    }
+    Ok(Cow::Owned(data))
+}
+
+/// A decoded JPEG-LS image, samples interleaved by pixel.
+pub struct JpegLsImage {
+    pub width: usize,
+    pub height: usize,
+    pub components: usize,
+    pub samples: Vec<u16>,
+}
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DRI: u8 = 0xDD;
+const SOF55: u8 = 0xF7;
+const LSE: u8 = 0xF8;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+
+/// Decodes a JPEG-LS stream: lossless and near-lossless, all interleave modes
+/// and restart intervals. Mapping tables (palettised JPEG-LS) are rejected.
+pub fn decode(data: &[u8]) -> Result<JpegLsImage, String> {
+    let mut stream = MarkerStream { data, position: 0 };
+    if stream.next_marker()? != SOI {
+        return Err("JPEG-LS stream does not start with SOI".to_string());
+    }
+
+    let mut frame: Option<FrameHeader> = None;
+    let mut presets = Presets::default();
+    let mut restart_interval = 0;
+    let mut samples = Vec::new();
+    loop {
+        let marker = stream.next_marker()?;
+        match marker {
+            SOF55 => {
+                let segment = stream.segment()?;
+                let header = FrameHeader::parse(segment)?;
+                samples = vec![0u16; header.width * header.height * header.components.len()];
+                frame = Some(header);
+            }
+            LSE => presets.parse(stream.segment()?)?,
+            DRI => {
+                let segment = stream.segment()?;
+                restart_interval = segment
+                    .iter()
+                    .fold(0usize, |value, &byte| (value << 8) | byte as usize);
+            }
+            SOS => {
+                let frame = frame
+                    .as_ref()
+                    .ok_or("JPEG-LS scan precedes the frame header")?;
+                let segment = stream.segment()?;
+                let scan = ScanHeader::parse(segment, frame)?;
+                let segments = stream.entropy_segments();
+                decode_scan(
+                    frame,
+                    &scan,
+                    &presets,
+                    restart_interval,
+                    &segments,
+                    &mut samples,
+                )?;
+            }
+            EOI => break,
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                return Err("Stream is a JPEG, not a JPEG-LS, image".to_string());
+            }
+            _ => {
+                stream.segment()?;
+            }
+        }
+    }
+
+    let frame = frame.ok_or("JPEG-LS stream has no frame header")?;
+    Ok(JpegLsImage {
+        width: frame.width,
+        height: frame.height,
+        components: frame.components.len(),
+        samples,
+    })
+}
+
+struct MarkerStream<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> MarkerStream<'a> {
+    fn next_marker(&mut self) -> Result<u8, String> {
+        while self.position < self.data.len() && self.data[self.position] != 0xFF {
+            self.position += 1;
+        }
+        // Any number of 0xFF fill bytes may precede a marker.
+        while self.position < self.data.len() && self.data[self.position] == 0xFF {
+            self.position += 1;
+        }
+        let marker = *self
+            .data
+            .get(self.position)
+            .ok_or("JPEG-LS stream ended before EOI")?;
+        self.position += 1;
+        Ok(marker)
+    }
+
+    /// The parameters of a marker segment, after its length field.
+    fn segment(&mut self) -> Result<&'a [u8], String> {
+        let length = self
+            .data
+            .get(self.position..self.position + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+            .ok_or("JPEG-LS marker segment is truncated")?;
+        let segment = self
+            .data
+            .get(self.position + 2..self.position + length.max(2))
+            .ok_or("JPEG-LS marker segment is truncated")?;
+        self.position += length.max(2);
+        Ok(segment)
+    }
+
+    /// Entropy-coded segments of a scan, split at restart markers, leaving the
+    /// stream at the marker that ends the scan.
+    fn entropy_segments(&mut self) -> Vec<&'a [u8]> {
+        let mut segments = Vec::new();
+        let mut start = self.position;
+        let mut position = self.position;
+        while position + 1 < self.data.len() {
+            // Within scan data 0xFF is always followed by a stuffed zero bit.
+            if self.data[position] == 0xFF && self.data[position + 1] >= 0x80 {
+                let marker = self.data[position + 1];
+                segments.push(&self.data[start..position]);
+                if (RST0..=RST7).contains(&marker) {
+                    position += 2;
+                    start = position;
+                    continue;
+                }
+                self.position = position;
+                return segments;
+            }
+            position += 1;
+        }
+        segments.push(&self.data[start..]);
+        self.position = self.data.len();
+        segments
+    }
+}
+
+struct FrameHeader {
+    precision: u32,
+    width: usize,
+    height: usize,
+    /// Component identifiers in frame order.
+    components: Vec<u8>,
+}
+
+impl FrameHeader {
+    fn parse(segment: &[u8]) -> Result<Self, String> {
+        let truncated = || "JPEG-LS frame header is truncated".to_string();
+        let [precision, height_high, height_low, width_high, width_low, count, ..] = *segment
+        else {
+            return Err(truncated());
+        };
+        let count = count as usize;
+        let components: Vec<u8> = (0..count)
+            .map(|index| segment.get(6 + index * 3).copied().ok_or_else(truncated))
+            .collect::<Result<_, _>>()?;
+        let header = Self {
+            precision: precision as u32,
+            width: u16::from_be_bytes([width_high, width_low]) as usize,
+            height: u16::from_be_bytes([height_high, height_low]) as usize,
+            components,
+        };
+        if !(2..=16).contains(&header.precision) {
+            return Err(format!(
+                "JPEG-LS sample precision {} is not supported",
+                header.precision
+            ));
+        }
+        if header.width == 0 || header.height == 0 || header.components.is_empty() {
+            return Err("JPEG-LS frame header has no image size".to_string());
+        }
+        Ok(header)
+    }
+}
+
+struct ScanHeader {
+    /// Indices into the frame components, in scan order.
+    components: Vec<usize>,
+    near: i32,
+    interleave: u8,
+    point_transform: u32,
+}
+
+impl ScanHeader {
+    fn parse(segment: &[u8], frame: &FrameHeader) -> Result<Self, String> {
+        let truncated = || "JPEG-LS scan header is truncated".to_string();
+        let count = *segment.first().ok_or_else(truncated)? as usize;
+        let components = (0..count)
+            .map(|index| {
+                let id = *segment.get(1 + index * 2).ok_or_else(truncated)?;
+                if segment.get(2 + index * 2).is_some_and(|&table| table != 0) {
+                    return Err("JPEG-LS mapping tables are not supported".to_string());
+                }
+                frame
+                    .components
+                    .iter()
+                    .position(|&component| component == id)
+                    .ok_or_else(|| format!("JPEG-LS scan refers to unknown component {id}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let parameters = segment
+            .get(1 + count * 2..4 + count * 2)
+            .ok_or_else(truncated)?;
+        let interleave = parameters[1];
+        if interleave > 2 || (interleave == 0 && count != 1) {
+            return Err(format!("Invalid JPEG-LS interleave mode {interleave}"));
+        }
+        Ok(Self {
+            components,
+            near: parameters[0] as i32,
+            interleave,
+            point_transform: (parameters[2] & 0x0F) as u32,
+        })
+    }
+}
+
+/// Preset coding parameters from an LSE segment; zero selects the default.
+#[derive(Default)]
+struct Presets {
+    max_value: i32,
+    t1: i32,
+    t2: i32,
+    t3: i32,
+    reset: i32,
+}
+
+impl Presets {
+    fn parse(&mut self, segment: &[u8]) -> Result<(), String> {
+        match segment.first() {
+            Some(1) => {
+                let values: Vec<i32> = segment[1..]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as i32)
+                    .collect();
+                let [max_value, t1, t2, t3, reset, ..] = values[..] else {
+                    return Err("JPEG-LS preset parameters are truncated".to_string());
+                };
+                *self = Self {
+                    max_value,
+                    t1,
+                    t2,
+                    t3,
+                    reset,
+                };
+                Ok(())
+            }
+            Some(id) => Err(format!("JPEG-LS extension segment {id} is not supported")),
+            None => Err("JPEG-LS extension segment is empty".to_string()),
+        }
+    }
+}
+
+/// Coding parameters of one scan (T.87 A.2.1 and C.2.4.1.1).
+struct Parameters {
+    max_value: i32,
+    near: i32,
+    t1: i32,
+    t2: i32,
+    t3: i32,
+    reset: i32,
+    range: i32,
+    qbpp: u32,
+    limit: u32,
+}
+
+impl Parameters {
+    fn new(precision: u32, near: i32, presets: &Presets) -> Result<Self, String> {
+        let max_value = if presets.max_value > 0 {
+            presets.max_value
+        } else {
+            (1 << precision) - 1
+        };
+        if near > 255.min(max_value / 2) {
+            return Err(format!("Invalid JPEG-LS NEAR value {near}"));
+        }
+
+        let clamp = |value: i32, low: i32| {
+            if value > max_value || value < low {
+                low
+            } else {
+                value
+            }
+        };
+        let (default_t1, default_t2, default_t3) = if max_value >= 128 {
+            let factor = (max_value.min(4095) + 128) / 256;
+            let t1 = clamp(factor + 2 + 3 * near, near + 1);
+            let t2 = clamp(factor * 4 + 3 + 5 * near, t1);
+            (t1, t2, clamp(factor * 17 + 4 + 7 * near, t2))
+        } else {
+            let factor = 256 / (max_value + 1);
+            let t1 = clamp((3 / factor + 3 * near).max(2), near + 1);
+            let t2 = clamp((7 / factor + 5 * near).max(3), t1);
+            (t1, t2, clamp((21 / factor + 7 * near).max(4), t2))
+        };
+        let or_default = |value: i32, default: i32| if value > 0 { value } else { default };
+
+        let range = (max_value + 2 * near) / (2 * near + 1) + 1;
+        let bits = |value: i32| 32 - ((value - 1).max(1) as u32).leading_zeros();
+        let bpp = bits(max_value + 1).max(2);
+        Ok(Self {
+            max_value,
+            near,
+            t1: or_default(presets.t1, default_t1),
+            t2: or_default(presets.t2, default_t2),
+            t3: or_default(presets.t3, default_t3),
+            reset: or_default(presets.reset, 64),
+            range,
+            qbpp: bits(range),
+            limit: 2 * (bpp + bpp.max(8)),
+        })
+    }
+
+    fn quantize(&self, gradient: i32) -> i32 {
+        match gradient {
+            d if d <= -self.t3 => -4,
+            d if d <= -self.t2 => -3,
+            d if d <= -self.t1 => -2,
+            d if d < -self.near => -1,
+            d if d <= self.near => 0,
+            d if d < self.t1 => 1,
+            d if d < self.t2 => 2,
+            d if d < self.t3 => 3,
+            _ => 4,
+        }
+    }
+
+    /// Adds a dequantised error to a prediction, undoing the modulo
+    /// reduction of the encoder.
+    fn reconstruct(&self, predicted: i32, error: i32) -> i32 {
+        let step = 2 * self.near + 1;
+        let mut value = predicted + error * step;
+        if value < -self.near {
+            value += self.range * step;
+        } else if value > self.max_value + self.near {
+            value -= self.range * step;
+        }
+        value.clamp(0, self.max_value)
+    }
+
+    fn initial_a(&self) -> i32 {
+        ((self.range + 32) / 64).max(2)
+    }
+}
+
+/// Run length order for each run index (T.87 A.7.1.1).
+const J: [u32; 32] = [
+    0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 9, 10, 11, 12, 13,
+    14, 15,
+];
+
+#[derive(Clone, Copy)]
+struct Context {
+    a: i32,
+    b: i32,
+    c: i32,
+    n: i32,
+}
+
+impl Context {
+    fn golomb_k(&self) -> u32 {
+        let mut k = 0;
+        while (self.n << k) < self.a && k < 24 {
+            k += 1;
+        }
+        k
+    }
+
+    fn update(&mut self, error: i32, parameters: &Parameters) {
+        let mut a = self.a + error.abs();
+        let mut b = self.b + error * (2 * parameters.near + 1);
+        let mut n = self.n;
+        if n == parameters.reset {
+            a >>= 1;
+            b >>= 1;
+            n >>= 1;
+        }
+        n += 1;
+        self.a = a;
+        self.n = n;
+        if b + n <= 0 {
+            b += n;
+            if b <= -n {
+                b = -n + 1;
+            }
+            if self.c > -128 {
+                self.c -= 1;
+            }
+        } else if b > 0 {
+            b -= n;
+            if b > 0 {
+                b = 0;
+            }
+            if self.c < 127 {
+                self.c += 1;
+            }
+        }
+        self.b = b;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RunContext {
+    a: i32,
+    n: i32,
+    nn: i32,
+    interruption_type: i32,
+}
+
+impl RunContext {
+    fn golomb_k(&self) -> u32 {
+        let target = self.a + (self.n >> 1) * self.interruption_type;
+        let mut k = 0;
+        while (self.n << k) < target && k < 24 {
+            k += 1;
+        }
+        k
+    }
+
+    fn error(&self, mapped: i32, k: u32) -> i32 {
+        let map = mapped & 1 == 1;
+        let magnitude = (mapped + map as i32) / 2;
+        if (k != 0 || 2 * self.nn >= self.n) == map {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    fn update(&mut self, error: i32, mapped: i32, reset: i32) {
+        if error < 0 {
+            self.nn += 1;
+        }
+        self.a += (mapped + 1 - self.interruption_type) >> 1;
+        if self.n == reset {
+            self.a >>= 1;
+            self.n >>= 1;
+            self.nn >>= 1;
+        }
+        self.n += 1;
+    }
+}
+
+/// Decoding state of a scan, reset at every restart marker.
+struct ScanDecoder<'a> {
+    parameters: &'a Parameters,
+    contexts: Vec<Context>,
+    run_contexts: [RunContext; 2],
+}
+
+impl<'a> ScanDecoder<'a> {
+    fn new(parameters: &'a Parameters) -> Self {
+        let a = parameters.initial_a();
+        let run_context = |interruption_type| RunContext {
+            a,
+            n: 1,
+            nn: 0,
+            interruption_type,
+        };
+        Self {
+            parameters,
+            contexts: vec![
+                Context {
+                    a,
+                    b: 0,
+                    c: 0,
+                    n: 1
+                };
+                365
+            ],
+            run_contexts: [run_context(0), run_context(1)],
+        }
+    }
+
+    fn context_id(&self, d1: i32, d2: i32, d3: i32) -> i32 {
+        let p = self.parameters;
+        (p.quantize(d1) * 9 + p.quantize(d2)) * 9 + p.quantize(d3)
+    }
+
+    fn decode_regular(
+        &mut self,
+        bits: &mut BitReader,
+        context_id: i32,
+        predicted: i32,
+    ) -> Result<i32, String> {
+        let p = self.parameters;
+        let sign = if context_id < 0 { -1 } else { 1 };
+        let context = &mut self.contexts[context_id.unsigned_abs() as usize];
+        let k = context.golomb_k();
+        let predicted = (predicted + sign * context.c).clamp(0, p.max_value);
+
+        let mapped = bits.decode_value(k, p.limit, p.qbpp)?;
+        let mut error = if mapped & 1 == 0 {
+            mapped / 2
+        } else {
+            -(mapped + 1) / 2
+        };
+        if k == 0 && p.near == 0 && 2 * context.b + context.n - 1 < 0 {
+            error = -error - 1;
+        }
+        context.update(error, p);
+        Ok(p.reconstruct(predicted, sign * error))
+    }
+
+    /// Decodes the length of a run of `Ra` starting a line segment of
+    /// `remaining` samples.
+    fn decode_run_length(
+        &self,
+        bits: &mut BitReader,
+        remaining: usize,
+        run_index: &mut usize,
+    ) -> Result<usize, String> {
+        let mut length = 0;
+        while bits.read_bit()? {
+            let count = (1usize << J[*run_index]).min(remaining - length);
+            length += count;
+            if count == 1 << J[*run_index] {
+                *run_index = (*run_index + 1).min(31);
+            }
+            if length == remaining {
+                return Ok(length);
+            }
+        }
+        length += bits.read(J[*run_index])? as usize;
+        if length > remaining {
+            return Err("JPEG-LS run extends past the end of the line".to_string());
+        }
+        Ok(length)
+    }
+
+    /// Decodes the sample interrupting a run (T.87 A.7.2).
+    fn decode_interruption(
+        &mut self,
+        bits: &mut BitReader,
+        ra: i32,
+        rb: i32,
+        run_index: usize,
+        single_component: bool,
+    ) -> Result<i32, String> {
+        let p = self.parameters;
+        let same = single_component && (ra - rb).abs() <= p.near;
+        let context = &mut self.run_contexts[same as usize];
+        let k = context.golomb_k();
+        let mapped = bits.decode_value(k, p.limit - J[run_index] - 1, p.qbpp)?;
+        let error = context.error(mapped + context.interruption_type, k);
+        context.update(error, mapped, p.reset);
+        if same {
+            Ok(p.reconstruct(ra, error))
+        } else {
+            let sign = if rb - ra < 0 { -1 } else { 1 };
+            Ok(p.reconstruct(rb, error * sign))
+        }
+    }
+
+    /// Decodes one line of a single component. Both lines carry a border
+    /// sample on either side: index `x + 1` holds column `x`.
+    fn decode_line(
+        &mut self,
+        bits: &mut BitReader,
+        previous: &mut [i32],
+        current: &mut [i32],
+        run_index: &mut usize,
+    ) -> Result<(), String> {
+        let width = current.len() - 2;
+        previous[width + 1] = previous[width];
+        current[0] = previous[1];
+
+        let mut x = 1;
+        while x <= width {
+            let (ra, rb, rc, rd) = (
+                current[x - 1],
+                previous[x],
+                previous[x - 1],
+                previous[x + 1],
+            );
+            let context_id = self.context_id(rd - rb, rb - rc, rc - ra);
+            if context_id != 0 {
+                current[x] = self.decode_regular(bits, context_id, predict(ra, rb, rc))?;
+                x += 1;
+                continue;
+            }
+
+            let length = self.decode_run_length(bits, width + 1 - x, run_index)?;
+            current[x..x + length].fill(ra);
+            x += length;
+            if x <= width {
+                current[x] = self.decode_interruption(bits, ra, previous[x], *run_index, true)?;
+                *run_index = run_index.saturating_sub(1);
+                x += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes one line of `n` sample-interleaved components; pixel `x` of
+    /// component `c` is at `(x + 1) * n + c`.
+    fn decode_interleaved_line(
+        &mut self,
+        bits: &mut BitReader,
+        previous: &mut [i32],
+        current: &mut [i32],
+        n: usize,
+        run_index: &mut usize,
+    ) -> Result<(), String> {
+        let width = current.len() / n - 2;
+        for c in 0..n {
+            previous[(width + 1) * n + c] = previous[width * n + c];
+            current[c] = previous[n + c];
+        }
+
+        let mut x = 1;
+        while x <= width {
+            let mut context_ids = [0; 4];
+            for (c, context_id) in context_ids.iter_mut().enumerate().take(n) {
+                let (ra, rb) = (current[(x - 1) * n + c], previous[x * n + c]);
+                let (rc, rd) = (previous[(x - 1) * n + c], previous[(x + 1) * n + c]);
+                *context_id = self.context_id(rd - rb, rb - rc, rc - ra);
+            }
+            if context_ids[..n].iter().any(|&id| id != 0) {
+                for (c, &context_id) in context_ids.iter().enumerate().take(n) {
+                    let (ra, rb) = (current[(x - 1) * n + c], previous[x * n + c]);
+                    let rc = previous[(x - 1) * n + c];
+                    current[x * n + c] =
+                        self.decode_regular(bits, context_id, predict(ra, rb, rc))?;
+                }
+                x += 1;
+                continue;
+            }
+
+            let length = self.decode_run_length(bits, width + 1 - x, run_index)?;
+            for run in x..x + length {
+                for c in 0..n {
+                    current[run * n + c] = current[(x - 1) * n + c];
+                }
+            }
+            x += length;
+            if x <= width {
+                for c in 0..n {
+                    let (ra, rb) = (current[(x - 1) * n + c], previous[x * n + c]);
+                    current[x * n + c] =
+                        self.decode_interruption(bits, ra, rb, *run_index, false)?;
+                }
+                *run_index = run_index.saturating_sub(1);
+                x += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Median edge detector prediction (T.87 A.4.1).
+fn predict(ra: i32, rb: i32, rc: i32) -> i32 {
+    if rc >= ra.max(rb) {
+        ra.min(rb)
+    } else if rc <= ra.min(rb) {
+        ra.max(rb)
+    } else {
+        ra + rb - rc
+    }
+}
+
+fn decode_scan(
+    frame: &FrameHeader,
+    scan: &ScanHeader,
+    presets: &Presets,
+    restart_interval: usize,
+    segments: &[&[u8]],
+    samples: &mut [u16],
+) -> Result<(), String> {
+    let parameters = Parameters::new(frame.precision, scan.near, presets)?;
+    let (width, height) = (frame.width, frame.height);
+    let components = frame.components.len();
+    let n = scan.components.len();
+    let lines_per_interval = if restart_interval > 0 {
+        restart_interval
+    } else {
+        height
+    };
+
+    // Sample interleaving keeps one buffer for all components, line
+    // interleaving and single-component scans one per component.
+    let (buffers, stride) = if scan.interleave == 2 {
+        (1, (width + 2) * n)
+    } else {
+        (n, width + 2)
+    };
+    let mut previous = vec![vec![0i32; stride]; buffers];
+    let mut current = vec![vec![0i32; stride]; buffers];
+    let mut run_indices = vec![0usize; buffers];
+
+    let mut decoder = ScanDecoder::new(&parameters);
+    let mut bits = BitReader::new(segments.first().copied().unwrap_or_default());
+    for y in 0..height {
+        if y > 0 && y % lines_per_interval == 0 {
+            let segment = segments
+                .get(y / lines_per_interval)
+                .ok_or("JPEG-LS scan is missing a restart interval")?;
+            bits = BitReader::new(segment);
+            decoder = ScanDecoder::new(&parameters);
+            previous.iter_mut().for_each(|line| line.fill(0));
+            run_indices.fill(0);
+        }
+
+        for buffer in 0..buffers {
+            if scan.interleave == 2 {
+                decoder.decode_interleaved_line(
+                    &mut bits,
+                    &mut previous[buffer],
+                    &mut current[buffer],
+                    n,
+                    &mut run_indices[buffer],
+                )?;
+            } else {
+                decoder.decode_line(
+                    &mut bits,
+                    &mut previous[buffer],
+                    &mut current[buffer],
+                    &mut run_indices[buffer],
+                )?;
+            }
+        }
+
+        for (index, &component) in scan.components.iter().enumerate() {
+            for x in 0..width {
+                let value = if scan.interleave == 2 {
+                    current[0][(x + 1) * n + index]
+                } else {
+                    current[index][x + 1]
+                };
+                samples[(y * width + x) * components + component] =
+                    ((value as u32) << scan.point_transform) as u16;
+            }
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Ok(())
+}
+
+/// Reads entropy-coded bits, dropping the zero bit stuffed after each 0xFF.
+/// Past the end of the segment it yields zero bits.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    cache: u64,
+    bits: u32,
+    after_ff: bool,
+    overrun: usize,
+}
+
+/// Longest unary prefix accepted before the stream is considered corrupt.
+const MAX_UNARY_LENGTH: u32 = 64;
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            cache: 0,
+            bits: 0,
+            after_ff: false,
+            overrun: 0,
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), String> {
+        while self.bits <= 56 {
+            let Some(&byte) = self.data.get(self.position) else {
+                self.overrun += 1;
+                if self.overrun > 16 {
+                    return Err("JPEG-LS scan data is truncated".to_string());
+                }
+                self.bits += 8;
+                continue;
+            };
+            self.position += 1;
+            let (value, count) = if self.after_ff {
+                (byte & 0x7F, 7)
+            } else {
+                (byte, 8)
+            };
+            self.cache |= (value as u64) << (64 - self.bits - count);
+            self.bits += count;
+            self.after_ff = byte == 0xFF;
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, count: u32) -> Result<u32, String> {
+        if count == 0 {
+            return Ok(0);
+        }
+        if self.bits < count {
+            self.fill()?;
+        }
+        let value = (self.cache >> (64 - count)) as u32;
+        self.cache <<= count;
+        self.bits -= count;
+        Ok(value)
+    }
+
+    fn read_bit(&mut self) -> Result<bool, String> {
+        Ok(self.read(1)? == 1)
+    }
+
+    /// Counts the zero bits before the next one bit, consuming both.
+    fn read_unary(&mut self) -> Result<u32, String> {
+        let mut count = 0;
+        loop {
+            if self.bits == 0 {
+                self.fill()?;
+            }
+            let zeros = self.cache.leading_zeros().min(self.bits);
+            if zeros < self.bits {
+                self.cache <<= zeros + 1;
+                self.bits -= zeros + 1;
+                return Ok(count + zeros);
+            }
+            count += zeros;
+            self.cache = 0;
+            self.bits = 0;
+            if count > MAX_UNARY_LENGTH {
+                return Err("JPEG-LS scan data is corrupt".to_string());
+            }
+        }
+    }
+
+    /// Reads a limited-length Golomb code (T.87 A.5.3).
+    fn decode_value(&mut self, k: u32, limit: u32, qbpp: u32) -> Result<i32, String> {
+        let high = self.read_unary()?;
+        if high >= limit - (qbpp + 1) {
+            return Ok(self.read(qbpp)? as i32 + 1);
+        }
+        Ok(((high << k) | self.read(k)?) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference streams encoded with CharLS from the 32 × 20 images in the
+    // `.raw` files, which hold 8-bit samples or 16-bit little-endian ones;
+    // near-lossless references are CharLS's own decoding of the stream.
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/jpeg_ls/",
+                $name
+            ))
+        };
+    }
+
+    fn assert_decodes(stream: &[u8], expected: &[u8], components: usize, bits: u32) {
+        let image = decode(stream).unwrap();
+        assert_eq!((image.width, image.height), (32, 20));
+        assert_eq!(image.components, components);
+        let expected: Vec<u16> = if bits <= 8 {
+            expected.iter().map(|&byte| byte as u16).collect()
+        } else {
+            expected
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect()
+        };
+        assert_eq!(image.samples, expected);
+    }
+
+    #[test]
+    fn decodes_lossless_grayscale() {
+        assert_decodes(fixture!("gray8_lossless.jls"), fixture!("gray8.raw"), 1, 8);
+        assert_decodes(
+            fixture!("gray12_lossless.jls"),
+            fixture!("gray12.raw"),
+            1,
+            12,
+        );
+        assert_decodes(
+            fixture!("gray16_lossless.jls"),
+            fixture!("gray16.raw"),
+            1,
+            16,
+        );
+    }
+
+    #[test]
+    fn decodes_near_lossless_grayscale() {
+        assert_decodes(
+            fixture!("gray8_near3.jls"),
+            fixture!("gray8_near3.raw"),
+            1,
+            8,
+        );
+        // MAXVAL below 128 takes the low-bit-depth default thresholds.
+        assert_decodes(
+            fixture!("gray6_near2.jls"),
+            fixture!("gray6_near2.raw"),
+            1,
+            6,
+        );
+    }
+
+    #[test]
+    fn decodes_lossless_colour_in_every_interleave_mode() {
+        for stream in [
+            fixture!("rgb8_ilv0_lossless.jls").as_slice(),
+            fixture!("rgb8_ilv1_lossless.jls"),
+            fixture!("rgb8_ilv2_lossless.jls"),
+        ] {
+            assert_decodes(stream, fixture!("rgb8.raw"), 3, 8);
+        }
+    }
+
+    #[test]
+    fn decodes_near_lossless_colour_in_every_interleave_mode() {
+        assert_decodes(
+            fixture!("rgb8_ilv0_near2.jls"),
+            fixture!("rgb8_ilv0_near2.raw"),
+            3,
+            8,
+        );
+        assert_decodes(
+            fixture!("rgb8_ilv1_near2.jls"),
+            fixture!("rgb8_ilv1_near2.raw"),
+            3,
+            8,
+        );
+        assert_decodes(
+            fixture!("rgb8_ilv2_near2.jls"),
+            fixture!("rgb8_ilv2_near2.raw"),
+            3,
+            8,
+        );
+    }
+
+    #[test]
+    fn near_is_limited_by_half_of_max_value() {
+        let presets = Presets::default();
+        assert!(Parameters::new(16, 255, &presets).is_ok());
+        assert!(Parameters::new(16, 256, &presets).is_err());
+        assert!(Parameters::new(6, 31, &presets).is_ok());
+        assert!(Parameters::new(6, 32, &presets).is_err());
+    }
+
+    #[test]
+    fn low_bit_depth_thresholds_add_near_before_the_minimum() {
+        // MAXVAL 63: FACTOR 4, so 3 / FACTOR etc. fall below the minimums.
+        let parameters = Parameters::new(6, 2, &Presets::default()).unwrap();
+        assert_eq!((parameters.t1, parameters.t2, parameters.t3), (6, 11, 19));
+    }
+}
@@ -0,0 +1,47 @@
+//! Transfer syntax support beyond what dicom-rs provides out of the box.
+
+pub mod deflate;
+pub mod jpeg_ls;
+
+use dicom::encoding::TransferSyntaxIndex;
+use dicom::transfer_syntax::TransferSyntaxRegistry;
+
+/// Human-readable name of a transfer syntax UID, falling back to the UID.
+pub fn transfer_syntax_name(uid: &str) -> String {
+    let uid = uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+    match TransferSyntaxRegistry.get(uid) {
+        Some(ts) => ts.name().to_string(),
+        None => uid.to_string(),
+    }
+}
+
+/// Whether the pixel data of a transfer syntax can be decoded, if it is known.
+pub fn can_decode_pixel_data(uid: &str) -> Option<bool> {
+    let uid = uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+    TransferSyntaxRegistry
+        .get(uid)
+        .map(|ts| ts.can_decode_all())
+}
+
+/// Whether the pixel data decoder of a transfer syntax converts YBR samples to
+/// RGB. The JPEG and JPEG 2000 decoders do; RLE Lossless and JPEG-LS samples
+/// keep the colour space of the Photometric Interpretation.
+pub fn decodes_to_rgb(uid: &str) -> bool {
+    let uid = uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+    matches!(
+        uid,
+        // JPEG baseline, extended and lossless.
+        "1.2.840.10008.1.2.4.50"
+            | "1.2.840.10008.1.2.4.51"
+            | "1.2.840.10008.1.2.4.57"
+            | "1.2.840.10008.1.2.4.70"
+            // JPEG 2000 and HTJ2K.
+            | "1.2.840.10008.1.2.4.90"
+            | "1.2.840.10008.1.2.4.91"
+            | "1.2.840.10008.1.2.4.92"
+            | "1.2.840.10008.1.2.4.93"
+            | "1.2.840.10008.1.2.4.201"
+            | "1.2.840.10008.1.2.4.202"
+            | "1.2.840.10008.1.2.4.203"
+    )
+}
@@ -0,0 +1,204 @@
+use crate::message::Message;
+use crate::model::{Histogram, Window};
+use iced::mouse;
+use iced::widget::canvas::{self, event, Event, Geometry, Path, Stroke, Text};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+
+const HANDLE_GRAB_DISTANCE: f32 = 6.0;
+
+/// Histogram bars with the current VOI window drawn as draggable handles.
+pub struct HistogramChart<'a> {
+    histogram: &'a Histogram,
+    window: Option<Window>,
+    log_scale: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DragHandle {
+    #[default]
+    None,
+    Lower,
+    Upper,
+    /// Moving the whole window, holding the offset from its centre.
+    Center(f64),
+}
+
+impl<'a> HistogramChart<'a> {
+    pub fn new(histogram: &'a Histogram, window: Option<Window>, log_scale: bool) -> Self {
+        Self {
+            histogram,
+            window,
+            log_scale,
+        }
+    }
+
+    fn value_at(&self, x: f32, width: f32) -> f64 {
+        let fraction = (x / width.max(1.0)).clamp(0.0, 1.0) as f64;
+        self.histogram.min + fraction * (self.histogram.max - self.histogram.min)
+    }
+
+    fn x_of(&self, value: f64, width: f32) -> f32 {
+        let range = (self.histogram.max - self.histogram.min).max(f64::EPSILON);
+        ((value - self.histogram.min) / range) as f32 * width
+    }
+}
+
+impl canvas::Program<Message> for HistogramChart<'_> {
+    type State = DragHandle;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Some(window) = self.window else {
+            return (event::Status::Ignored, None);
+        };
+        let Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+
+        match event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                let lower = self.x_of(window.lower(), bounds.width);
+                let upper = self.x_of(window.upper(), bounds.width);
+                *state = if (position.x - lower).abs() <= HANDLE_GRAB_DISTANCE {
+                    DragHandle::Lower
+                } else if (position.x - upper).abs() <= HANDLE_GRAB_DISTANCE {
+                    DragHandle::Upper
+                } else if position.x > lower && position.x < upper {
+                    DragHandle::Center(self.value_at(position.x, bounds.width) - window.center)
+                } else {
+                    return (event::Status::Ignored, None);
+                };
+                (event::Status::Captured, None)
+            }
+            mouse::Event::CursorMoved { .. } if *state != DragHandle::None => {
+                let Some(position) = cursor.position_from(bounds.position()) else {
+                    return (event::Status::Ignored, None);
+                };
+                let value = self.value_at(position.x, bounds.width);
+                let updated = match *state {
+                    DragHandle::Lower => {
+                        Window::from_bounds(value.min(window.upper() - 1.0), window.upper())
+                    }
+                    DragHandle::Upper => {
+                        Window::from_bounds(window.lower(), value.max(window.lower() + 1.0))
+                    }
+                    DragHandle::Center(offset) => Window {
+                        center: value - offset,
+                        width: window.width,
+                    },
+                    DragHandle::None => window,
+                };
+                (event::Status::Captured, Some(Message::SetWindow(updated)))
+            }
+            mouse::Event::ButtonReleased(mouse::Button::Left) if *state != DragHandle::None => {
+                *state = DragHandle::None;
+                (event::Status::Captured, None)
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let palette = theme.extended_palette();
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(
+            Point::ORIGIN,
+            bounds.size(),
+            palette.background.weak.color.scale_alpha(0.3),
+        );
+
+        let scale = |count: u32| {
+            if self.log_scale {
+                (count as f32).ln_1p()
+            } else {
+                count as f32
+            }
+        };
+        let tallest = self
+            .histogram
+            .bins
+            .iter()
+            .copied()
+            .map(scale)
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        let bar_width = bounds.width / self.histogram.bins.len() as f32;
+        for (index, &count) in self.histogram.bins.iter().enumerate() {
+            let height = scale(count) / tallest * bounds.height;
+            frame.fill_rectangle(
+                Point::new(index as f32 * bar_width, bounds.height - height),
+                Size::new(bar_width.max(1.0), height),
+                palette.primary.base.color,
+            );
+        }
+
+        if let Some(window) = self.window {
+            let lower = self.x_of(window.lower(), bounds.width);
+            let upper = self.x_of(window.upper(), bounds.width);
+            frame.fill_rectangle(
+                Point::new(lower, 0.0),
+                Size::new((upper - lower).max(0.0), bounds.height),
+                Color::from_rgba(1.0, 0.85, 0.2, 0.12),
+            );
+            let handle = Stroke::default()
+                .with_color(Color::from_rgb(1.0, 0.85, 0.2))
+                .with_width(2.0);
+            for (x, value) in [(lower, window.lower()), (upper, window.upper())] {
+                frame.stroke(
+                    &Path::line(Point::new(x, 0.0), Point::new(x, bounds.height)),
+                    handle,
+                );
+                frame.fill_text(Text {
+                    content: format!("{value:.0}"),
+                    position: Point::new(x + 3.0, 2.0),
+                    color: palette.background.base.text,
+                    size: 11.0.into(),
+                    ..Text::default()
+                });
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        match (state, self.window, cursor.position_in(bounds)) {
+            (DragHandle::Lower | DragHandle::Upper, ..) => mouse::Interaction::ResizingHorizontally,
+            (DragHandle::Center(_), ..) => mouse::Interaction::Grabbing,
+            (DragHandle::None, Some(window), Some(position)) => {
+                let lower = self.x_of(window.lower(), bounds.width);
+                let upper = self.x_of(window.upper(), bounds.width);
+                if (position.x - lower).abs() <= HANDLE_GRAB_DISTANCE
+                    || (position.x - upper).abs() <= HANDLE_GRAB_DISTANCE
+                {
+                    mouse::Interaction::ResizingHorizontally
+                } else if position.x > lower && position.x < upper {
+                    mouse::Interaction::Grab
+                } else {
+                    mouse::Interaction::default()
+                }
+            }
+            _ => mouse::Interaction::default(),
+        }
+    }
+}
@@ -0,0 +1,639 @@
+use crate::message::Message;
+use crate::model::{AnnotationContext, Corner, CornerTemplates, EdgeLabels, Shape, ViewTransform};
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::widget::canvas::path::arc::Elliptical;
+use iced::widget::canvas::{self, event, Event, Geometry, Path, Stroke, Text};
+use iced::widget::image::{FilterMethod, Handle};
+use iced::{Color, Point, Radians, Rectangle, Renderer, Size, Theme, Vector};
+
+const SHAPE_COLOR: Color = Color::from_rgb(1.0, 0.85, 0.2);
+const DRAFT_COLOR: Color = Color::from_rgb(0.4, 0.85, 1.0);
+pub(crate) const ANNOTATION_COLOR: Color = Color::from_rgb(0.95, 0.95, 0.9);
+pub(crate) const ANNOTATION_SIZE: f32 = 12.0;
+pub(crate) const ANNOTATION_LINE_HEIGHT: f32 = 15.0;
+pub(crate) const ANNOTATION_MARGIN: f32 = 6.0;
+pub(crate) const ORIENTATION_SIZE: f32 = 15.0;
+/// Longest the scale bar may get, as a fraction of the image width on screen.
+pub(crate) const SCALE_BAR_MAX_FRACTION: f32 = 0.25;
+
+/// A shape drawn on top of the image, in image pixel coordinates.
+#[derive(Debug, Clone)]
+pub struct ShapeOverlay {
+    pub shape: Shape,
+    pub label: Option<String>,
+}
+
+/// An image drawn over the frame, e.g. an overlay plane, placed in image
+/// pixel coordinates.
+#[derive(Debug, Clone)]
+pub struct ImageLayer {
+    pub handle: Handle,
+    pub bounds: Rectangle,
+}
+
+/// An outline drawn over the frame in its own colour, e.g. an RT contour,
+/// in image pixel coordinates.
+#[derive(Debug, Clone)]
+pub struct ContourOverlay {
+    pub points: Vec<Point>,
+    pub closed: bool,
+    pub color: Color,
+}
+
+/// Text drawn over the frame at an image pixel position.
+#[derive(Debug, Clone)]
+pub struct TextOverlay {
+    pub position: Point,
+    pub text: String,
+    pub color: Color,
+}
+
+/// Canvas that draws a frame scaled to fit its bounds and reports the cursor
+/// position in image pixel coordinates (x = column, y = row).
+pub struct ImageCanvas<'a> {
+    handle: Handle,
+    image_size: Size,
+    layers: Vec<ImageLayer>,
+    contours: Vec<ContourOverlay>,
+    labels: Vec<TextOverlay>,
+    shapes: Vec<ShapeOverlay>,
+    draft: Option<Shape>,
+    interactive: bool,
+    transform: ViewTransform,
+    annotations: Option<(&'a CornerTemplates, AnnotationContext<'a>)>,
+    edge_labels: Option<EdgeLabels>,
+    /// Row and column spacing in millimetres, for the scale bar.
+    spacing: Option<[f64; 2]>,
+    /// Region of the image to fit to the canvas, in image pixel coordinates.
+    displayed_area: Option<Rectangle>,
+}
+
+#[derive(Debug, Default)]
+pub struct CanvasState {
+    hovered: Option<Point>,
+    pressed: bool,
+}
+
+impl<'a> ImageCanvas<'a> {
+    pub fn new(handle: Handle, columns: u32, rows: u32) -> Self {
+        Self {
+            handle,
+            image_size: Size::new(columns as f32, rows as f32),
+            layers: Vec::new(),
+            contours: Vec::new(),
+            labels: Vec::new(),
+            shapes: Vec::new(),
+            draft: None,
+            interactive: false,
+            transform: ViewTransform::default(),
+            annotations: None,
+            edge_labels: None,
+            spacing: None,
+            displayed_area: None,
+        }
+    }
+
+    pub fn layers(mut self, layers: Vec<ImageLayer>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn contours(mut self, contours: Vec<ContourOverlay>) -> Self {
+        self.contours = contours;
+        self
+    }
+
+    pub fn labels(mut self, labels: Vec<TextOverlay>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Shows only `area` of the image, scaled to fit; the whole image when `None`.
+    pub fn displayed_area(mut self, area: Option<Rectangle>) -> Self {
+        self.displayed_area = area;
+        self
+    }
+
+    pub fn shapes(mut self, shapes: Vec<ShapeOverlay>) -> Self {
+        self.shapes = shapes;
+        self
+    }
+
+    pub fn draft(mut self, draft: Option<Shape>) -> Self {
+        self.draft = draft;
+        self
+    }
+
+    /// Emit press, drag and release messages for drawing measurements.
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Display orientation; `handle` and the layer handles must already be
+    /// oriented, while shapes and reported points stay in image coordinates.
+    pub fn transform(mut self, transform: ViewTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn edge_labels(mut self, labels: Option<EdgeLabels>) -> Self {
+        self.edge_labels = labels;
+        self
+    }
+
+    pub fn scale_bar(mut self, spacing: Option<[f64; 2]>) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Corner text resolved against `context`; its zoom is replaced by the
+    /// display scale at draw time.
+    pub fn annotations(
+        mut self,
+        templates: &'a CornerTemplates,
+        context: AnnotationContext<'a>,
+    ) -> Self {
+        self.annotations = Some((templates, context));
+        self
+    }
+
+    /// The displayed area in display coordinates, after the flip and rotation.
+    fn display_area(&self) -> Rectangle {
+        let area = self
+            .displayed_area
+            .unwrap_or(Rectangle::new(Point::ORIGIN, self.image_size));
+        let a = self.transform.to_display(area.position(), self.image_size);
+        let b = self.transform.to_display(
+            Point::new(area.x + area.width, area.y + area.height),
+            self.image_size,
+        );
+        Rectangle::new(
+            Point::new(a.x.min(b.x), a.y.min(b.y)),
+            Size::new((a.x - b.x).abs(), (a.y - b.y).abs()),
+        )
+    }
+
+    /// Where the displayed area is drawn on a canvas of `bounds` size.
+    fn image_bounds(&self, bounds: Size) -> Rectangle {
+        fit_rect(bounds, self.display_area().size())
+    }
+
+    fn image_transform(&self, bounds: Size) -> ImageTransform {
+        let target = self.image_bounds(bounds);
+        let area = self.display_area();
+        let scale = target.width / area.width.max(f32::EPSILON);
+        ImageTransform {
+            origin: Point::new(target.x - area.x * scale, target.y - area.y * scale),
+            scale,
+            image_size: self.image_size,
+            orientation: self.transform,
+        }
+    }
+
+    fn to_image_point(&self, bounds: Size, position: Point) -> Option<Point> {
+        let target = self.image_bounds(bounds);
+        if !target.contains(position) || target.width <= 0.0 {
+            return None;
+        }
+        let transform = self.image_transform(bounds);
+        let display = self.transform.display_size(self.image_size);
+        let point = transform.to_display(position);
+        let inside =
+            (0.0..=display.width).contains(&point.x) && (0.0..=display.height).contains(&point.y);
+        inside.then(|| self.transform.to_image(point, self.image_size))
+    }
+
+    fn to_image_point_clamped(&self, bounds: Size, position: Point) -> Point {
+        let display = self.transform.display_size(self.image_size);
+        let point = self.image_transform(bounds).to_display(position);
+        let point = Point::new(
+            point.x.clamp(0.0, display.width),
+            point.y.clamp(0.0, display.height),
+        );
+        self.transform.to_image(point, self.image_size)
+    }
+}
+
+impl canvas::Program<Message> for ImageCanvas<'_> {
+    type State = CanvasState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+        let position = cursor.position_in(bounds);
+
+        match event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) if self.interactive => {
+                match position.and_then(|position| self.to_image_point(bounds.size(), position)) {
+                    Some(point) => {
+                        state.pressed = true;
+                        (event::Status::Captured, Some(Message::ImagePressed(point)))
+                    }
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            mouse::Event::ButtonReleased(mouse::Button::Left) if state.pressed => {
+                state.pressed = false;
+                let point = cursor
+                    .position_from(bounds.position())
+                    .map(|position| self.to_image_point_clamped(bounds.size(), position));
+                (event::Status::Captured, point.map(Message::ImageReleased))
+            }
+            mouse::Event::CursorMoved { .. } if state.pressed => {
+                let point = cursor
+                    .position_from(bounds.position())
+                    .map(|position| self.to_image_point_clamped(bounds.size(), position));
+                state.hovered = point;
+                (event::Status::Captured, point.map(Message::ImageDragged))
+            }
+            mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft => {
+                let hovered =
+                    position.and_then(|position| self.to_image_point(bounds.size(), position));
+                if hovered == state.hovered {
+                    return (event::Status::Ignored, None);
+                }
+                state.hovered = hovered;
+                (event::Status::Ignored, Some(Message::ImageHovered(hovered)))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let target = self.image_bounds(bounds.size());
+        let transform = self.image_transform(bounds.size());
+        let display = self.transform.display_size(self.image_size);
+        frame.with_clip(target, |frame| {
+            frame.draw_image(
+                Rectangle::new(
+                    Point::new(transform.origin.x - target.x, transform.origin.y - target.y),
+                    Size::new(
+                        display.width * transform.scale,
+                        display.height * transform.scale,
+                    ),
+                ),
+                &self.handle,
+            );
+        });
+
+        for layer in &self.layers {
+            let a = transform.to_canvas(layer.bounds.position());
+            let b = transform.to_canvas(Point::new(
+                layer.bounds.x + layer.bounds.width,
+                layer.bounds.y + layer.bounds.height,
+            ));
+            frame.with_clip(target, |frame| {
+                frame.draw_image(
+                    Rectangle::new(
+                        Point::new(a.x.min(b.x) - target.x, a.y.min(b.y) - target.y),
+                        Size::new((b.x - a.x).abs(), (b.y - a.y).abs()),
+                    ),
+                    canvas::Image::new(&layer.handle).filter_method(FilterMethod::Nearest),
+                );
+            });
+        }
+        for contour in &self.contours {
+            let points = contour
+                .points
+                .iter()
+                .map(|point| transform.to_canvas(*point))
+                .collect::<Vec<_>>();
+            let path = match points.as_slice() {
+                [point] => Path::circle(*point, 2.0),
+                points => polyline(points, contour.closed),
+            };
+            frame.stroke(
+                &path,
+                Stroke::default().with_color(contour.color).with_width(1.5),
+            );
+        }
+        for label in &self.labels {
+            frame.fill_text(Text {
+                content: label.text.clone(),
+                position: transform.to_canvas(label.position),
+                color: label.color,
+                size: 13.0.into(),
+                ..Text::default()
+            });
+        }
+        for overlay in &self.shapes {
+            draw_shape(&mut frame, &transform, &overlay.shape, SHAPE_COLOR);
+            if let Some(label) = &overlay.label {
+                let anchor = transform.to_canvas(label_anchor(&overlay.shape));
+                frame.fill_text(Text {
+                    content: label.clone(),
+                    position: anchor + Vector::new(6.0, -18.0),
+                    color: SHAPE_COLOR,
+                    size: 13.0.into(),
+                    ..Text::default()
+                });
+            }
+        }
+        if let Some(draft) = &self.draft {
+            draw_shape(&mut frame, &transform, draft, DRAFT_COLOR);
+        }
+        if let Some(labels) = &self.edge_labels {
+            draw_edge_labels(&mut frame, bounds.size(), labels);
+        }
+        if let Some(spacing) = self.spacing {
+            let column_spacing = if self.transform.swaps_axes() {
+                spacing[0]
+            } else {
+                spacing[1]
+            };
+            draw_scale_bar(
+                &mut frame,
+                bounds.size(),
+                target,
+                column_spacing / transform.scale as f64,
+            );
+        }
+        if let Some((templates, context)) = &self.annotations {
+            let context = AnnotationContext {
+                zoom: transform.scale,
+                ..context.clone()
+            };
+            for corner in Corner::ALL {
+                draw_corner_text(
+                    &mut frame,
+                    bounds.size(),
+                    corner,
+                    &templates.render(corner, &context),
+                );
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        _bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.hovered.is_some() || state.pressed {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+/// Maps image pixel coordinates onto the fitted canvas rectangle.
+struct ImageTransform {
+    origin: Point,
+    scale: f32,
+    image_size: Size,
+    orientation: ViewTransform,
+}
+
+impl ImageTransform {
+    /// Maps a canvas position into display coordinates of the whole image.
+    fn to_display(&self, position: Point) -> Point {
+        Point::new(
+            (position.x - self.origin.x) / self.scale.max(f32::EPSILON),
+            (position.y - self.origin.y) / self.scale.max(f32::EPSILON),
+        )
+    }
+
+    fn to_canvas(&self, point: Point) -> Point {
+        let point = self.orientation.to_display(point, self.image_size);
+        Point::new(
+            self.origin.x + point.x * self.scale,
+            self.origin.y + point.y * self.scale,
+        )
+    }
+}
+
+fn draw_shape(frame: &mut canvas::Frame, transform: &ImageTransform, shape: &Shape, color: Color) {
+    let map = |point: &Point| transform.to_canvas(*point);
+    let path = match shape {
+        Shape::Line(a, b) => Path::line(map(a), map(b)),
+        Shape::Angle { start, vertex, end } => {
+            polyline(&[map(start), map(vertex), map(end)], false)
+        }
+        Shape::Cobb([a, b, c, d]) => Path::new(|builder| {
+            builder.move_to(map(a));
+            builder.line_to(map(b));
+            builder.move_to(map(c));
+            builder.line_to(map(d));
+        }),
+        Shape::Rectangle(a, b) => {
+            let (a, b) = (map(a), map(b));
+            Path::rectangle(
+                Point::new(a.x.min(b.x), a.y.min(b.y)),
+                Size::new((a.x - b.x).abs(), (a.y - b.y).abs()),
+            )
+        }
+        Shape::Ellipse(a, b) => {
+            let (a, b) = (map(a), map(b));
+            Path::new(|builder| {
+                builder.ellipse(Elliptical {
+                    center: Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0),
+                    radii: Vector::new((a.x - b.x).abs() / 2.0, (a.y - b.y).abs() / 2.0),
+                    rotation: Radians(0.0),
+                    start_angle: Radians(0.0),
+                    end_angle: Radians(std::f32::consts::TAU),
+                });
+            })
+        }
+        Shape::Freehand(points) => polyline(&points.iter().map(map).collect::<Vec<_>>(), true),
+    };
+
+    frame.stroke(&path, Stroke::default().with_color(color).with_width(1.5));
+}
+
+/// Draws lines stacked away from `corner` of the canvas.
+fn draw_corner_text(frame: &mut canvas::Frame, size: Size, corner: Corner, lines: &[String]) {
+    let (x, horizontal) = match corner {
+        Corner::TopLeft | Corner::BottomLeft => (ANNOTATION_MARGIN, Horizontal::Left),
+        Corner::TopRight | Corner::BottomRight => {
+            (size.width - ANNOTATION_MARGIN, Horizontal::Right)
+        }
+    };
+    let top = match corner {
+        Corner::TopLeft | Corner::TopRight => ANNOTATION_MARGIN,
+        Corner::BottomLeft | Corner::BottomRight => {
+            size.height - ANNOTATION_MARGIN - lines.len() as f32 * ANNOTATION_LINE_HEIGHT
+        }
+    };
+
+    for (index, line) in lines.iter().enumerate() {
+        let position = Point::new(x, top + index as f32 * ANNOTATION_LINE_HEIGHT);
+        draw_label(
+            frame,
+            line,
+            position,
+            ANNOTATION_SIZE,
+            (horizontal, Vertical::Top),
+        );
+    }
+}
+
+/// Draws orientation letters at the middle of each canvas edge.
+pub(super) fn draw_edge_labels(frame: &mut canvas::Frame, size: Size, labels: &EdgeLabels) {
+    let edges = [
+        (
+            &labels.left,
+            Point::new(ANNOTATION_MARGIN, size.height / 2.0),
+            (Horizontal::Left, Vertical::Center),
+        ),
+        (
+            &labels.right,
+            Point::new(size.width - ANNOTATION_MARGIN, size.height / 2.0),
+            (Horizontal::Right, Vertical::Center),
+        ),
+        (
+            &labels.top,
+            Point::new(size.width / 2.0, ANNOTATION_MARGIN),
+            (Horizontal::Center, Vertical::Top),
+        ),
+        (
+            &labels.bottom,
+            Point::new(size.width / 2.0, size.height - ANNOTATION_MARGIN),
+            (Horizontal::Center, Vertical::Bottom),
+        ),
+    ];
+    for (label, position, alignment) in edges {
+        draw_label(frame, label, position, ORIENTATION_SIZE, alignment);
+    }
+}
+
+/// Draws a horizontal bar of a round length in millimetres above the bottom
+/// orientation label.
+fn draw_scale_bar(frame: &mut canvas::Frame, size: Size, target: Rectangle, mm_per_point: f64) {
+    if mm_per_point <= 0.0 || !mm_per_point.is_finite() {
+        return;
+    }
+    let max_length = (target.width * SCALE_BAR_MAX_FRACTION) as f64 * mm_per_point;
+    let Some(length) = round_length(max_length) else {
+        return;
+    };
+    let width = (length / mm_per_point) as f32;
+    let y = size.height - ANNOTATION_MARGIN - ORIENTATION_SIZE - 10.0;
+    let left = (size.width - width) / 2.0;
+    let right = left + width;
+    let tick = 4.0;
+    let path = Path::new(|builder| {
+        builder.move_to(Point::new(left, y - tick));
+        builder.line_to(Point::new(left, y));
+        builder.line_to(Point::new(right, y));
+        builder.line_to(Point::new(right, y - tick));
+    });
+    frame.stroke(
+        &path,
+        Stroke::default()
+            .with_color(Color::from_rgba(0.0, 0.0, 0.0, 0.8))
+            .with_width(3.0),
+    );
+    frame.stroke(
+        &path,
+        Stroke::default()
+            .with_color(ANNOTATION_COLOR)
+            .with_width(1.5),
+    );
+    draw_label(
+        frame,
+        &format!("{length} mm"),
+        Point::new(size.width / 2.0, y - tick - 2.0),
+        ANNOTATION_SIZE,
+        (Horizontal::Center, Vertical::Bottom),
+    );
+}
+
+/// Largest length of the form 1, 2 or 5 × 10ⁿ mm that fits in `max`.
+pub(crate) fn round_length(max: f64) -> Option<f64> {
+    if max <= 0.0 {
+        return None;
+    }
+    let magnitude = 10f64.powf(max.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|length| *length <= max)
+}
+
+/// Draws text with a dark shadow so it stays legible over bright pixels.
+pub(super) fn draw_label(
+    frame: &mut canvas::Frame,
+    content: &str,
+    position: Point,
+    size: f32,
+    (horizontal_alignment, vertical_alignment): (Horizontal, Vertical),
+) {
+    if content.is_empty() {
+        return;
+    }
+    let text = Text {
+        content: content.to_string(),
+        position,
+        color: ANNOTATION_COLOR,
+        size: size.into(),
+        horizontal_alignment,
+        vertical_alignment,
+        ..Text::default()
+    };
+    frame.fill_text(Text {
+        position: position + Vector::new(1.0, 1.0),
+        color: Color::from_rgba(0.0, 0.0, 0.0, 0.8),
+        ..text.clone()
+    });
+    frame.fill_text(text);
+}
+
+fn polyline(points: &[Point], closed: bool) -> Path {
+    Path::new(|builder| {
+        let mut points = points.iter();
+        if let Some(first) = points.next() {
+            builder.move_to(*first);
+        }
+        for point in points {
+            builder.line_to(*point);
+        }
+        if closed {
+            builder.close();
+        }
+    })
+}
+
+fn label_anchor(shape: &Shape) -> Point {
+    match shape {
+        Shape::Line(a, _) | Shape::Rectangle(a, _) | Shape::Ellipse(a, _) => *a,
+        Shape::Angle { vertex, .. } => *vertex,
+        Shape::Cobb([a, ..]) => *a,
+        Shape::Freehand(points) => points.first().copied().unwrap_or(Point::ORIGIN),
+    }
+}
+
+pub(super) fn fit_rect(bounds: Size, image: Size) -> Rectangle {
+    if image.width <= 0.0 || image.height <= 0.0 {
+        return Rectangle::new(Point::ORIGIN, Size::ZERO);
+    }
+    let scale = (bounds.width / image.width).min(bounds.height / image.height);
+    let size = Size::new(image.width * scale, image.height * scale);
+    Rectangle::new(
+        Point::new(
+            (bounds.width - size.width) / 2.0,
+            (bounds.height - size.height) / 2.0,
+        ),
+        size,
+    )
+}
@@ -1 +1,5 @@
+pub mod histogram_chart;
+pub mod image_canvas;
+pub mod mpr_canvas;
 pub mod segmented_toggle;
+pub mod waveform_chart;
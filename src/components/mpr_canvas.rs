@@ -0,0 +1,196 @@
+use super::image_canvas::{
+    draw_edge_labels, draw_label, fit_rect, ANNOTATION_MARGIN, ANNOTATION_SIZE,
+};
+use crate::message::Message;
+use crate::model::{EdgeLabels, MprAxis};
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::widget::canvas::{self, event, Event, Geometry, Path, Stroke};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme, Vector};
+
+/// A crosshair line through the centre, in display direction, with the colour
+/// of the plane it belongs to.
+#[derive(Debug, Clone)]
+pub struct CrosshairLine {
+    pub direction: Vector,
+    pub color: Color,
+}
+
+/// One plane of the MPR viewer. Clicking or dragging moves the crosshair and
+/// the mouse wheel pages along the plane normal.
+pub struct MprCanvas {
+    axis: MprAxis,
+    handle: Handle,
+    image_size: Size,
+    crosshair: Point,
+    lines: Vec<CrosshairLine>,
+    edge_labels: Option<EdgeLabels>,
+    title: String,
+    color: Color,
+}
+
+#[derive(Debug, Default)]
+pub struct MprCanvasState {
+    pressed: bool,
+}
+
+impl MprCanvas {
+    pub fn new(axis: MprAxis, handle: Handle, width: u32, height: u32) -> Self {
+        Self {
+            axis,
+            handle,
+            image_size: Size::new(width as f32, height as f32),
+            crosshair: Point::ORIGIN,
+            lines: Vec::new(),
+            edge_labels: None,
+            title: String::new(),
+            color: Color::WHITE,
+        }
+    }
+
+    /// Crosshair centre in image coordinates and the lines through it.
+    pub fn crosshair(mut self, center: Point, lines: Vec<CrosshairLine>) -> Self {
+        self.crosshair = center;
+        self.lines = lines;
+        self
+    }
+
+    pub fn edge_labels(mut self, labels: Option<EdgeLabels>) -> Self {
+        self.edge_labels = labels;
+        self
+    }
+
+    pub fn title(mut self, title: String, color: Color) -> Self {
+        self.title = title;
+        self.color = color;
+        self
+    }
+
+    fn to_image_point(&self, bounds: Size, position: Point) -> Point {
+        let target = fit_rect(bounds, self.image_size);
+        let scale = self.image_size.width / target.width.max(f32::EPSILON);
+        Point::new(
+            ((position.x - target.x) * scale).clamp(0.0, self.image_size.width),
+            ((position.y - target.y) * scale).clamp(0.0, self.image_size.height),
+        )
+    }
+}
+
+impl canvas::Program<Message> for MprCanvas {
+    type State = MprCanvasState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+
+        match event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                state.pressed = true;
+                let point = self.to_image_point(bounds.size(), position);
+                (
+                    event::Status::Captured,
+                    Some(Message::MoveMprCrosshair(self.axis, point)),
+                )
+            }
+            mouse::Event::CursorMoved { .. } if state.pressed => {
+                let point = cursor
+                    .position_from(bounds.position())
+                    .map(|position| self.to_image_point(bounds.size(), position));
+                (
+                    event::Status::Captured,
+                    point.map(|point| Message::MoveMprCrosshair(self.axis, point)),
+                )
+            }
+            mouse::Event::ButtonReleased(mouse::Button::Left) if state.pressed => {
+                state.pressed = false;
+                (event::Status::Captured, None)
+            }
+            mouse::Event::WheelScrolled { delta } if cursor.is_over(bounds) => {
+                let steps = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 20.0,
+                };
+                (
+                    event::Status::Captured,
+                    Some(Message::ScrollMpr(self.axis, steps)),
+                )
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let target = fit_rect(bounds.size(), self.image_size);
+        frame.draw_image(target, &self.handle);
+
+        let scale = target.width / self.image_size.width.max(f32::EPSILON);
+        let center = Point::new(
+            target.x + self.crosshair.x * scale,
+            target.y + self.crosshair.y * scale,
+        );
+        let reach = target.width + target.height;
+        frame.with_clip(target, |frame| {
+            let offset = Vector::new(target.x, target.y);
+            for line in &self.lines {
+                let length = line.direction.x.hypot(line.direction.y);
+                if length <= f32::EPSILON {
+                    continue;
+                }
+                let step = line.direction * (reach / length);
+                frame.stroke(
+                    &Path::line(center - step - offset, center + step - offset),
+                    Stroke::default().with_color(line.color).with_width(1.0),
+                );
+            }
+        });
+
+        frame.stroke(
+            &Path::rectangle(Point::ORIGIN, bounds.size()),
+            Stroke::default().with_color(self.color).with_width(2.0),
+        );
+        if let Some(labels) = &self.edge_labels {
+            draw_edge_labels(&mut frame, bounds.size(), labels);
+        }
+        draw_label(
+            &mut frame,
+            &self.title,
+            Point::new(ANNOTATION_MARGIN, ANNOTATION_MARGIN),
+            ANNOTATION_SIZE,
+            (Horizontal::Left, Vertical::Top),
+        );
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.pressed || cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
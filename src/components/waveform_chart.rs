@@ -0,0 +1,217 @@
+use crate::message::Message;
+use crate::model::{MultiplexGroup, PaperSpeed, WaveformChannel, WaveformGain};
+use iced::mouse;
+use iced::widget::canvas::{self, Geometry, Path, Stroke, Text};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+
+/// Logical pixels per millimetre of ECG paper, at the 96 dpi reference
+/// resolution that logical pixels are defined by.
+pub const PIXELS_PER_MM: f32 = 96.0 / 25.4;
+/// Height of each channel's lane.
+const LANE_MM: f32 = 30.0;
+/// Room at the left for the channel label and the 1 mV calibration pulse.
+const MARGIN_MM: f32 = 15.0;
+const MINOR_GRID: Color = Color::from_rgba(0.9, 0.3, 0.3, 0.15);
+const MAJOR_GRID: Color = Color::from_rgba(0.9, 0.3, 0.3, 0.4);
+const TRACE: Color = Color::from_rgb(0.55, 1.0, 0.6);
+
+/// The channels of a multiplex group on an ECG grid: 1 mm minor and 5 mm
+/// major squares, voltage channels at the chosen mm/mV with a 1 mV
+/// calibration pulse, other channels fitted to their lane.
+pub struct WaveformChart<'a> {
+    group: &'a MultiplexGroup,
+    speed: PaperSpeed,
+    gain: WaveformGain,
+}
+
+impl<'a> WaveformChart<'a> {
+    pub fn new(group: &'a MultiplexGroup, speed: PaperSpeed, gain: WaveformGain) -> Self {
+        Self { group, speed, gain }
+    }
+
+    /// Size the chart needs to show the whole recording at its scale.
+    pub fn size(group: &MultiplexGroup, speed: PaperSpeed) -> Size {
+        let width = MARGIN_MM + group.duration() as f32 * speed.millimetres_per_second();
+        let height = LANE_MM * group.channels.len().max(1) as f32;
+        Size::new(width * PIXELS_PER_MM, height * PIXELS_PER_MM)
+    }
+
+    fn draw_grid(&self, frame: &mut canvas::Frame, size: Size) {
+        let millimetres = |pixels: f32| (pixels / PIXELS_PER_MM).ceil() as usize;
+        for step in 0..=millimetres(size.width) {
+            let x = step as f32 * PIXELS_PER_MM;
+            let color = if step % 5 == 0 {
+                MAJOR_GRID
+            } else {
+                MINOR_GRID
+            };
+            frame.stroke(
+                &Path::line(Point::new(x, 0.0), Point::new(x, size.height)),
+                Stroke::default().with_color(color).with_width(1.0),
+            );
+        }
+        for step in 0..=millimetres(size.height) {
+            let y = step as f32 * PIXELS_PER_MM;
+            let color = if step % 5 == 0 {
+                MAJOR_GRID
+            } else {
+                MINOR_GRID
+            };
+            frame.stroke(
+                &Path::line(Point::new(0.0, y), Point::new(size.width, y)),
+                Stroke::default().with_color(color).with_width(1.0),
+            );
+        }
+    }
+
+    /// Traces a channel in the lane centred on `baseline`.
+    fn draw_channel(
+        &self,
+        frame: &mut canvas::Frame,
+        channel: &WaveformChannel,
+        baseline: f32,
+        text_color: Color,
+    ) {
+        let pixels_per_second = self.speed.millimetres_per_second() * PIXELS_PER_MM;
+        let lane = LANE_MM * PIXELS_PER_MM;
+        let origin = MARGIN_MM * PIXELS_PER_MM;
+        // Voltage channels use the calibrated gain; others fill most of the lane.
+        let (offset, scale) = match channel.millivolts_per_unit() {
+            Some(millivolts) => (
+                0.0,
+                millivolts as f32 * self.gain.millimetres_per_millivolt() * PIXELS_PER_MM,
+            ),
+            None => match channel.range() {
+                Some((low, high)) if high > low => {
+                    ((low + high) / 2.0, 0.8 * lane / (high - low) as f32)
+                }
+                _ => (channel.range().map_or(0.0, |(low, _)| low), 0.0),
+            },
+        };
+        let y_of = |value: f64| baseline - (value - offset) as f32 * scale;
+
+        let mut label = channel.label.clone();
+        if channel.millivolts_per_unit().is_none() {
+            if let Some(unit) = &channel.unit {
+                label.push_str(&format!(" ({unit})"));
+            }
+        }
+        frame.fill_text(Text {
+            content: label,
+            position: Point::new(4.0, baseline - lane / 2.0 + 2.0),
+            color: text_color,
+            size: 12.0.into(),
+            ..Text::default()
+        });
+        if channel.millivolts_per_unit().is_some() {
+            // Standard 1 mV pulse, 5 mm wide.
+            let pulse = self.gain.millimetres_per_millivolt() * PIXELS_PER_MM;
+            let (start, width) = (5.0 * PIXELS_PER_MM, 5.0 * PIXELS_PER_MM);
+            let path = Path::new(|builder| {
+                builder.move_to(Point::new(start - 2.0 * PIXELS_PER_MM, baseline));
+                builder.line_to(Point::new(start, baseline));
+                builder.line_to(Point::new(start, baseline - pulse));
+                builder.line_to(Point::new(start + width, baseline - pulse));
+                builder.line_to(Point::new(start + width, baseline));
+                builder.line_to(Point::new(start + width + 2.0 * PIXELS_PER_MM, baseline));
+            });
+            frame.stroke(&path, Stroke::default().with_color(TRACE).with_width(1.5));
+        }
+
+        // With several samples per pixel column, draw each column's extremes.
+        let samples_per_pixel = self.group.sampling_frequency as f32 / pixels_per_second;
+        let mut points = Vec::new();
+        if samples_per_pixel > 2.0 {
+            let columns = (channel.samples.len() as f32 / samples_per_pixel).ceil() as usize;
+            for column in 0..columns {
+                let start = (column as f32 * samples_per_pixel) as usize;
+                let end =
+                    (((column + 1) as f32 * samples_per_pixel) as usize).min(channel.samples.len());
+                let Some(slice) = channel.samples.get(start..end) else {
+                    break;
+                };
+                let (low, high) = slice
+                    .iter()
+                    .filter(|value| value.is_finite())
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &value| {
+                        (low.min(value), high.max(value))
+                    });
+                let x = origin + column as f32;
+                if low.is_finite() {
+                    points.push(Some(Point::new(x, y_of(low))));
+                    points.push(Some(Point::new(x, y_of(high))));
+                } else {
+                    points.push(None);
+                }
+            }
+        } else {
+            points.extend(channel.samples.iter().enumerate().map(|(index, &value)| {
+                value.is_finite().then(|| {
+                    let seconds = index as f64 / self.group.sampling_frequency;
+                    Point::new(origin + seconds as f32 * pixels_per_second, y_of(value))
+                })
+            }));
+        }
+        let trace = Path::new(|builder| {
+            let mut drawing = false;
+            for point in &points {
+                match point {
+                    Some(point) if drawing => builder.line_to(*point),
+                    Some(point) => {
+                        builder.move_to(*point);
+                        drawing = true;
+                    }
+                    // Padding leaves a gap in the trace.
+                    None => drawing = false,
+                }
+            }
+        });
+        frame.stroke(&trace, Stroke::default().with_color(TRACE).with_width(1.2));
+    }
+}
+
+impl canvas::Program<Message> for WaveformChart<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let palette = theme.extended_palette();
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(
+            Point::ORIGIN,
+            bounds.size(),
+            Color::from_rgb(0.08, 0.08, 0.1),
+        );
+        self.draw_grid(&mut frame, bounds.size());
+
+        // Seconds along the bottom edge.
+        let pixels_per_second = self.speed.millimetres_per_second() * PIXELS_PER_MM;
+        let step = (60.0 / pixels_per_second).ceil().max(1.0) as usize;
+        for second in (0..=self.group.duration().floor() as usize).step_by(step) {
+            frame.fill_text(Text {
+                content: format!("{second} s"),
+                position: Point::new(
+                    MARGIN_MM * PIXELS_PER_MM + second as f32 * pixels_per_second + 2.0,
+                    bounds.height - 14.0,
+                ),
+                color: palette.background.weak.text,
+                size: 10.0.into(),
+                ..Text::default()
+            });
+        }
+
+        let lane = LANE_MM * PIXELS_PER_MM;
+        for (index, channel) in self.group.channels.iter().enumerate() {
+            let baseline = (index as f32 + 0.5) * lane;
+            self.draw_channel(&mut frame, channel, baseline, palette.background.base.text);
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
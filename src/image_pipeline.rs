@@ -1,13 +1,34 @@
+use crate::codecs;
+use crate::model::attributes::attribute_text;
+use crate::model::functional_groups;
+use crate::model::segmentation::{SegmentMask, SEGMENT_OPACITY};
+use crate::model::{
+    Calibration, ColorMap, DisplaySettings, FrameData, FramePreview, ImagePlane, OverlayBitmap,
+    OverlayPlane, OverlayStyle, PaletteLut, Rescale, Reslice, UltrasoundRegion, ViewTransform,
+    Volume, Window,
+};
 use dicom::object::DefaultDicomObject;
 use dicom::pixeldata::{
-    DecodedPixelData, PhotometricInterpretation, PixelDecoder, PlanarConfiguration,
+    DecodedPixelData, PhotometricInterpretation, PixelDecoder, PixelRepresentation,
+    PlanarConfiguration,
 };
 use iced::widget::image::Handle;
+use iced::{Color, Point, Size};
+use image::{ImageBuffer, Luma, RgbaImage};
+use std::sync::Arc;
 
 pub struct FrameImagePipeline;
 
+pub struct RenderedFrame {
+    pub preview: FramePreview,
+    pub data: Option<FrameData>,
+}
+
 impl FrameImagePipeline {
-    pub fn render_first_frame(object: &DefaultDicomObject) -> Result<Option<Handle>, String> {
+    /// Decodes the Pixel Data of an object, or `None` when it holds no frames.
+    pub fn decode(
+        object: &DefaultDicomObject,
+    ) -> Result<Option<DecodedPixelData<'static>>, String> {
         let decoded = match object.decode_pixel_data() {
             Ok(data) => data,
             Err(err) => {
@@ -18,13 +39,317 @@ impl FrameImagePipeline {
         if decoded.number_of_frames() == 0 {
             return Ok(None);
         }
+        Ok(Some(decoded.to_owned()))
+    }
 
-        Self::frame_to_handle(&decoded, 0).map(Some)
+    /// Renders one frame, through `window` when given or the default VOI window otherwise.
+    pub fn render_frame(
+        object: &DefaultDicomObject,
+        decoded: &DecodedPixelData<'_>,
+        frame_idx: u32,
+        window: Option<Window>,
+        color_map: ColorMap,
+    ) -> Result<RenderedFrame, String> {
+        let data = match Self::frame_data(object, decoded, frame_idx) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                log::warn!("Unable to read stored pixel values: {err}");
+                None
+            }
+        };
+        let windowed = data.as_ref().and_then(|data| {
+            let window = window.or(data.default_window)?;
+            Some(Self::render_windowed(data, window, color_map))
+        });
+        let handle = match windowed {
+            Some(handle) => handle,
+            None => {
+                Self::frame_to_handle(decoded, frame_idx, &sample_interpretation(object, decoded))?
+            }
+        };
+
+        let preview = FramePreview {
+            handle,
+            columns: decoded.columns(),
+            rows: decoded.rows(),
+        };
+        Ok(RenderedFrame { preview, data })
+    }
+
+    pub fn frame_data(
+        object: &DefaultDicomObject,
+        decoded: &DecodedPixelData<'_>,
+        frame_idx: u32,
+    ) -> Result<FrameData, String> {
+        let raw = raw_samples(decoded, frame_idx, &sample_interpretation(object, decoded))?;
+        let stored = stored_values(decoded, &raw);
+        let overlays = OverlayPlane::read_all(object)
+            .iter()
+            .filter_map(|plane| plane.bitmap(frame_idx, Some(&raw)))
+            .collect();
+
+        // Enhanced multi-frame objects hold geometry, rescale and windowing
+        // per frame in their functional groups.
+        let frame_object = functional_groups::frame_object(object, frame_idx);
+        let attributes = frame_object.as_ref().unwrap_or(object);
+
+        let mut data = FrameData {
+            rows: decoded.rows(),
+            columns: decoded.columns(),
+            samples_per_pixel: decoded.samples_per_pixel(),
+            stored,
+            rescale: Rescale::from_object(attributes),
+            plane: ImagePlane::from_object(attributes),
+            calibration: Calibration::from_object(attributes),
+            ultrasound_regions: UltrasoundRegion::read_all(object),
+            invert: matches!(
+                decoded.photometric_interpretation(),
+                PhotometricInterpretation::Monochrome1
+            ),
+            default_window: None,
+            overlays,
+            palette: None,
+        };
+        if data.is_monochrome() {
+            let signed = decoded.pixel_representation() == PixelRepresentation::Signed;
+            data.palette = match PaletteLut::from_object(object, signed) {
+                Ok(palette) => palette.map(Arc::new),
+                Err(err) => {
+                    log::warn!("Ignoring Palette Color LUT: {err}");
+                    None
+                }
+            };
+            data.default_window =
+                Window::from_object(attributes).or_else(|| Window::full_range(&data));
+        }
+        Ok(data)
+    }
+
+    /// Renders a frame as the viewer shows it: windowed, colour mapped, with
+    /// visible overlays burned in and the view transform applied. The stored
+    /// values are returned alongside for annotating the image.
+    pub fn render_displayed(
+        object: &DefaultDicomObject,
+        decoded: &DecodedPixelData<'_>,
+        frame_idx: u32,
+        display: &DisplaySettings,
+    ) -> Result<(RgbaImage, Option<FrameData>), String> {
+        let rendered = Self::render_frame(
+            object,
+            decoded,
+            frame_idx,
+            display.window,
+            display.color_map,
+        )?;
+        let Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } = &rendered.preview.handle
+        else {
+            return Err(String::from("Rendered frame is not an RGBA image"));
+        };
+
+        let mut rgba = pixels.to_vec();
+        let bitmaps = rendered.data.iter().flat_map(|data| &data.overlays);
+        for bitmap in bitmaps {
+            let style = display
+                .overlays
+                .get(&bitmap.group)
+                .copied()
+                .unwrap_or_else(|| OverlayStyle::for_group(bitmap.group));
+            if !style.visible {
+                continue;
+            }
+            let [r, g, b, _] = style.color.to_color().into_rgba8();
+            let columns = bitmap.columns.max(1) as usize;
+            for (index, _) in bitmap.bits.iter().enumerate().filter(|(_, &set)| set) {
+                let row = bitmap.origin.0 + (index / columns) as i32;
+                let column = bitmap.origin.1 + (index % columns) as i32;
+                if row < 0 || column < 0 || row >= *height as i32 || column >= *width as i32 {
+                    continue;
+                }
+                let offset = (row as usize * *width as usize + column as usize) * 4;
+                rgba[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        let oriented = Self::orient(Handle::from_rgba(*width, *height, rgba), display.transform);
+        match oriented {
+            Handle::Rgba {
+                width,
+                height,
+                pixels,
+                ..
+            } => RgbaImage::from_raw(width, height, pixels.to_vec())
+                .map(|image| (image, rendered.data))
+                .ok_or_else(|| String::from("Rendered frame has an unexpected size")),
+            _ => Err(String::from("Rendered frame is not an RGBA image")),
+        }
+    }
+
+    /// Stored values of a monochrome frame as 16-bit grayscale, without any
+    /// VOI or orientation. Signed values are offset by 32768.
+    pub fn render_raw16(
+        object: &DefaultDicomObject,
+        decoded: &DecodedPixelData<'_>,
+        frame_idx: u32,
+    ) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>, String> {
+        let data = Self::frame_data(object, decoded, frame_idx)?;
+        if !data.is_monochrome() {
+            return Err(String::from("Raw 16-bit export needs a monochrome frame"));
+        }
+        let offset = match decoded.pixel_representation() {
+            PixelRepresentation::Signed => 32768,
+            PixelRepresentation::Unsigned => 0,
+        };
+        let samples = data
+            .stored
+            .iter()
+            .map(|&stored| (stored + offset).clamp(0, u16::MAX as i32) as u16)
+            .collect();
+        ImageBuffer::from_raw(data.columns, data.rows, samples)
+            .ok_or_else(|| String::from("Frame has fewer samples than Rows × Columns"))
+    }
+
+    /// Renders an overlay bitmap as a transparent image with set bits in `color`.
+    pub fn overlay_handle(bitmap: &OverlayBitmap, color: Color) -> Handle {
+        let [r, g, b, _] = color.into_rgba8();
+        let mut rgba = Vec::with_capacity(bitmap.bits.len() * 4);
+        for &set in &bitmap.bits {
+            if set {
+                rgba.extend_from_slice(&[r, g, b, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+        Handle::from_rgba(bitmap.columns, bitmap.rows, rgba)
+    }
+
+    /// Renders a segment mask in `color`, more opaque where coverage is higher.
+    pub fn segment_handle(mask: &SegmentMask, color: Color) -> Handle {
+        let [r, g, b, _] = color.into_rgba8();
+        let mut rgba = Vec::with_capacity(mask.coverage.len() * 4);
+        for &coverage in &mask.coverage {
+            let alpha = (coverage as f32 * SEGMENT_OPACITY).round() as u8;
+            rgba.extend_from_slice(&[r, g, b, alpha]);
+        }
+        Handle::from_rgba(mask.columns, mask.rows, rgba)
+    }
+
+    /// Resamples a fused volume onto the pixels of `plane`, mapping the
+    /// window linearly through `color_map`. Pixels outside the volume or at
+    /// the bottom of the window stay transparent so the underlying image
+    /// shows through.
+    pub fn fusion_handle(
+        volume: &Volume,
+        plane: &ImagePlane,
+        rows: u32,
+        columns: u32,
+        window: Window,
+        color_map: ColorMap,
+        opacity: f32,
+    ) -> Handle {
+        let alpha = (opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let mut rgba = Vec::with_capacity(rows as usize * columns as usize * 4);
+        for row in 0..rows {
+            for column in 0..columns {
+                let point = plane.patient_position(row as f64, column as f64);
+                let gray = volume.sample(point).map(|value| {
+                    let fraction = (value as f64 - window.lower()) / window.width;
+                    (fraction.clamp(0.0, 1.0) * 255.0).round() as u8
+                });
+                match gray {
+                    Some(gray) if gray > 0 => {
+                        let [r, g, b] = color_map.apply(gray);
+                        rgba.extend_from_slice(&[r, g, b, alpha]);
+                    }
+                    _ => rgba.extend_from_slice(&[0, 0, 0, 0]),
+                }
+            }
+        }
+        Handle::from_rgba(columns, rows, rgba)
+    }
+
+    /// Mirrors and rotates an RGBA image for display; other handles are
+    /// returned unchanged.
+    pub fn orient(handle: Handle, transform: ViewTransform) -> Handle {
+        if transform.is_identity() {
+            return handle;
+        }
+        let Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } = &handle
+        else {
+            return handle;
+        };
+
+        let image = Size::new(*width as f32, *height as f32);
+        let display = transform.display_size(image);
+        let display_width = display.width as usize;
+        let mut rgba = vec![0; pixels.len()];
+        for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+            let source = Point::new(
+                (index % *width as usize) as f32 + 0.5,
+                (index / *width as usize) as f32 + 0.5,
+            );
+            let target = transform.to_display(source, image);
+            let offset = (target.y as usize * display_width + target.x as usize) * 4;
+            rgba[offset..offset + 4].copy_from_slice(pixel);
+        }
+        Handle::from_rgba(display.width as u32, display.height as u32, rgba)
+    }
+
+    /// Renders a monochrome frame through the given VOI window and colour map.
+    /// Values covered by a Palette Color LUT take their palette colour instead.
+    pub fn render_windowed(frame: &FrameData, window: Window, color_map: ColorMap) -> Handle {
+        let mut rgba = Vec::with_capacity(frame.stored.len() * 4);
+        let stored = frame
+            .stored
+            .iter()
+            .step_by(frame.samples_per_pixel.max(1) as usize);
+        for (&stored, value) in stored.zip(frame.modality_values()) {
+            let color = match &frame.palette {
+                Some(palette) if !palette.supplemental || palette.contains(stored) => {
+                    palette.lookup(stored)
+                }
+                _ => {
+                    let mut gray = window.apply(value);
+                    if frame.invert {
+                        gray = 255 - gray;
+                    }
+                    color_map.apply(gray)
+                }
+            };
+            rgba.extend_from_slice(&[color[0], color[1], color[2], 255]);
+        }
+        Handle::from_rgba(frame.columns, frame.rows, rgba)
+    }
+
+    /// Renders a reformatted plane through the given VOI window, with points
+    /// outside the volume black.
+    pub fn render_reslice(reslice: &Reslice, window: Window) -> Handle {
+        let mut rgba = Vec::with_capacity(reslice.values.len() * 4);
+        for &value in &reslice.values {
+            let gray = if value.is_nan() {
+                0
+            } else {
+                window.apply(value as f64)
+            };
+            rgba.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+        Handle::from_rgba(reslice.width, reslice.height, rgba)
     }
 
+    /// Renders a frame without VOI, reading colour samples as `photometric`.
     pub fn frame_to_handle(
         decoded: &DecodedPixelData<'_>,
         frame_idx: u32,
+        photometric: &PhotometricInterpretation,
     ) -> Result<Handle, String> {
         if frame_idx >= decoded.number_of_frames() {
             return Err(format!(
@@ -33,12 +358,15 @@ impl FrameImagePipeline {
             ));
         }
 
-        match decoded.photometric_interpretation() {
+        match photometric {
             photometric if photometric.is_monochrome() => {
                 Self::monochrome_to_handle(decoded, frame_idx)
             }
             PhotometricInterpretation::Rgb => Self::rgb_to_handle(decoded, frame_idx),
-            other => Self::fallback_to_dynamic(decoded, frame_idx, other.as_str()),
+            other => match YbrFormat::of(other) {
+                Some(format) => Self::ybr_to_handle(decoded, frame_idx, other, format),
+                None => Self::fallback_to_dynamic(decoded, frame_idx, other.as_str()),
+            },
         }
     }
 
@@ -110,6 +438,26 @@ impl FrameImagePipeline {
         Ok(Handle::from_rgba(width, height, rgba))
     }
 
+    /// Converts a YBR frame to RGB, after chroma upsampling in `raw_samples`.
+    fn ybr_to_handle(
+        decoded: &DecodedPixelData<'_>,
+        frame_idx: u32,
+        photometric: &PhotometricInterpretation,
+        format: YbrFormat,
+    ) -> Result<Handle, String> {
+        let raw = raw_samples(decoded, frame_idx, photometric)?;
+        let stored = stored_values(decoded, &raw);
+        let bits_stored = decoded.bits_stored().clamp(1, 16) as u32;
+        let signed = decoded.pixel_representation() == PixelRepresentation::Signed;
+
+        let mut rgba = Vec::with_capacity(stored.len() / 3 * 4);
+        for pixel in stored.chunks_exact(3) {
+            let [r, g, b] = ybr_to_rgb(format.color_space, pixel, bits_stored, signed);
+            rgba.extend_from_slice(&[r, g, b, 255]);
+        }
+        Ok(Handle::from_rgba(decoded.columns(), decoded.rows(), rgba))
+    }
+
     fn fallback_to_dynamic(
         decoded: &DecodedPixelData<'_>,
         frame_idx: u32,
@@ -128,6 +476,291 @@ impl FrameImagePipeline {
     }
 }
 
+/// Photometric interpretation of decoded samples. dicom-rs labels every
+/// decoded 3-sample encapsulated frame RGB, but only the JPEG and JPEG 2000
+/// decoders convert colour; RLE Lossless and JPEG-LS samples, like native
+/// ones, keep the interpretation of the dataset.
+fn sample_interpretation(
+    object: &DefaultDicomObject,
+    decoded: &DecodedPixelData<'_>,
+) -> PhotometricInterpretation {
+    if codecs::decodes_to_rgb(object.meta().transfer_syntax()) {
+        return decoded.photometric_interpretation().clone();
+    }
+    attribute_text(object, "PhotometricInterpretation")
+        .map(PhotometricInterpretation::from)
+        .unwrap_or_else(|| decoded.photometric_interpretation().clone())
+}
+
+/// Unmasked samples of one frame, pixel-interleaved regardless of the
+/// planar configuration, with subsampled YBR chroma upsampled to one sample
+/// per pixel.
+fn raw_samples(
+    decoded: &DecodedPixelData<'_>,
+    frame_idx: u32,
+    photometric: &PhotometricInterpretation,
+) -> Result<Vec<u32>, String> {
+    let subsampling =
+        YbrFormat::of(photometric).map_or(ChromaSubsampling::None, |format| format.subsampling);
+    let (bytes, subsampled) = if subsampling == ChromaSubsampling::None {
+        let bytes = decoded
+            .frame_data(frame_idx)
+            .map_err(|err| format!("Failed to read frame {frame_idx}: {err}"))?;
+        (bytes, false)
+    } else {
+        subsampled_frame_data(decoded, frame_idx, subsampling)?
+    };
+    let samples: Vec<u32> = match decoded.bits_allocated() {
+        8 => bytes.iter().map(|&byte| byte as u32).collect(),
+        16 => bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]) as u32)
+            .collect(),
+        32 => bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect(),
+        other => return Err(format!("Unsupported Bits Allocated: {other}")),
+    };
+
+    if subsampled {
+        return upsample_chroma(decoded, &samples, subsampling);
+    }
+
+    let samples_per_pixel = decoded.samples_per_pixel() as usize;
+    if samples_per_pixel > 1 && decoded.planar_configuration() == PlanarConfiguration::PixelFirst {
+        let pixel_count = samples.len() / samples_per_pixel;
+        let mut interleaved = Vec::with_capacity(samples.len());
+        for pixel in 0..pixel_count {
+            for sample in 0..samples_per_pixel {
+                interleaved.push(samples[sample * pixel_count + pixel]);
+            }
+        }
+        return Ok(interleaved);
+    }
+    Ok(samples)
+}
+
+/// Masks raw samples to Bits Stored below High Bit and sign-extends them.
+fn stored_values(decoded: &DecodedPixelData<'_>, raw: &[u32]) -> Vec<i32> {
+    let bits_stored = decoded.bits_stored().clamp(1, 32) as u32;
+    let shift = (decoded.high_bit() as u32 + 1).saturating_sub(bits_stored);
+    let mask = if bits_stored >= 32 {
+        u32::MAX
+    } else {
+        (1u32 << bits_stored) - 1
+    };
+    let signed = decoded.pixel_representation() == PixelRepresentation::Signed;
+
+    raw.iter()
+        .map(|&sample| {
+            let value = (sample >> shift) & mask;
+            if signed && bits_stored < 32 && value & (1 << (bits_stored - 1)) != 0 {
+                (value as i64 - (1i64 << bits_stored)) as i32
+            } else {
+                value as i32
+            }
+        })
+        .collect()
+}
+
+/// Colour spaces of the YBR photometric interpretations (PS3.3 C.7.6.3.1.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YbrColorSpace {
+    /// YBR_FULL, YBR_FULL_422 and YBR_ICT: full-range ITU-R BT.601.
+    Full,
+    /// YBR_PARTIAL_422 and YBR_PARTIAL_420: luminance 16–235 and chroma
+    /// 16–240 for 8-bit data.
+    Partial,
+    /// YBR_RCT: the reversible colour transform of JPEG 2000.
+    Reversible,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChromaSubsampling {
+    None,
+    /// One chroma sample per two pixels of a row (4:2:2).
+    Horizontal,
+    /// One chroma sample per two-by-two block of pixels (4:2:0).
+    HorizontalAndVertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct YbrFormat {
+    color_space: YbrColorSpace,
+    subsampling: ChromaSubsampling,
+}
+
+impl YbrFormat {
+    fn of(photometric: &PhotometricInterpretation) -> Option<Self> {
+        let (color_space, subsampling) = match photometric {
+            PhotometricInterpretation::YbrFull | PhotometricInterpretation::YbrIct => {
+                (YbrColorSpace::Full, ChromaSubsampling::None)
+            }
+            PhotometricInterpretation::YbrFull422 => {
+                (YbrColorSpace::Full, ChromaSubsampling::Horizontal)
+            }
+            PhotometricInterpretation::YbrPartial420 => (
+                YbrColorSpace::Partial,
+                ChromaSubsampling::HorizontalAndVertical,
+            ),
+            PhotometricInterpretation::YbrRct => {
+                (YbrColorSpace::Reversible, ChromaSubsampling::None)
+            }
+            PhotometricInterpretation::Other(name) if name.trim() == "YBR_PARTIAL_422" => {
+                (YbrColorSpace::Partial, ChromaSubsampling::Horizontal)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            color_space,
+            subsampling,
+        })
+    }
+}
+
+/// Layout of one subsampled frame: interleaved 4:2:2 data stores whole
+/// `Y Y Cb Cr` groups per pixel pair, planar and 4:2:0 data store the Y plane
+/// followed by the Cb and Cr planes.
+struct SubsampledLayout {
+    columns: usize,
+    rows: usize,
+    chroma_columns: usize,
+    chroma_rows: usize,
+    interleaved: bool,
+}
+
+impl SubsampledLayout {
+    fn new(decoded: &DecodedPixelData<'_>, subsampling: ChromaSubsampling) -> Self {
+        let columns = decoded.columns() as usize;
+        let rows = decoded.rows() as usize;
+        let chroma_rows = match subsampling {
+            ChromaSubsampling::HorizontalAndVertical => rows.div_ceil(2),
+            _ => rows,
+        };
+        Self {
+            columns,
+            rows,
+            chroma_columns: columns.div_ceil(2),
+            chroma_rows,
+            interleaved: subsampling == ChromaSubsampling::Horizontal
+                && decoded.planar_configuration() == PlanarConfiguration::Standard,
+        }
+    }
+
+    fn sample_count(&self) -> usize {
+        if self.interleaved {
+            self.rows * self.chroma_columns * 4
+        } else {
+            self.columns * self.rows + 2 * self.chroma_columns * self.chroma_rows
+        }
+    }
+}
+
+/// Bytes of one frame of a 4:2:2 or 4:2:0 interpretation, and whether they
+/// are actually subsampled: decoders and many writers store such data at
+/// full resolution despite the name, which shows in the Pixel Data length.
+fn subsampled_frame_data<'a>(
+    decoded: &'a DecodedPixelData<'_>,
+    frame_idx: u32,
+    subsampling: ChromaSubsampling,
+) -> Result<(&'a [u8], bool), String> {
+    let layout = SubsampledLayout::new(decoded, subsampling);
+    let bytes_per_sample = (decoded.bits_allocated() as usize).div_ceil(8);
+    let full_length = layout.columns * layout.rows * 3 * bytes_per_sample;
+    let subsampled_length = layout.sample_count() * bytes_per_sample;
+
+    let data = decoded.data();
+    let frames = decoded.number_of_frames().max(1) as usize;
+    let (frame_length, subsampled) = if data.len() >= full_length * frames {
+        (full_length, false)
+    } else {
+        (subsampled_length, true)
+    };
+    let start = frame_length * frame_idx as usize;
+    data.get(start..start + frame_length)
+        .map(|bytes| (bytes, subsampled))
+        .ok_or_else(|| format!("Failed to read frame {frame_idx}: Pixel Data is too short"))
+}
+
+/// Expands subsampled Y, Cb, Cr samples to interleaved full-resolution
+/// triplets, repeating each chroma sample over the pixels it covers.
+fn upsample_chroma(
+    decoded: &DecodedPixelData<'_>,
+    samples: &[u32],
+    subsampling: ChromaSubsampling,
+) -> Result<Vec<u32>, String> {
+    let layout = SubsampledLayout::new(decoded, subsampling);
+    if samples.len() < layout.sample_count() {
+        return Err(format!(
+            "Subsampled YBR frame has {} samples, expected {}",
+            samples.len(),
+            layout.sample_count()
+        ));
+    }
+
+    let luma_count = layout.columns * layout.rows;
+    let chroma_count = layout.chroma_columns * layout.chroma_rows;
+    let mut ybr = Vec::with_capacity(luma_count * 3);
+    for row in 0..layout.rows {
+        for column in 0..layout.columns {
+            let triplet = if layout.interleaved {
+                let group = (row * layout.chroma_columns + column / 2) * 4;
+                [
+                    samples[group + column % 2],
+                    samples[group + 2],
+                    samples[group + 3],
+                ]
+            } else {
+                let chroma_row = row * layout.chroma_rows / layout.rows;
+                let chroma = chroma_row * layout.chroma_columns + column / 2;
+                [
+                    samples[row * layout.columns + column],
+                    samples[luma_count + chroma],
+                    samples[luma_count + chroma_count + chroma],
+                ]
+            };
+            ybr.extend_from_slice(&triplet);
+        }
+    }
+    Ok(ybr)
+}
+
+/// Converts one Y, Cb, Cr triplet of stored values to 8-bit RGB. Unsigned
+/// chroma is centred on half the stored range.
+fn ybr_to_rgb(color_space: YbrColorSpace, ybr: &[i32], bits_stored: u32, signed: bool) -> [u8; 3] {
+    let max = ((1u32 << bits_stored) - 1) as f64;
+    let half = if signed {
+        0.0
+    } else {
+        (1u32 << (bits_stored - 1)) as f64
+    };
+    let (y, cb, cr) = (ybr[0] as f64, ybr[1] as f64 - half, ybr[2] as f64 - half);
+
+    let [r, g, b] = match color_space {
+        YbrColorSpace::Full => [
+            y + 1.402 * cr,
+            y - 0.344136 * cb - 0.714136 * cr,
+            y + 1.772 * cb,
+        ],
+        YbrColorSpace::Partial => {
+            // Offsets and ranges of 8-bit data, scaled to the stored depth.
+            let y = (y - 16.0 * max / 255.0) * 255.0 / 219.0;
+            let (cb, cr) = (cb * 255.0 / 224.0, cr * 255.0 / 224.0);
+            [
+                y + 1.402 * cr,
+                y - 0.344136 * cb - 0.714136 * cr,
+                y + 1.772 * cb,
+            ]
+        }
+        YbrColorSpace::Reversible => {
+            let g = y - ((cb + cr) / 4.0).floor();
+            [cr + g, g, cb + g]
+        }
+    };
+    [r, g, b].map(|value| (value * 255.0 / max).round().clamp(0.0, 255.0) as u8)
+}
+
 fn rgb_interleaved_to_rgba(samples: &[u8]) -> Result<Vec<u8>, String> {
     if !samples.len().is_multiple_of(3) {
         return Err(format!(
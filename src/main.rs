@@ -1,4 +1,6 @@
 mod app;
+mod burn_in;
+mod codecs;
 mod components;
 mod image_pipeline;
 mod message;
@@ -1,10 +1,97 @@
-use crate::model::{DicomEntry, TreeNodeKey, TreeViewMode};
+use crate::model::{
+    ColorMap, DicomEntry, DocumentPreview, ExportMode, ExportScale, ExportScope, HistogramScope,
+    MeasurementTool, MprAxis, MprView, OverlayColor, PaperSpeed, Projection, TreeNodeKey,
+    TreeViewMode, ViewTransform, Volume, WaveformGain, Window,
+};
+use iced::widget::image::Handle;
+use iced::Point;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum Message {
     PickFiles,
     FilesLoaded(Vec<Result<DicomEntry, String>>),
+    /// Thumbnail of the instance with the given [`thumbnail::key`].
+    ThumbnailReady(String, Result<Handle, String>),
+    /// Rendered preview of the document of the instance with the given SOP
+    /// Instance UID.
+    DocumentRendered(String, DocumentPreview),
     SelectInstance(usize),
+    /// Selects a loaded instance by SOP Instance UID, and a 0-based frame of it.
+    OpenReferencedInstance(String, Option<u32>),
     ToggleNode(TreeNodeKey),
     SetTreeViewMode(TreeViewMode),
+    ImageHovered(Option<Point>),
+    ImagePressed(Point),
+    ImageDragged(Point),
+    ImageReleased(Point),
+    SelectTool(Option<MeasurementTool>),
+    DeleteMeasurement(usize),
+    ExportMeasurements,
+    MeasurementsExported(Result<Option<PathBuf>, String>),
+    SetExportMode(ExportMode),
+    SetExportTemplate(String),
+    SetExportScale(ExportScale),
+    ExportImages(ExportScope),
+    /// Exports the frames of the displayed object or series as a GIF or APNG.
+    ExportCine(ExportScope),
+    /// Number of image files written, zero when the export was cancelled.
+    ImagesExported(Result<usize, String>),
+    /// Saves the window, displayed area, orientation and measurements of the
+    /// displayed series as a presentation state.
+    ExportPresentationState,
+    PresentationStateExported(Result<Option<PathBuf>, String>),
+    /// Writes the CT dose of every loaded dose report as CSV.
+    ExportDoseCsv,
+    DoseCsvExported(Result<Option<PathBuf>, String>),
+    /// Extracts the document of the displayed encapsulated document instance.
+    SaveDocument,
+    DocumentSaved(Result<Option<PathBuf>, String>),
+    /// Shows a multiplex group of the displayed waveform, by index.
+    SelectWaveformGroup(usize),
+    SetPaperSpeed(PaperSpeed),
+    SetWaveformGain(WaveformGain),
+    SetWindow(Window),
+    ResetWindow,
+    SetColorMap(ColorMap),
+    ToggleHistogram,
+    SetHistogramLogScale(bool),
+    SetHistogramScope(HistogramScope),
+    SelectFrame(u32),
+    SetOverlayVisible(u16, bool),
+    SetOverlayColor(u16, OverlayColor),
+    /// Shows or hides a segment, by segmentation SOP Instance UID and segment number.
+    SetSegmentVisible(String, u16, bool),
+    /// Shows or hides an RT ROI, by structure set SOP Instance UID and ROI number.
+    SetRoiVisible(String, u32, bool),
+    ToggleAnnotations,
+    /// Outlines the ultrasound regions of the displayed frame, or hides them.
+    SetUltrasoundRegionsVisible(bool),
+    /// Flips or rotates the displayed image by the given transform.
+    TransformView(ViewTransform),
+    /// Fuses a PET series onto the displayed image, or stops fusing.
+    SetFusionSeries(Option<String>),
+    FusionVolumeAssembled(String, Result<Arc<Volume>, String>),
+    SetFusionOpacity(f32),
+    SetFusionColorMap(ColorMap),
+    /// Upper end of the PET window.
+    SetFusionUpper(f64),
+    /// Applies a presentation state, by SOP Instance UID, to the displayed
+    /// image, or shows it without one.
+    SelectPresentationState(Option<String>),
+    /// Stacks the series of the selected instance into a volume for MPR.
+    OpenMpr,
+    MprVolumeAssembled(Result<Arc<Volume>, String>),
+    /// Reformatted MPR planes for the numbered reslice request.
+    MprResliced(u64, Result<Vec<MprView>, String>),
+    CloseMpr,
+    MoveMprCrosshair(MprAxis, Point),
+    /// Moves the crosshair along the normal of a plane by a number of steps.
+    ScrollMpr(MprAxis, f32),
+    SetMprSlab(f64),
+    SetMprProjection(Projection),
+    SetMprYaw(f64),
+    SetMprPitch(f64),
+    ResetMpr,
 }
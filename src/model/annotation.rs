@@ -0,0 +1,216 @@
+use super::attributes::attribute_text;
+use super::Window;
+use crate::utils::render_lines;
+use dicom::object::InMemDicomObject;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE_NAME: &str = "annotations.ini";
+const CONFIG_ENV_VAR: &str = "DICOMANCER_ANNOTATIONS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        }
+    }
+}
+
+/// Text templates for the four image corners. Placeholders name attribute
+/// aliases (`{PatientName}`, `{SliceLocation:.1}`) or viewer values:
+/// `{Frame}`, `{FrameCount}`, `{WindowCenter}`, `{WindowWidth}` and `{Zoom}`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CornerTemplates {
+    corners: [String; 4],
+}
+
+impl CornerTemplates {
+    fn new(top_left: &str, top_right: &str, bottom_left: &str, bottom_right: &str) -> Self {
+        Self {
+            corners: [top_left, top_right, bottom_left, bottom_right].map(str::to_string),
+        }
+    }
+
+    pub fn get(&self, corner: Corner) -> &str {
+        &self.corners[corner as usize]
+    }
+
+    fn set(&mut self, corner: Corner, template: String) {
+        self.corners[corner as usize] = template;
+    }
+
+    pub fn render(&self, corner: Corner, context: &AnnotationContext<'_>) -> Vec<String> {
+        render_lines(self.get(corner), |name| context.lookup(name))
+    }
+}
+
+/// Values the corner templates are resolved against.
+#[derive(Clone)]
+pub struct AnnotationContext<'a> {
+    pub object: &'a InMemDicomObject,
+    pub frame_index: u32,
+    pub frame_count: u32,
+    pub window: Option<Window>,
+    /// Display scale, 1.0 being one screen pixel per image pixel.
+    pub zoom: f32,
+}
+
+impl AnnotationContext<'_> {
+    fn lookup(&self, name: &str) -> Option<String> {
+        match name {
+            "Frame" => (self.frame_count > 1).then(|| (self.frame_index + 1).to_string()),
+            "FrameCount" => (self.frame_count > 1).then(|| self.frame_count.to_string()),
+            "WindowCenter" => self.window.map(|window| window.center.to_string()),
+            "WindowWidth" => self.window.map(|window| window.width.to_string()),
+            "Zoom" => Some(format!("{:.0}%", self.zoom * 100.0)),
+            alias => attribute_text(self.object, alias),
+        }
+    }
+}
+
+/// Corner templates keyed by Modality, with a fallback for other modalities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationConfig {
+    default: CornerTemplates,
+    by_modality: BTreeMap<String, CornerTemplates>,
+}
+
+impl Default for AnnotationConfig {
+    fn default() -> Self {
+        let patient = "{PatientName}\nID {PatientID}\n{PatientBirthDate} {PatientSex}";
+        let study = "{InstitutionName}\n{StudyDescription}\n{StudyDate} {StudyTime}";
+        let series = "Se {SeriesNumber} Im {InstanceNumber}\n{SeriesDescription}\nFrame {Frame}/{FrameCount}";
+        let display = "W {WindowWidth:.0} L {WindowCenter:.0}\nZoom {Zoom}";
+
+        let mut by_modality = BTreeMap::new();
+        by_modality.insert(
+            "CT".to_string(),
+            CornerTemplates::new(
+                patient,
+                study,
+                "Se {SeriesNumber} Im {InstanceNumber}\n{SeriesDescription}\nLoc {SliceLocation:.1} mm\nThk {SliceThickness:.1} mm",
+                "{KVP:.0} kV {XRayTubeCurrent:.0} mA\nW {WindowWidth:.0} L {WindowCenter:.0}\nZoom {Zoom}",
+            ),
+        );
+        by_modality.insert(
+            "MR".to_string(),
+            CornerTemplates::new(
+                patient,
+                study,
+                "Se {SeriesNumber} Im {InstanceNumber}\n{SeriesDescription}\nLoc {SliceLocation:.1} mm\nThk {SliceThickness:.1} mm",
+                "TR {RepetitionTime:.0} TE {EchoTime:.0}\n{MagneticFieldStrength:.1} T\nW {WindowWidth:.0} L {WindowCenter:.0}\nZoom {Zoom}",
+            ),
+        );
+        let projection = CornerTemplates::new(
+            patient,
+            study,
+            "{ViewPosition} {ImageLaterality}\n{SeriesDescription}\nIm {InstanceNumber}",
+            display,
+        );
+        for modality in ["CR", "DX", "MG"] {
+            by_modality.insert(modality.to_string(), projection.clone());
+        }
+
+        Self {
+            default: CornerTemplates::new(patient, study, series, display),
+            by_modality,
+        }
+    }
+}
+
+impl AnnotationConfig {
+    /// Built-in templates overridden by the user's `annotations.ini`, if any.
+    pub fn load() -> Self {
+        let mut config = Self::default();
+        let Some(path) = config_path() else {
+            return config;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                log::info!("Loading annotation templates from {}", path.display());
+                config.apply_ini(&contents);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => log::warn!(
+                "{}: failed to read annotation templates ({err})",
+                path.display()
+            ),
+        }
+        config
+    }
+
+    pub fn for_modality(&self, modality: Option<&str>) -> &CornerTemplates {
+        modality
+            .and_then(|modality| self.by_modality.get(modality))
+            .unwrap_or(&self.default)
+    }
+
+    /// Applies `[default]` / `[<Modality>]` sections of `corner = template`
+    /// lines, where `\n` in a template starts a new line.
+    fn apply_ini(&mut self, contents: &str) {
+        let mut section = String::from("default");
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                continue;
+            }
+            let Some((key, template)) = line.split_once('=') else {
+                log::warn!("Ignoring annotation template line `{line}`");
+                continue;
+            };
+            let Some(corner) = Corner::ALL
+                .into_iter()
+                .find(|corner| corner.key() == key.trim())
+            else {
+                log::warn!("Unknown annotation corner `{}`", key.trim());
+                continue;
+            };
+
+            let template = template.trim().replace("\\n", "\n");
+            if section.eq_ignore_ascii_case("default") {
+                self.default.set(corner, template);
+            } else {
+                let default = self.default.clone();
+                self.by_modality
+                    .entry(section.to_ascii_uppercase())
+                    .or_insert(default)
+                    .set(corner, template);
+            }
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("dicomancer").join(CONFIG_FILE_NAME))
+}
@@ -0,0 +1,34 @@
+use dicom::object::InMemDicomObject;
+
+pub fn attribute_text(object: &InMemDicomObject, name: &str) -> Option<String> {
+    object
+        .element_by_name(name)
+        .ok()
+        .and_then(|element| element.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn attribute_f64(object: &InMemDicomObject, name: &str) -> Option<f64> {
+    object
+        .element_by_name(name)
+        .ok()
+        .and_then(|element| element.to_float64().ok())
+}
+
+pub fn attribute_f64s(object: &InMemDicomObject, name: &str) -> Option<Vec<f64>> {
+    object
+        .element_by_name(name)
+        .ok()
+        .and_then(|element| element.to_multi_float64().ok())
+        .filter(|values| !values.is_empty())
+}
+
+/// Items of a sequence attribute; empty when it is missing or not a sequence.
+pub fn sequence_items<'a>(object: &'a InMemDicomObject, name: &str) -> &'a [InMemDicomObject] {
+    object
+        .element_by_name(name)
+        .ok()
+        .and_then(|element| element.items())
+        .unwrap_or(&[])
+}
@@ -0,0 +1,147 @@
+use super::attributes::{attribute_f64, attribute_f64s};
+use super::export::{render_annotated, DisplaySettings, ExportSource};
+use dicom::object::InMemDicomObject;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Frame time used when an object records no timing, e.g. a stack of
+/// single-frame slices.
+const DEFAULT_FRAME_TIME_MS: f64 = 100.0;
+
+/// Animated image formats, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CineFormat {
+    Gif,
+    Apng,
+}
+
+impl CineFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(CineFormat::Gif),
+            "png" | "apng" => Some(CineFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+/// How long each frame is shown, in milliseconds.
+///
+/// Frame Time Vector (0018,1065) holds the time since the previous frame, so
+/// frame `i` lasts until entry `i + 1` and the last frame repeats the last
+/// increment. Without it, Frame Time (0018,1063) applies to every frame,
+/// then Cine Rate or Recommended Display Frame Rate in frames per second.
+pub fn frame_durations(object: &InMemDicomObject, frame_count: u32) -> Vec<f64> {
+    let count = frame_count as usize;
+    if let Some(vector) = attribute_f64s(object, "FrameTimeVector")
+        .filter(|vector| vector.len() == count && count > 1)
+    {
+        let mut durations = vector[1..].to_vec();
+        durations.push(vector[count - 1]);
+        if durations.iter().all(|duration| *duration > 0.0) {
+            return durations;
+        }
+    }
+
+    let positive = |value: &f64| *value > 0.0 && value.is_finite();
+    let frame_time = attribute_f64(object, "FrameTime")
+        .filter(positive)
+        .or_else(|| {
+            attribute_f64(object, "CineRate")
+                .or_else(|| attribute_f64(object, "RecommendedDisplayFrameRate"))
+                .filter(positive)
+                .map(|rate| 1000.0 / rate)
+        })
+        .unwrap_or(DEFAULT_FRAME_TIME_MS);
+    vec![frame_time; count]
+}
+
+/// Renders every frame of `sources` as displayed and writes them to `path`
+/// as an endlessly looping GIF or APNG with the recorded frame timing.
+/// Returns the number of frames written.
+pub fn export_cine(
+    sources: &[ExportSource],
+    path: &Path,
+    display: &DisplaySettings,
+) -> Result<usize, String> {
+    let format = CineFormat::from_path(path).ok_or_else(|| {
+        format!(
+            "{}: unknown animation format, use .gif or .png",
+            path.display()
+        )
+    })?;
+
+    let mut frames: Vec<(RgbaImage, f64)> = Vec::new();
+    for source in sources {
+        let frame_count = source.pixels.number_of_frames();
+        let durations = frame_durations(&source.object, frame_count);
+        for (frame_index, duration) in (0..frame_count).zip(durations) {
+            let image = render_annotated(&source.object, &source.pixels, frame_index, display)?;
+            if let Some((first, _)) = frames.first() {
+                if first.dimensions() != image.dimensions() {
+                    return Err(format!(
+                        "Frame {} is {}×{}, but the animation is {}×{}",
+                        frames.len() + 1,
+                        image.width(),
+                        image.height(),
+                        first.width(),
+                        first.height()
+                    ));
+                }
+            }
+            frames.push((image, duration));
+        }
+    }
+    if frames.is_empty() {
+        return Err(String::from("No frames to export"));
+    }
+
+    let file = File::create(path)
+        .map_err(|err| format!("{}: failed to create file ({err})", path.display()))?;
+    let writer = BufWriter::new(file);
+    let result = match format {
+        CineFormat::Gif => write_gif(writer, &frames),
+        CineFormat::Apng => write_apng(writer, &frames),
+    };
+    result.map_err(|err| format!("{}: failed to write animation ({err})", path.display()))?;
+    log::info!("Exported {} frame(s) to {}", frames.len(), path.display());
+    Ok(frames.len())
+}
+
+fn write_gif(writer: BufWriter<File>, frames: &[(RgbaImage, f64)]) -> Result<(), String> {
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|err| err.to_string())?;
+    encoder
+        .encode_frames(frames.iter().map(|(image, duration)| {
+            let delay = Delay::from_numer_denom_ms(duration.round().max(1.0) as u32, 1);
+            Frame::from_parts(image.clone(), 0, 0, delay)
+        }))
+        .map_err(|err| err.to_string())
+}
+
+fn write_apng(writer: BufWriter<File>, frames: &[(RgbaImage, f64)]) -> Result<(), String> {
+    let (width, height) = frames[0].0.dimensions();
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|err| err.to_string())?;
+    let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+    for (image, duration) in frames {
+        let milliseconds = duration.round().clamp(1.0, u16::MAX as f64) as u16;
+        writer
+            .set_frame_delay(milliseconds, 1000)
+            .map_err(|err| err.to_string())?;
+        writer
+            .write_image_data(image.as_raw())
+            .map_err(|err| err.to_string())?;
+    }
+    writer.finish().map_err(|err| err.to_string())
+}
@@ -1,11 +1,71 @@
+use super::{
+    ColorMap, CtDoseSummary, EncapsulatedDocument, FrameData, FunctionalGroup, OverlayPlane,
+    PresentationState, Segmentation, StructureSet, StructuredReport, ViewTransform, Waveform,
+    Window,
+};
+use crate::utils::{format_tag, value_to_string};
+use dicom::core::dictionary::DataDictionary;
+use dicom::core::header::Header;
+use dicom::dictionary_std::StandardDataDictionary;
+use dicom::object::mem::InMemElement;
+use dicom::object::DefaultDicomObject;
+use dicom::pixeldata::DecodedPixelData;
 use iced::widget::image::Handle;
+use iced::Rectangle;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct DicomView {
     pub file_path: PathBuf,
+    /// Transfer syntax UID of the file as stored on disk.
+    pub transfer_syntax: String,
+    pub source: Arc<DefaultDicomObject>,
     pub metadata: Vec<MetadataRow>,
-    pub image: Option<Handle>,
+    /// Shared and Per-Frame Functional Groups of the displayed frame of an
+    /// enhanced multi-frame object.
+    pub frame_groups: Vec<FunctionalGroup>,
+    /// Decoded Pixel Data, kept so other frames can be rendered on demand.
+    pub pixels: Option<Arc<DecodedPixelData<'static>>>,
+    pub frame_count: u32,
+    pub frame_index: u32,
+    pub image: Option<FramePreview>,
+    /// Why the Pixel Data could not be previewed, shown in place of the image.
+    pub pixel_error: Option<String>,
+    pub frame: Option<FrameData>,
+    /// VOI window the preview is currently rendered with.
+    pub window: Option<Window>,
+    /// Pseudocolor map applied to windowed monochrome values.
+    pub color_map: ColorMap,
+    pub overlays: Vec<OverlayPlane>,
+    /// Segments of a Segmentation object, drawn over the images it references.
+    pub segmentation: Option<Arc<Segmentation>>,
+    /// ROIs of an RT Structure Set, outlined on the images they lie on.
+    pub structure_set: Option<Arc<StructureSet>>,
+    /// Flip and rotation the preview and overlay layers are rendered with.
+    pub transform: ViewTransform,
+    /// Region of the image shown, in image pixel coordinates; the whole
+    /// image when `None`.
+    pub displayed_area: Option<Rectangle>,
+    /// Display settings and annotations of a Grayscale Softcopy Presentation
+    /// State, applied to the images it references.
+    pub presentation_state: Option<Arc<PresentationState>>,
+    /// Content tree of a Structured Report document, shown in place of the image.
+    pub structured_report: Option<Arc<StructuredReport>>,
+    /// CT dose of an X-Ray Radiation Dose SR.
+    pub dose_summary: Option<Arc<CtDoseSummary>>,
+    /// Document of an Encapsulated PDF, CDA, STL or OBJ instance, shown in
+    /// place of the image.
+    pub document: Option<Arc<EncapsulatedDocument>>,
+    /// Multiplex groups of a Waveform Sequence, plotted in place of the image.
+    pub waveform: Option<Arc<Waveform>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FramePreview {
+    pub handle: Handle,
+    pub columns: u32,
+    pub rows: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -16,6 +76,22 @@ pub struct MetadataRow {
     pub value: String,
 }
 
+impl MetadataRow {
+    pub fn from_element(element: &InMemElement) -> Self {
+        let tag = element.tag();
+        Self {
+            tag: format_tag(tag),
+            vr: element.vr().to_string().to_owned(),
+            alias: StandardDataDictionary
+                .by_tag(tag)
+                .map(|entry| entry.alias)
+                .unwrap_or("Unknown")
+                .to_string(),
+            value: value_to_string(element.value(), element.vr()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DicomEntry {
     pub patient_id: String,
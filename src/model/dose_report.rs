@@ -0,0 +1,249 @@
+use super::attributes::attribute_text;
+use super::structured_report::{SrNode, SrValue, StructuredReport};
+use dicom::object::InMemDicomObject;
+use std::collections::BTreeMap;
+
+const DCM: &str = "DCM";
+const X_RAY_RADIATION_DOSE_REPORT: &str = "113701";
+const CT_ACCUMULATED_DOSE_DATA: &str = "113811";
+const TOTAL_NUMBER_OF_IRRADIATION_EVENTS: &str = "113812";
+const CT_DOSE_LENGTH_PRODUCT_TOTAL: &str = "113813";
+const CT_ACQUISITION: &str = "113819";
+const CT_ACQUISITION_TYPE: &str = "113820";
+const CT_ACQUISITION_PARAMETERS: &str = "113822";
+const SCANNING_LENGTH: &str = "113825";
+const CT_DOSE: &str = "113829";
+const MEAN_CTDI_VOL: &str = "113830";
+const CTDIW_PHANTOM_TYPE: &str = "113835";
+const DLP: &str = "113838";
+const ACQUISITION_PROTOCOL: &str = "125203";
+const TARGET_REGION: &str = "123014";
+
+/// One CT Acquisition (TID 10013) of a dose report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CtAcquisition {
+    pub protocol: Option<String>,
+    pub target_region: Option<String>,
+    /// CT Acquisition Type, e.g. Spiral Acquisition.
+    pub acquisition_type: Option<String>,
+    /// Mean CTDIvol in mGy.
+    pub ctdi_vol: Option<f64>,
+    /// Dose length product in mGy·cm.
+    pub dlp: Option<f64>,
+    pub phantom: Option<String>,
+    /// Scanning length in mm.
+    pub scan_length: Option<f64>,
+}
+
+/// CT dose of an X-Ray Radiation Dose SR (TID 10011).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CtDoseSummary {
+    pub sop_instance_uid: String,
+    pub study_instance_uid: String,
+    pub patient_id: Option<String>,
+    pub study_date: Option<String>,
+    pub acquisitions: Vec<CtAcquisition>,
+    /// CT Dose Length Product Total of the accumulated dose data, in mGy·cm.
+    pub total_dlp: Option<f64>,
+    /// Total Number of Irradiation Events of the accumulated dose data.
+    pub events: Option<u32>,
+}
+
+impl CtDoseSummary {
+    /// Reads the CT dose of a dose report; other reports give `None`.
+    pub fn from_report(report: &StructuredReport, object: &InMemDicomObject) -> Option<Self> {
+        let root = &report.root;
+        if !root.is(DCM, X_RAY_RADIATION_DOSE_REPORT) {
+            return None;
+        }
+        let accumulated = root.child_named(DCM, CT_ACCUMULATED_DOSE_DATA);
+        let acquisitions = root
+            .children_named(DCM, CT_ACQUISITION)
+            .map(CtAcquisition::from_node)
+            .collect::<Vec<_>>();
+        // Projection X-Ray dose reports share the root concept.
+        if accumulated.is_none() && acquisitions.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sop_instance_uid: report.sop_instance_uid.clone(),
+            study_instance_uid: attribute_text(object, "StudyInstanceUID").unwrap_or_default(),
+            patient_id: attribute_text(object, "PatientID"),
+            study_date: attribute_text(object, "StudyDate"),
+            acquisitions,
+            total_dlp: accumulated
+                .and_then(|node| node.child_named(DCM, CT_DOSE_LENGTH_PRODUCT_TOTAL))
+                .and_then(|node| node.numeric())
+                .map(|(value, _)| value),
+            events: accumulated
+                .and_then(|node| node.child_named(DCM, TOTAL_NUMBER_OF_IRRADIATION_EVENTS))
+                .and_then(|node| node.numeric())
+                .map(|(value, _)| value as u32),
+        })
+    }
+
+    /// The reported DLP total, or the sum over the acquisitions without one.
+    pub fn dlp(&self) -> f64 {
+        self.total_dlp.unwrap_or_else(|| {
+            self.acquisitions
+                .iter()
+                .filter_map(|acquisition| acquisition.dlp)
+                .sum()
+        })
+    }
+}
+
+impl CtAcquisition {
+    fn from_node(node: &SrNode) -> Self {
+        let parameters = node.child_named(DCM, CT_ACQUISITION_PARAMETERS);
+        let dose = node.child_named(DCM, CT_DOSE);
+        let number = |parent: Option<&SrNode>, code: &str| {
+            parent?
+                .child_named(DCM, code)?
+                .numeric()
+                .map(|(value, _)| value)
+        };
+        Self {
+            protocol: node
+                .child_named(DCM, ACQUISITION_PROTOCOL)
+                .and_then(text_value),
+            target_region: node.child_named(DCM, TARGET_REGION).and_then(text_value),
+            acquisition_type: node
+                .child_named(DCM, CT_ACQUISITION_TYPE)
+                .and_then(text_value),
+            ctdi_vol: number(dose, MEAN_CTDI_VOL),
+            dlp: number(dose, DLP),
+            phantom: dose
+                .and_then(|dose| dose.child_named(DCM, CTDIW_PHANTOM_TYPE))
+                .and_then(text_value),
+            scan_length: parameters
+                .and_then(|parameters| parameters.child_named(DCM, SCANNING_LENGTH))
+                .and_then(|node| {
+                    let (value, unit) = node.numeric()?;
+                    let scale = match unit.map(|unit| unit.value.as_str()) {
+                        Some("cm") => 10.0,
+                        Some("m") => 1000.0,
+                        _ => 1.0,
+                    };
+                    Some(value * scale)
+                }),
+        }
+    }
+}
+
+/// Dose of every CT dose report loaded for a study.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudyDoseTotal {
+    pub study_instance_uid: String,
+    pub patient_id: Option<String>,
+    pub study_date: Option<String>,
+    pub reports: usize,
+    pub acquisitions: usize,
+    /// Sum of the reports' DLP totals, in mGy·cm.
+    pub dlp: f64,
+    /// Highest CTDIvol of any acquisition, in mGy.
+    pub max_ctdi_vol: Option<f64>,
+}
+
+/// Totals per study, in the order studies first appear.
+pub fn study_totals<'a>(
+    summaries: impl IntoIterator<Item = &'a CtDoseSummary>,
+) -> Vec<StudyDoseTotal> {
+    let mut order = Vec::new();
+    let mut totals: BTreeMap<String, StudyDoseTotal> = BTreeMap::new();
+    for summary in summaries {
+        let total = totals
+            .entry(summary.study_instance_uid.clone())
+            .or_insert_with(|| {
+                order.push(summary.study_instance_uid.clone());
+                StudyDoseTotal {
+                    study_instance_uid: summary.study_instance_uid.clone(),
+                    patient_id: summary.patient_id.clone(),
+                    study_date: summary.study_date.clone(),
+                    reports: 0,
+                    acquisitions: 0,
+                    dlp: 0.0,
+                    max_ctdi_vol: None,
+                }
+            });
+        total.reports += 1;
+        total.acquisitions += summary.acquisitions.len();
+        total.dlp += summary.dlp();
+        for ctdi_vol in summary
+            .acquisitions
+            .iter()
+            .filter_map(|acquisition| acquisition.ctdi_vol)
+        {
+            total.max_ctdi_vol = Some(total.max_ctdi_vol.map_or(ctdi_vol, |max| max.max(ctdi_vol)));
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|study| totals.remove(&study))
+        .collect()
+}
+
+pub const CSV_HEADER: [&str; 12] = [
+    "PatientID",
+    "StudyInstanceUID",
+    "StudyDate",
+    "SOPInstanceUID",
+    "Acquisition",
+    "Protocol",
+    "TargetRegion",
+    "AcquisitionType",
+    "CTDIvol_mGy",
+    "DLP_mGycm",
+    "Phantom",
+    "ScanLength_mm",
+];
+
+/// CSV rows: each acquisition of each report, then a total row per study.
+pub fn csv_rows(summaries: &[&CtDoseSummary]) -> Vec<Vec<String>> {
+    let number = |value: Option<f64>| value.map(|value| format!("{value:.2}")).unwrap_or_default();
+    let mut rows = Vec::new();
+    for summary in summaries {
+        for (index, acquisition) in summary.acquisitions.iter().enumerate() {
+            rows.push(vec![
+                summary.patient_id.clone().unwrap_or_default(),
+                summary.study_instance_uid.clone(),
+                summary.study_date.clone().unwrap_or_default(),
+                summary.sop_instance_uid.clone(),
+                (index + 1).to_string(),
+                acquisition.protocol.clone().unwrap_or_default(),
+                acquisition.target_region.clone().unwrap_or_default(),
+                acquisition.acquisition_type.clone().unwrap_or_default(),
+                number(acquisition.ctdi_vol),
+                number(acquisition.dlp),
+                acquisition.phantom.clone().unwrap_or_default(),
+                number(acquisition.scan_length),
+            ]);
+        }
+    }
+    for total in study_totals(summaries.iter().copied()) {
+        rows.push(vec![
+            total.patient_id.unwrap_or_default(),
+            total.study_instance_uid,
+            total.study_date.unwrap_or_default(),
+            String::new(),
+            String::from("Study total"),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            number(Some(total.dlp)),
+            String::new(),
+            String::new(),
+        ]);
+    }
+    rows
+}
+
+/// TEXT value or code meaning of an item.
+fn text_value(node: &SrNode) -> Option<String> {
+    match &node.value {
+        SrValue::Text(text) => Some(text.clone()),
+        SrValue::Code(Some(code)) => Some(code.meaning.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,387 @@
+use super::attributes::{attribute_f64, attribute_text};
+use super::mesh::Mesh;
+use super::FramePreview;
+use dicom::object::InMemDicomObject;
+use iced::widget::image::Handle;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+const ENCAPSULATED_PDF: &str = "1.2.840.10008.5.1.4.1.1.104.1";
+const ENCAPSULATED_CDA: &str = "1.2.840.10008.5.1.4.1.1.104.2";
+const ENCAPSULATED_STL: &str = "1.2.840.10008.5.1.4.1.1.104.3";
+const ENCAPSULATED_OBJ: &str = "1.2.840.10008.5.1.4.1.1.104.4";
+const ENCAPSULATED_MTL: &str = "1.2.840.10008.5.1.4.1.1.104.5";
+/// Resolution PDF pages are rendered at, in dots per inch.
+const PDF_DPI: u32 = 96;
+/// How long `pdftoppm` may take to render a page before it is killed.
+const PDF_RENDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kind of an encapsulated document, by SOP Class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Pdf,
+    Cda,
+    Stl,
+    Obj,
+    Mtl,
+    Other,
+}
+
+impl DocumentKind {
+    fn from_sop_class(uid: &str) -> Option<Self> {
+        Some(match uid {
+            ENCAPSULATED_PDF => Self::Pdf,
+            ENCAPSULATED_CDA => Self::Cda,
+            ENCAPSULATED_STL => Self::Stl,
+            ENCAPSULATED_OBJ => Self::Obj,
+            ENCAPSULATED_MTL => Self::Mtl,
+            _ => return None,
+        })
+    }
+
+    fn from_mime_type(mime_type: &str) -> Self {
+        match mime_type {
+            "application/pdf" => Self::Pdf,
+            "text/XML" | "text/xml" | "application/xml" => Self::Cda,
+            "model/stl" | "application/sla" => Self::Stl,
+            "model/obj" => Self::Obj,
+            "model/mtl" => Self::Mtl,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pdf => "Encapsulated PDF",
+            Self::Cda => "Encapsulated CDA",
+            Self::Stl => "Encapsulated STL",
+            Self::Obj => "Encapsulated OBJ",
+            Self::Mtl => "Encapsulated MTL",
+            Self::Other => "Encapsulated document",
+        }
+    }
+
+    /// File extension the extracted document is saved with.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Cda => "xml",
+            Self::Stl => "stl",
+            Self::Obj => "obj",
+            Self::Mtl => "mtl",
+            Self::Other => "bin",
+        }
+    }
+}
+
+/// How an encapsulated document is shown in the app.
+#[derive(Debug, Clone)]
+pub enum DocumentPreview {
+    /// A rendered page or view of the document, with a caption.
+    Image(FramePreview, String),
+    Text(String),
+    /// A PDF page still to be rendered by [`EncapsulatedDocument::render_preview`].
+    Pending,
+    /// Why the document cannot be shown; it can still be extracted.
+    Unavailable(String),
+}
+
+/// The document of an Encapsulated PDF, CDA, STL, OBJ or MTL instance.
+#[derive(Debug, Clone)]
+pub struct EncapsulatedDocument {
+    pub kind: DocumentKind,
+    pub mime_type: Option<String>,
+    pub title: Option<String>,
+    pub sop_instance_uid: String,
+    /// The document's bytes, without the padding to an even length.
+    pub data: Vec<u8>,
+    pub preview: DocumentPreview,
+}
+
+impl EncapsulatedDocument {
+    /// Reads the Encapsulated Document of an object and renders its preview,
+    /// except for PDF pages which are left [`DocumentPreview::Pending`]; other
+    /// objects give `None`.
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        let element = object.element_by_name("EncapsulatedDocument").ok()?;
+        let mut data = element.to_bytes().ok()?.into_owned();
+        if let Some(length) = attribute_f64(object, "EncapsulatedDocumentLength") {
+            data.truncate(length as usize);
+        }
+        let mime_type = attribute_text(object, "MIMETypeOfEncapsulatedDocument");
+        let kind = attribute_text(object, "SOPClassUID")
+            .and_then(|uid| DocumentKind::from_sop_class(&uid))
+            .or_else(|| mime_type.as_deref().map(DocumentKind::from_mime_type))
+            .unwrap_or(DocumentKind::Other);
+        let preview = preview(kind, &data).unwrap_or_else(DocumentPreview::Unavailable);
+        Some(Self {
+            kind,
+            mime_type,
+            title: attribute_text(object, "DocumentTitle"),
+            sop_instance_uid: attribute_text(object, "SOPInstanceUID").unwrap_or_default(),
+            data,
+            preview,
+        })
+    }
+
+    /// Renders the first page of a PDF document. This runs an external
+    /// program, so it is kept off the loading path.
+    pub fn render_preview(&self) -> DocumentPreview {
+        match self.kind {
+            DocumentKind::Pdf => {
+                render_pdf_page(&self.data).unwrap_or_else(DocumentPreview::Unavailable)
+            }
+            _ => self.preview.clone(),
+        }
+    }
+
+    /// File name to extract the document to: its title, or else its SOP
+    /// Instance UID, with the extension of its kind.
+    pub fn file_name(&self) -> String {
+        let stem = self
+            .title
+            .as_deref()
+            .map(|title| {
+                title
+                    .chars()
+                    .map(|ch| {
+                        if ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                            ch
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .filter(|stem| !stem.trim_matches('_').is_empty())
+            .unwrap_or_else(|| self.sop_instance_uid.clone());
+        let stem = if stem.is_empty() { "document" } else { &stem };
+        format!("{stem}.{}", self.kind.extension())
+    }
+}
+
+fn preview(kind: DocumentKind, data: &[u8]) -> Result<DocumentPreview, String> {
+    match kind {
+        DocumentKind::Pdf => Ok(DocumentPreview::Pending),
+        DocumentKind::Cda => Ok(DocumentPreview::Text(cda_narrative(
+            &String::from_utf8_lossy(data),
+        ))),
+        DocumentKind::Stl | DocumentKind::Obj => {
+            let mesh = if kind == DocumentKind::Stl {
+                Mesh::from_stl(data)?
+            } else {
+                Mesh::from_obj(data)?
+            };
+            if mesh.triangles.is_empty() {
+                return Err(String::from("The model has no triangles"));
+            }
+            let [x, y, z] = mesh.size();
+            let caption = format!(
+                "{} triangles, {x:.1} × {y:.1} × {z:.1} mm",
+                mesh.triangles.len()
+            );
+            Ok(DocumentPreview::Image(to_preview(mesh.render()), caption))
+        }
+        DocumentKind::Mtl => Ok(DocumentPreview::Text(
+            String::from_utf8_lossy(data)
+                .trim_end_matches('\0')
+                .to_string(),
+        )),
+        DocumentKind::Other => Err(String::from("No preview for this document type")),
+    }
+}
+
+/// Renders the first page with Poppler's `pdftoppm`, when it is installed.
+fn render_pdf_page(data: &[u8]) -> Result<DocumentPreview, String> {
+    let dir = private_temp_dir()?;
+    let stem = dir.join("page");
+    let input = stem.with_extension("pdf");
+    let output = stem.with_extension("png");
+    let rendered = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&input)
+        .and_then(|mut file| file.write_all(data))
+        .map_err(|err| format!("Failed to write the PDF ({err})"))
+        .and_then(|_| run_pdftoppm(&input, &stem))
+        .and_then(|_| {
+            image::open(&output).map_err(|err| format!("Failed to read the rendered page ({err})"))
+        });
+    let _ = std::fs::remove_dir_all(&dir);
+    let page = rendered?.into_rgba8();
+    Ok(DocumentPreview::Image(
+        to_preview(page),
+        String::from("Page 1"),
+    ))
+}
+
+/// Creates a new directory under the system temporary directory that only
+/// the current user can access; fails rather than reuse an existing one.
+fn private_temp_dir() -> Result<PathBuf, String> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let dir = std::env::temp_dir().join(format!(
+        "dicomancer-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    builder
+        .create(&dir)
+        .map_err(|err| format!("Failed to create a temporary directory ({err})"))?;
+    Ok(dir)
+}
+
+/// Runs `pdftoppm`, killing it if it has not finished within
+/// [`PDF_RENDER_TIMEOUT`].
+fn run_pdftoppm(input: &Path, output_stem: &Path) -> Result<(), String> {
+    let mut child = Command::new("pdftoppm")
+        .args(["-png", "-singlefile", "-f", "1", "-l", "1", "-r"])
+        .arg(PDF_DPI.to_string())
+        .arg(input)
+        .arg(output_stem)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => String::from(
+                "Install Poppler (pdftoppm) to preview PDF pages; the document can still be saved",
+            ),
+            _ => format!("Failed to run pdftoppm ({err})"),
+        })?;
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < PDF_RENDER_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(20))
+            }
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "pdftoppm did not render the PDF within {} s",
+                    PDF_RENDER_TIMEOUT.as_secs()
+                ));
+            }
+            Err(err) => return Err(format!("Failed to wait for pdftoppm ({err})")),
+        }
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("pdftoppm could not render the PDF ({status})"))
+    }
+}
+
+fn to_preview(image: image::RgbaImage) -> FramePreview {
+    let (columns, rows) = image.dimensions();
+    FramePreview {
+        handle: Handle::from_rgba(columns, rows, image.into_raw()),
+        columns,
+        rows,
+    }
+}
+
+/// Human-readable text of a CDA document: the character data of its XML,
+/// with a line break after titles, paragraphs, list items and table rows.
+fn cda_narrative(xml: &str) -> String {
+    const BLOCKS: [&str; 8] = [
+        "title",
+        "paragraph",
+        "br",
+        "item",
+        "tr",
+        "section",
+        "caption",
+        "component",
+    ];
+    let mut text = String::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").unwrap_or(body.len());
+            text.push_str(&body[..end]);
+            rest = body.get(end + 3..).unwrap_or_default();
+            continue;
+        }
+        let close = if rest.starts_with("<!--") { "-->" } else { ">" };
+        let end = rest.find(close).map_or(rest.len(), |end| end + close.len());
+        let name = rest[1..end]
+            .trim_start_matches('/')
+            .split(|ch: char| ch.is_whitespace() || ch == '>' || ch == '/')
+            .next()
+            .unwrap_or_default();
+        // Tags may carry a namespace prefix, such as hl7:paragraph.
+        let name = name.rsplit(':').next().unwrap_or_default();
+        if BLOCKS.contains(&name) {
+            text.push('\n');
+        } else if name == "td" || name == "th" {
+            text.push('\t');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(&decode_entities(rest));
+
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let line = line
+            .split('\t')
+            .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|cell| !cell.is_empty())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#')?.parse().ok())
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
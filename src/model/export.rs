@@ -0,0 +1,408 @@
+use super::annotation::{AnnotationContext, CornerTemplates};
+use super::attributes::attribute_text;
+use super::{ColorMap, FrameData, Orientation, OverlayStyle, ViewTransform, Window};
+use crate::burn_in::BurnIn;
+use crate::image_pipeline::FrameImagePipeline;
+use crate::utils::render_template;
+use dicom::object::{DefaultDicomObject, InMemDicomObject};
+use dicom::pixeldata::DecodedPixelData;
+use iced::{Point, Rectangle, Size};
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, ImageFormat, Luma, RgbaImage};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const DEFAULT_FILE_TEMPLATE: &str = "{SeriesNumber}_{InstanceNumber:04}.png";
+
+/// Image file formats frames can be exported to, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    Tiff,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ExportFormat::Png),
+            "jpg" | "jpeg" => Some(ExportFormat::Jpeg),
+            "tif" | "tiff" => Some(ExportFormat::Tiff),
+            _ => None,
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            ExportFormat::Png => ImageFormat::Png,
+            ExportFormat::Jpeg => ImageFormat::Jpeg,
+            ExportFormat::Tiff => ImageFormat::Tiff,
+        }
+    }
+}
+
+/// Whether exported frames look like the viewer or hold the stored values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportMode {
+    /// Windowed, colour mapped, oriented and with visible overlays burned in.
+    #[default]
+    Displayed,
+    /// Stored values of monochrome frames as 16-bit grayscale, without VOI.
+    Raw16,
+}
+
+impl ExportMode {
+    pub const ALL: [ExportMode; 2] = [ExportMode::Displayed, ExportMode::Raw16];
+}
+
+impl fmt::Display for ExportMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportMode::Displayed => "As displayed",
+            ExportMode::Raw16 => "Raw 16-bit",
+        })
+    }
+}
+
+/// Which frames an export covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    /// The displayed frame, saved to a file the user picks.
+    Frame,
+    /// Every frame of the displayed object, into a folder.
+    Instance,
+    /// Every frame of every loaded instance in the displayed series, into a folder.
+    Series,
+}
+
+/// Size of exported images relative to the stored frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportScale {
+    Half,
+    #[default]
+    Original,
+    Double,
+    Quadruple,
+}
+
+impl ExportScale {
+    pub const ALL: [ExportScale; 4] = [
+        ExportScale::Half,
+        ExportScale::Original,
+        ExportScale::Double,
+        ExportScale::Quadruple,
+    ];
+
+    pub fn factor(self) -> f32 {
+        match self {
+            ExportScale::Half => 0.5,
+            ExportScale::Original => 1.0,
+            ExportScale::Double => 2.0,
+            ExportScale::Quadruple => 4.0,
+        }
+    }
+
+    fn apply(self, image: RgbaImage) -> RgbaImage {
+        let factor = self.factor();
+        if factor == 1.0 {
+            return image;
+        }
+        let width = ((image.width() as f32 * factor).round() as u32).max(1);
+        let height = ((image.height() as f32 * factor).round() as u32).max(1);
+        // Keep enlarged pixels sharp, as the viewer draws them.
+        let filter = if factor > 1.0 {
+            FilterType::Nearest
+        } else {
+            FilterType::Triangle
+        };
+        imageops::resize(&image, width, height, filter)
+    }
+}
+
+impl fmt::Display for ExportScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", (self.factor() * 100.0) as u32)
+    }
+}
+
+/// Display state exported frames are rendered with in [`ExportMode::Displayed`].
+#[derive(Debug, Clone, Default)]
+pub struct DisplaySettings {
+    /// VOI window, or each frame's default window when `None`.
+    pub window: Option<Window>,
+    pub color_map: ColorMap,
+    pub transform: ViewTransform,
+    /// Region of the image shown, in image pixel coordinates, or the whole
+    /// image when `None`.
+    pub displayed_area: Option<Rectangle>,
+    /// Overlay styles by group; groups without a style use their default.
+    pub overlays: BTreeMap<u16, OverlayStyle>,
+    pub scale: ExportScale,
+    /// Corner text burned in with the orientation labels and scale bar, when
+    /// annotations are shown.
+    pub annotations: Option<CornerTemplates>,
+}
+
+/// A rendered frame ready to be encoded.
+#[derive(Debug, Clone)]
+pub enum ExportImage {
+    Rgba(RgbaImage),
+    Gray16(ImageBuffer<Luma<u16>, Vec<u16>>),
+}
+
+impl ExportImage {
+    /// Writes the image in the format named by the extension of `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let format = ExportFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "{}: unknown image format, use .png, .jpg or .tiff",
+                path.display()
+            )
+        })?;
+        let result = match (self, format) {
+            // JPEG has no alpha channel.
+            (ExportImage::Rgba(image), ExportFormat::Jpeg) => {
+                image::DynamicImage::ImageRgba8(image.clone())
+                    .into_rgb8()
+                    .save_with_format(path, ImageFormat::Jpeg)
+            }
+            (ExportImage::Rgba(image), format) => {
+                image.save_with_format(path, format.image_format())
+            }
+            (ExportImage::Gray16(_), ExportFormat::Jpeg) => {
+                return Err(format!(
+                    "{}: JPEG cannot hold 16-bit samples, use PNG or TIFF",
+                    path.display()
+                ))
+            }
+            (ExportImage::Gray16(image), format) => {
+                image.save_with_format(path, format.image_format())
+            }
+        };
+        result.map_err(|err| format!("{}: failed to write image ({err})", path.display()))
+    }
+}
+
+/// Renders one frame for export.
+pub fn render_export(
+    object: &DefaultDicomObject,
+    pixels: &DecodedPixelData<'_>,
+    frame_index: u32,
+    mode: ExportMode,
+    display: &DisplaySettings,
+) -> Result<ExportImage, String> {
+    match mode {
+        ExportMode::Displayed => {
+            render_annotated(object, pixels, frame_index, display).map(ExportImage::Rgba)
+        }
+        ExportMode::Raw16 => {
+            FrameImagePipeline::render_raw16(object, pixels, frame_index).map(ExportImage::Gray16)
+        }
+    }
+}
+
+/// Renders one frame as displayed, cropped to the displayed area, scaled and
+/// with annotations burned in.
+pub fn render_annotated(
+    object: &DefaultDicomObject,
+    pixels: &DecodedPixelData<'_>,
+    frame_index: u32,
+    display: &DisplaySettings,
+) -> Result<RgbaImage, String> {
+    let (image, frame) =
+        FrameImagePipeline::render_displayed(object, pixels, frame_index, display)?;
+    let image = match display.displayed_area {
+        Some(area) => crop_to_area(&image, area, display.transform),
+        None => image,
+    };
+    let mut image = display.scale.apply(image);
+    if let Some(templates) = &display.annotations {
+        burn_in(
+            &mut image,
+            templates,
+            object,
+            frame.as_ref(),
+            frame_index,
+            pixels.number_of_frames(),
+            display,
+        );
+    }
+    Ok(image)
+}
+
+/// Crops an oriented image to `area`, given in the coordinates of the image
+/// before `transform`. Parts of the area outside the image are dropped.
+fn crop_to_area(image: &RgbaImage, area: Rectangle, transform: ViewTransform) -> RgbaImage {
+    let size = transform.display_size(Size::new(image.width() as f32, image.height() as f32));
+    let a = transform.to_display(area.position(), size);
+    let b = transform.to_display(Point::new(area.x + area.width, area.y + area.height), size);
+    let clamp_x = |x: f32| x.round().clamp(0.0, image.width() as f32) as u32;
+    let clamp_y = |y: f32| y.round().clamp(0.0, image.height() as f32) as u32;
+    let (left, right) = (clamp_x(a.x.min(b.x)), clamp_x(a.x.max(b.x)));
+    let (top, bottom) = (clamp_y(a.y.min(b.y)), clamp_y(a.y.max(b.y)));
+    if right <= left || bottom <= top {
+        return image.clone();
+    }
+    imageops::crop_imm(image, left, top, right - left, bottom - top).to_image()
+}
+
+fn burn_in(
+    image: &mut RgbaImage,
+    templates: &CornerTemplates,
+    object: &InMemDicomObject,
+    frame: Option<&FrameData>,
+    frame_index: u32,
+    frame_count: u32,
+    display: &DisplaySettings,
+) {
+    let orientation = match frame.and_then(|frame| frame.plane) {
+        Some(plane) => Orientation::from_plane(&plane),
+        None => Orientation::from_object(object),
+    };
+    let zoom = display.scale.factor();
+    let mm_per_pixel = frame
+        .and_then(|frame| frame.calibration)
+        .map(|calibration| {
+            let spacing = if display.transform.swaps_axes() {
+                calibration.spacing[0]
+            } else {
+                calibration.spacing[1]
+            };
+            spacing / zoom as f64
+        });
+    BurnIn {
+        templates,
+        context: AnnotationContext {
+            object,
+            frame_index,
+            frame_count,
+            window: display
+                .window
+                .or_else(|| frame.and_then(|frame| frame.default_window)),
+            zoom,
+        },
+        edge_labels: orientation.map(|orientation| orientation.edge_labels(display.transform)),
+        mm_per_pixel,
+    }
+    .draw(image);
+}
+
+/// File name for one exported frame from a template such as
+/// `{SeriesNumber}_{InstanceNumber:04}.png`.
+///
+/// Placeholders name attribute aliases, plus `{Frame}` (1-based) and
+/// `{FrameCount}`. Frames of multi-frame objects get a `_{Frame:04}` suffix
+/// when the template does not mention `{Frame}`, and `.png` is added when
+/// the template has no image extension.
+pub fn file_name(
+    template: &str,
+    object: &InMemDicomObject,
+    frame_index: u32,
+    frame_count: u32,
+) -> String {
+    let template = if template.trim().is_empty() {
+        DEFAULT_FILE_TEMPLATE
+    } else {
+        template.trim()
+    };
+    let mut template = template.to_string();
+    if frame_count > 1 && !template.contains("{Frame") {
+        let stem_end = match template.rfind('.') {
+            Some(dot) if !template[dot..].contains('}') => dot,
+            _ => template.len(),
+        };
+        template.insert_str(stem_end, "_{Frame:04}");
+    }
+
+    let mut name = render_template(&template, |name| {
+        let value = match name {
+            "Frame" => Some((frame_index + 1).to_string()),
+            "FrameCount" => Some(frame_count.to_string()),
+            alias => attribute_text(object, alias),
+        };
+        value.map(|value| sanitize(&value))
+    });
+    if ExportFormat::from_path(Path::new(&name)).is_none() {
+        name.push_str(".png");
+    }
+    name
+}
+
+/// One object whose frames are exported in a batch.
+#[derive(Debug, Clone)]
+pub struct ExportSource {
+    pub object: Arc<DefaultDicomObject>,
+    pub pixels: Arc<DecodedPixelData<'static>>,
+}
+
+/// Exports every frame of `sources` into `directory`, naming files from
+/// `template` and adding a counter to names that would collide. Returns the
+/// number of files written.
+pub fn export_batch(
+    sources: &[ExportSource],
+    directory: &Path,
+    template: &str,
+    mode: ExportMode,
+    display: &DisplaySettings,
+) -> Result<usize, String> {
+    let mut used = BTreeSet::new();
+    let mut written = 0;
+    for source in sources {
+        let frame_count = source.pixels.number_of_frames();
+        for frame_index in 0..frame_count {
+            let name = file_name(template, &source.object, frame_index, frame_count);
+            let path = unique_path(directory, &name, &mut used);
+            // Templates may place files in subfolders, e.g. `{SeriesNumber}/{InstanceNumber}.png`.
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|err| {
+                    format!("{}: failed to create folder ({err})", parent.display())
+                })?;
+            }
+            let image = render_export(&source.object, &source.pixels, frame_index, mode, display)
+                .map_err(|err| format!("{}: {err}", path.display()))?;
+            image.save(&path)?;
+            written += 1;
+        }
+    }
+    log::info!("Exported {written} image(s) to {}", directory.display());
+    Ok(written)
+}
+
+fn unique_path(directory: &Path, name: &str, used: &mut BTreeSet<PathBuf>) -> PathBuf {
+    let path = directory.join(name);
+    if used.insert(path.clone()) {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("");
+    (2..)
+        .map(|counter| path.with_file_name(format!("{stem}_{counter}.{extension}")))
+        .find(|candidate| used.insert(candidate.clone()))
+        .expect("unbounded counter")
+}
+
+/// Keeps attribute values from introducing directories or characters that
+/// are invalid in file names.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || matches!(ch, '.' | '-' | '_' | ' ') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('.')
+        .to_string()
+}
@@ -0,0 +1,145 @@
+use super::attributes::{attribute_f64, attribute_text};
+use super::geometry::ImagePlane;
+use super::measurement::Calibration;
+use super::overlay::OverlayBitmap;
+use super::palette::PaletteLut;
+use super::suv;
+use super::ultrasound::{self, RegionReading, UltrasoundRegion};
+use super::voi::Window;
+use dicom::object::InMemDicomObject;
+use iced::Point;
+use std::sync::Arc;
+
+/// Linear Modality LUT taking stored values to modality units (e.g. HU).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rescale {
+    pub slope: f64,
+    pub intercept: f64,
+    pub unit: Option<String>,
+    /// Body-weight SUV per modality unit, for PET images in Bq/ml.
+    pub suv_factor: Option<f64>,
+}
+
+impl Rescale {
+    pub fn from_object(object: &InMemDicomObject) -> Self {
+        let unit = attribute_text(object, "RescaleType").or_else(|| {
+            (attribute_text(object, "Modality").as_deref() == Some("CT")).then(|| "HU".to_string())
+        });
+
+        Self {
+            slope: attribute_f64(object, "RescaleSlope").unwrap_or(1.0),
+            intercept: attribute_f64(object, "RescaleIntercept").unwrap_or(0.0),
+            unit,
+            suv_factor: suv::body_weight_factor(object),
+        }
+    }
+
+    pub fn apply(&self, stored: i32) -> f64 {
+        stored as f64 * self.slope + self.intercept
+    }
+
+    /// The same rescale producing body-weight SUV, when it can be computed.
+    pub fn to_suv(&self) -> Option<Self> {
+        let factor = self.suv_factor?;
+        Some(Self {
+            slope: self.slope * factor,
+            intercept: self.intercept * factor,
+            unit: Some(suv::SUV_UNIT.to_string()),
+            suv_factor: Some(1.0),
+        })
+    }
+}
+
+/// Stored sample values of one decoded frame, kept next to the preview so the
+/// viewer can read real values instead of the 8-bit rendering.
+#[derive(Debug, Clone)]
+pub struct FrameData {
+    pub rows: u32,
+    pub columns: u32,
+    pub samples_per_pixel: u16,
+    /// Stored values, pixel-interleaved when there is more than one sample.
+    pub stored: Vec<i32>,
+    pub rescale: Rescale,
+    pub plane: Option<ImagePlane>,
+    pub calibration: Option<Calibration>,
+    /// Sequence of Ultrasound Regions, which calibrate ultrasound images in
+    /// place of Pixel Spacing.
+    pub ultrasound_regions: Vec<UltrasoundRegion>,
+    /// MONOCHROME1: minimum values are displayed white.
+    pub invert: bool,
+    pub default_window: Option<Window>,
+    /// Overlay planes that apply to this frame.
+    pub overlays: Vec<OverlayBitmap>,
+    /// Palette Color LUT of PALETTE COLOR images, or a Supplemental Palette.
+    pub palette: Option<Arc<PaletteLut>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelProbe {
+    pub row: u32,
+    pub column: u32,
+    pub stored: Vec<i32>,
+    pub modality: Option<f64>,
+    /// Body-weight SUV of PET images.
+    pub suv: Option<f64>,
+    pub patient: Option<[f64; 3]>,
+    /// Physical values of the ultrasound region under the pixel.
+    pub region: Option<RegionReading>,
+}
+
+impl FrameData {
+    pub fn is_monochrome(&self) -> bool {
+        self.samples_per_pixel == 1
+    }
+
+    pub fn modality_values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.stored
+            .iter()
+            .step_by(self.samples_per_pixel.max(1) as usize)
+            .map(|&stored| self.rescale.apply(stored))
+    }
+
+    pub fn modality_range(&self) -> Option<(f64, f64)> {
+        if !self.is_monochrome() {
+            return None;
+        }
+        self.modality_values().fold(None, |acc, value| match acc {
+            None => Some((value, value)),
+            Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+        })
+    }
+
+    pub fn stored_at(&self, row: u32, column: u32) -> Option<&[i32]> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+        let samples = self.samples_per_pixel as usize;
+        let start = (row as usize * self.columns as usize + column as usize) * samples;
+        self.stored.get(start..start + samples)
+    }
+
+    pub fn probe(&self, row: u32, column: u32) -> Option<PixelProbe> {
+        let stored = self.stored_at(row, column)?.to_vec();
+        let modality = self.is_monochrome().then(|| self.rescale.apply(stored[0]));
+        let suv = modality
+            .zip(self.rescale.suv_factor)
+            .map(|(value, factor)| value * factor);
+        let patient = self
+            .plane
+            .map(|plane| plane.patient_position(row as f64, column as f64));
+
+        let center = Point::new(column as f32 + 0.5, row as f32 + 0.5);
+        let region = ultrasound::region_containing(&self.ultrasound_regions, &[center])
+            .map(|region| region.reading(column, row));
+
+        Some(PixelProbe {
+            row,
+            column,
+            stored,
+            modality,
+            suv,
+            patient,
+            region,
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use super::attributes::sequence_items;
+use super::MetadataRow;
+use dicom::core::dictionary::DataDictionary;
+use dicom::core::header::Header;
+use dicom::core::Tag;
+use dicom::dictionary_std::{tags, StandardDataDictionary};
+use dicom::object::InMemDicomObject;
+use std::collections::BTreeMap;
+
+/// A functional group macro of an enhanced multi-frame object as it applies
+/// to one frame, such as Plane Position or Frame VOI LUT.
+#[derive(Debug, Clone)]
+pub struct FunctionalGroup {
+    /// Macro name from its sequence keyword, e.g. "Plane Position".
+    pub name: String,
+    /// Whether the macro comes from the Shared Functional Groups Sequence
+    /// rather than the frame's item of the Per-Frame Functional Groups Sequence.
+    pub shared: bool,
+    pub rows: Vec<MetadataRow>,
+}
+
+/// Macro items that apply to a frame by sequence tag: the frame's own item
+/// replaces the shared one (PS3.3 C.7.6.16).
+fn frame_macros(
+    object: &InMemDicomObject,
+    frame_index: u32,
+) -> BTreeMap<Tag, (bool, &InMemDicomObject)> {
+    let mut macros = BTreeMap::new();
+    let shared = sequence_items(object, "SharedFunctionalGroupsSequence").first();
+    let per_frame =
+        sequence_items(object, "PerFrameFunctionalGroupsSequence").get(frame_index as usize);
+    for (is_shared, group) in [(true, shared), (false, per_frame)] {
+        for element in group.into_iter().flat_map(|group| group.iter()) {
+            if let Some(item) = element.items().and_then(|items| items.first()) {
+                macros.insert(element.tag(), (is_shared, item));
+            }
+        }
+    }
+    macros
+}
+
+/// The Shared and Per-Frame Functional Groups of a frame, one entry per
+/// macro; empty for objects without functional groups.
+pub fn frame_groups(object: &InMemDicomObject, frame_index: u32) -> Vec<FunctionalGroup> {
+    frame_macros(object, frame_index)
+        .into_iter()
+        .map(|(tag, (shared, item))| FunctionalGroup {
+            name: macro_name(tag),
+            shared,
+            rows: item.iter().map(MetadataRow::from_element).collect(),
+        })
+        .collect()
+}
+
+/// The object's attributes with the frame's functional group macros merged
+/// in at the top level, so that readers of Image Position, Pixel Spacing,
+/// Rescale or Window attributes see the frame's values. `None` for objects
+/// without functional groups.
+pub fn frame_object(object: &InMemDicomObject, frame_index: u32) -> Option<InMemDicomObject> {
+    let macros = frame_macros(object, frame_index);
+    if macros.is_empty() {
+        return None;
+    }
+    let mut merged = InMemDicomObject::from_element_iter(
+        object
+            .iter()
+            .filter(|element| {
+                !matches!(
+                    element.tag(),
+                    tags::PIXEL_DATA
+                        | tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE
+                        | tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE
+                )
+            })
+            .cloned(),
+    );
+    for (_, item) in macros.values() {
+        for element in item.iter() {
+            merged.put(element.clone());
+        }
+    }
+    Some(merged)
+}
+
+/// Whether frames carry their own item of a macro, e.g. a VOI window per frame.
+pub fn varies_per_frame(object: &InMemDicomObject, sequence: &str) -> bool {
+    sequence_items(object, "PerFrameFunctionalGroupsSequence")
+        .iter()
+        .any(|group| !sequence_items(group, sequence).is_empty())
+}
+
+/// "PlanePositionSequence" becomes "Plane Position".
+fn macro_name(tag: Tag) -> String {
+    let Some(entry) = StandardDataDictionary.by_tag(tag) else {
+        return format!("({:04X},{:04X})", tag.group(), tag.element());
+    };
+    let keyword = entry.alias.strip_suffix("Sequence").unwrap_or(entry.alias);
+    let mut name = String::new();
+    let mut previous: Option<char> = None;
+    for (index, character) in keyword.char_indices() {
+        let next_lower = keyword[index + character.len_utf8()..]
+            .chars()
+            .next()
+            .is_some_and(char::is_lowercase);
+        // Break before a capital that starts a word, keeping acronyms such as
+        // "VOI" together.
+        if character.is_uppercase()
+            && previous.is_some_and(|previous| previous.is_lowercase() || next_lower)
+        {
+            name.push(' ');
+        }
+        name.push(character);
+        previous = Some(character);
+    }
+    name
+}
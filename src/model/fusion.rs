@@ -0,0 +1,130 @@
+use super::attributes::attribute_text;
+use super::suv::SUV_UNIT;
+use super::thumbnail::group_series;
+use super::{ColorMap, DicomEntry, DicomView, Volume, Window};
+use crate::image_pipeline::FrameImagePipeline;
+use dicom::object::DefaultDicomObject;
+use dicom::pixeldata::DecodedPixelData;
+use std::fmt;
+use std::sync::Arc;
+
+pub const DEFAULT_FUSION_OPACITY: f32 = 0.5;
+/// Upper end of the initial PET window when the volume is in SUV.
+const DEFAULT_SUV_UPPER: f64 = 6.0;
+
+/// A PET series that can be fused onto the displayed image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FusionCandidate {
+    pub series_instance_uid: String,
+    pub label: String,
+}
+
+impl fmt::Display for FusionCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// PET series resampled onto the displayed slice in a pseudocolor map.
+#[derive(Debug, Clone)]
+pub struct Fusion {
+    pub series_instance_uid: String,
+    pub frame_of_reference: String,
+    /// `None` while the PET volume is being assembled.
+    pub volume: Option<Arc<Volume>>,
+    pub color_map: ColorMap,
+    pub opacity: f32,
+    /// PET window, in SUV when the volume could be converted.
+    pub window: Window,
+    /// Largest value of the volume, bounding the window.
+    pub max_value: f64,
+}
+
+impl Fusion {
+    pub fn new(series_instance_uid: String, frame_of_reference: String) -> Self {
+        Self {
+            series_instance_uid,
+            frame_of_reference,
+            volume: None,
+            color_map: ColorMap::HotIron,
+            opacity: DEFAULT_FUSION_OPACITY,
+            window: Window::from_bounds(0.0, DEFAULT_SUV_UPPER),
+            max_value: DEFAULT_SUV_UPPER,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: Arc<Volume>) {
+        self.max_value = volume.values.iter().copied().fold(0.0f32, f32::max) as f64;
+        if volume.unit.as_deref() != Some(SUV_UNIT) {
+            self.window = Window::from_bounds(0.0, volume.default_window.upper());
+        }
+        self.volume = Some(volume);
+    }
+
+    /// Whether the volume holds body-weight SUV rather than activity values.
+    pub fn is_suv(&self) -> bool {
+        self.volume
+            .as_ref()
+            .is_some_and(|volume| volume.unit.as_deref() == Some(SUV_UNIT))
+    }
+
+    /// Whether the fused PET shares the frame of reference of `view`.
+    pub fn applies_to(&self, view: &DicomView) -> bool {
+        attribute_text(&view.source, "FrameOfReferenceUID").as_deref()
+            == Some(self.frame_of_reference.as_str())
+    }
+}
+
+/// PET series sharing the frame of reference of `displayed`, unless it is
+/// PET itself.
+pub fn candidates(entries: &[DicomEntry], displayed: &DicomEntry) -> Vec<FusionCandidate> {
+    let object = &displayed.view.source;
+    if attribute_text(object, "Modality").as_deref() == Some("PT") {
+        return Vec::new();
+    }
+    let Some(frame_of_reference) = attribute_text(object, "FrameOfReferenceUID") else {
+        return Vec::new();
+    };
+    group_series(entries)
+        .into_iter()
+        .filter(|group| {
+            let object = &entries[group.representative()].view.source;
+            attribute_text(object, "Modality").as_deref() == Some("PT")
+                && attribute_text(object, "FrameOfReferenceUID").as_deref()
+                    == Some(frame_of_reference.as_str())
+        })
+        .map(|group| FusionCandidate {
+            series_instance_uid: group.series_instance_uid,
+            label: group.label,
+        })
+        .collect()
+}
+
+/// Stacks a PET series into a volume, converted to body-weight SUV when every
+/// slice carries what SUV needs.
+pub fn assemble_pet(
+    instances: Vec<(Arc<DefaultDicomObject>, Arc<DecodedPixelData<'static>>)>,
+) -> Result<Volume, String> {
+    let mut slices = instances
+        .iter()
+        .map(|(object, pixels)| {
+            if pixels.number_of_frames() > 1 {
+                return Err("Multi-frame PET instances cannot be fused".to_string());
+            }
+            FrameImagePipeline::frame_data(object, pixels, 0)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let suv = slices
+        .iter()
+        .map(|slice| slice.rescale.to_suv())
+        .collect::<Option<Vec<_>>>();
+    match suv {
+        Some(rescales) => {
+            for (slice, rescale) in slices.iter_mut().zip(rescales) {
+                slice.rescale = rescale;
+            }
+        }
+        None => log::info!("PET series lacks SUV information, fusing activity values"),
+    }
+    Volume::from_slices(slices)
+}
@@ -0,0 +1,88 @@
+use super::attributes::attribute_f64s;
+use dicom::object::InMemDicomObject;
+
+/// Position and orientation of a frame in the patient coordinate system,
+/// as described by the Image Plane module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImagePlane {
+    pub position: [f64; 3],
+    pub row_direction: [f64; 3],
+    pub column_direction: [f64; 3],
+    /// Spacing between rows, then between columns, in millimetres.
+    pub pixel_spacing: [f64; 2],
+}
+
+impl ImagePlane {
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        let position = attribute_f64s(object, "ImagePositionPatient")?;
+        let orientation = attribute_f64s(object, "ImageOrientationPatient")?;
+        let spacing = attribute_f64s(object, "PixelSpacing")?;
+        if position.len() < 3 || orientation.len() < 6 || spacing.len() < 2 {
+            return None;
+        }
+
+        Some(Self {
+            position: [position[0], position[1], position[2]],
+            row_direction: [orientation[0], orientation[1], orientation[2]],
+            column_direction: [orientation[3], orientation[4], orientation[5]],
+            pixel_spacing: [spacing[0], spacing[1]],
+        })
+    }
+
+    /// Patient-space coordinate (mm) of the centre of the pixel at `row`, `column`.
+    pub fn patient_position(&self, row: f64, column: f64) -> [f64; 3] {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        std::array::from_fn(|axis| {
+            self.position[axis]
+                + self.row_direction[axis] * column_spacing * column
+                + self.column_direction[axis] * row_spacing * row
+        })
+    }
+
+    /// Inverse of [`Self::patient_position`]: the fractional row and column a
+    /// patient-space point projects to, and its signed distance (mm) from the
+    /// plane along the normal.
+    pub fn project(&self, point: [f64; 3]) -> (f64, f64, f64) {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let offset = sub(point, self.position);
+        let normal = normalize(cross(self.row_direction, self.column_direction));
+        (
+            dot(offset, self.column_direction) / row_spacing,
+            dot(offset, self.row_direction) / column_spacing,
+            dot(offset, normal),
+        )
+    }
+}
+
+pub fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn scale(a: [f64; 3], factor: f64) -> [f64; 3] {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+pub fn normalize(a: [f64; 3]) -> [f64; 3] {
+    let length = dot(a, a).sqrt();
+    if length <= f64::EPSILON {
+        a
+    } else {
+        scale(a, 1.0 / length)
+    }
+}
@@ -0,0 +1,68 @@
+use super::measurement::{roi_values, RoiStats};
+use super::{FrameData, Shape};
+
+const BIN_COUNT: usize = 256;
+
+/// What the histogram is computed over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistogramScope {
+    #[default]
+    Frame,
+    /// The ROI measurement with the given id.
+    Roi(usize),
+}
+
+/// Distribution of modality values over a frame or ROI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bins: Vec<u32>,
+    /// Lower edge of the first bin.
+    pub min: f64,
+    /// Upper edge of the last bin.
+    pub max: f64,
+    pub stats: RoiStats,
+    pub median: f64,
+}
+
+impl Histogram {
+    pub fn for_frame(frame: &FrameData) -> Option<Self> {
+        if !frame.is_monochrome() {
+            return None;
+        }
+        Self::from_values(frame.modality_values().collect())
+    }
+
+    pub fn for_roi(frame: &FrameData, shape: &Shape) -> Option<Self> {
+        if !frame.is_monochrome() {
+            return None;
+        }
+        Self::from_values(roi_values(shape, frame))
+    }
+
+    fn from_values(mut values: Vec<f64>) -> Option<Self> {
+        let stats = RoiStats::from_values(&values)?;
+        values.sort_by(f64::total_cmp);
+        let middle = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) {
+            (values[middle - 1] + values[middle]) / 2.0
+        } else {
+            values[middle]
+        };
+
+        let (min, max) = (stats.min, stats.max.max(stats.min + 1.0));
+        let bin_width = (max - min) / BIN_COUNT as f64;
+        let mut bins = vec![0u32; BIN_COUNT];
+        for value in values {
+            let index = ((value - min) / bin_width) as usize;
+            bins[index.min(BIN_COUNT - 1)] += 1;
+        }
+
+        Some(Self {
+            bins,
+            min,
+            max,
+            stats,
+            median,
+        })
+    }
+}
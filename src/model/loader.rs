@@ -1,20 +1,42 @@
-use super::{DicomEntry, DicomView, MetadataRow};
-use crate::image_pipeline::FrameImagePipeline;
-use crate::utils::{format_tag, value_to_string};
-use dicom::core::dictionary::DataDictionary;
+use super::attributes::attribute_text;
+use super::functional_groups;
+use super::{
+    ColorMap, CtDoseSummary, DicomEntry, DicomView, DocumentPreview, EncapsulatedDocument,
+    MetadataRow, OverlayPlane, PresentationState, Segmentation, StructureSet, StructuredReport,
+    ViewTransform, Waveform,
+};
+use crate::codecs::{self, deflate};
+use crate::image_pipeline::{FrameImagePipeline, RenderedFrame};
 use dicom::core::header::Header;
-use dicom::dictionary_std::StandardDataDictionary;
+use dicom::dictionary_std::tags;
 use dicom::object::{open_file, DefaultDicomObject};
-use iced::widget::image::Handle;
+use dicom::pixeldata::DecodedPixelData;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub fn load_dicom(path: PathBuf) -> Result<DicomEntry, String> {
     log::info!("Loading DICOM file: {}", path.display());
-    let object = open_file(&path).map_err(|err| {
+    let opened = match deflate::open_deflated(&path) {
+        Ok(Some(object)) => Ok((
+            object,
+            deflate::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+        )),
+        Ok(None) => open_file(&path)
+            .map(|object| {
+                let transfer_syntax = object.meta().transfer_syntax().to_string();
+                (object, transfer_syntax)
+            })
+            .map_err(|err| err.to_string()),
+        Err(err) => Err(err),
+    };
+    let (object, transfer_syntax) = opened.map_err(|err| {
         let message = format!("{}: failed to open DICOM file ({err})", path.display());
         log::error!("{message}");
         message
     })?;
+    let transfer_syntax = transfer_syntax
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '\0')
+        .to_string();
 
     let patient_id = attribute_text(&object, "PatientID");
     let study_uid = attribute_text(&object, "StudyInstanceUID");
@@ -23,30 +45,90 @@ pub fn load_dicom(path: PathBuf) -> Result<DicomEntry, String> {
 
     let mut metadata = Vec::new();
     for element in object.iter() {
-        let tag = element.tag();
-        let tag_text = format_tag(tag);
-        let alias = StandardDataDictionary
-            .by_tag(tag)
-            .map(|entry| entry.alias)
-            .unwrap_or("Unknown")
-            .to_string();
-        let vr = element.vr();
-        let value = value_to_string(element.value(), vr);
+        let mut row = MetadataRow::from_element(element);
+        if element.tag() == tags::ENCAPSULATED_DOCUMENT {
+            if let Some(mime_type) = attribute_text(&object, "MIMETypeOfEncapsulatedDocument") {
+                let length = element
+                    .value()
+                    .primitive()
+                    .map_or(0, |value| value.calculate_byte_len());
+                row.value = format!("{mime_type} document ({length} bytes)");
+            }
+        }
+        metadata.push(row);
+    }
+
+    let (pixels, mut pixel_error) = match decode_pixels(&object) {
+        Ok(pixels) => (pixels, None),
+        Err(err) => (None, Some(err)),
+    };
+    let (image, frame) = match pixels
+        .as_deref()
+        .map(|pixels| extract_frame(&object, pixels))
+    {
+        Some(Ok(RenderedFrame { preview, data })) => (Some(preview), data),
+        Some(Err(err)) => {
+            pixel_error = Some(err);
+            (None, None)
+        }
+        None => (None, None),
+    };
 
-        metadata.push(MetadataRow {
-            tag: tag_text,
-            vr: vr.to_string().to_owned(),
-            alias,
-            value,
-        });
+    let segmentation = match Segmentation::from_object(&object, pixels.as_deref()) {
+        Ok(segmentation) => segmentation.map(Arc::new),
+        Err(err) => {
+            log::warn!("{}: failed to read segmentation ({err})", path.display());
+            None
+        }
+    };
+
+    let structured_report = StructuredReport::from_object(&object).map(Arc::new);
+    let dose_summary = structured_report
+        .as_deref()
+        .and_then(|report| CtDoseSummary::from_report(report, &object))
+        .map(Arc::new);
+    let document = EncapsulatedDocument::from_object(&object).map(Arc::new);
+    if let Some(document) = &document {
+        if let DocumentPreview::Unavailable(reason) = &document.preview {
+            log::warn!("{}: no document preview ({reason})", path.display());
+        }
     }
 
-    let image = extract_image_handle(&object);
+    let waveform = match Waveform::from_object(&object) {
+        Ok(waveform) => waveform.map(Arc::new),
+        Err(err) => {
+            log::warn!("{}: failed to read waveform ({err})", path.display());
+            None
+        }
+    };
 
+    let window = frame.as_ref().and_then(|frame| frame.default_window);
     let view = DicomView {
         file_path: path,
+        transfer_syntax,
         metadata,
+        frame_groups: functional_groups::frame_groups(&object, 0),
+        frame_count: pixels
+            .as_ref()
+            .map_or(0, |pixels| pixels.number_of_frames()),
+        frame_index: 0,
+        pixels,
         image,
+        pixel_error,
+        frame,
+        window,
+        color_map: ColorMap::default(),
+        overlays: OverlayPlane::read_all(&object),
+        segmentation,
+        structure_set: StructureSet::from_object(&object).map(Arc::new),
+        transform: ViewTransform::default(),
+        displayed_area: None,
+        presentation_state: PresentationState::from_object(&object).map(Arc::new),
+        structured_report,
+        dose_summary,
+        document,
+        waveform,
+        source: Arc::new(object),
     };
 
     Ok(DicomEntry {
@@ -58,21 +140,44 @@ pub fn load_dicom(path: PathBuf) -> Result<DicomEntry, String> {
     })
 }
 
-fn extract_image_handle(object: &DefaultDicomObject) -> Option<Handle> {
-    match FrameImagePipeline::render_first_frame(object) {
-        Ok(handle) => handle,
-        Err(err) => {
-            log::warn!("Unable to build frame preview: {err}");
-            None
-        }
+/// Decodes the Pixel Data, naming the transfer syntax when it cannot be decoded.
+/// Objects without Pixel Data decode to `None` rather than an error.
+fn decode_pixels(
+    object: &DefaultDicomObject,
+) -> Result<Option<Arc<DecodedPixelData<'static>>>, String> {
+    if object.element(tags::PIXEL_DATA).is_err() {
+        return Ok(None);
+    }
+
+    // Deflated files are inflated on load, so check the in-memory syntax.
+    let transfer_syntax = object.meta().transfer_syntax();
+    let name = codecs::transfer_syntax_name(transfer_syntax);
+    let unsupported = match codecs::can_decode_pixel_data(transfer_syntax) {
+        Some(true) => None,
+        Some(false) => Some(format!("{name} ({transfer_syntax}) is not supported")),
+        None => Some(format!("Unknown transfer syntax {transfer_syntax}")),
+    };
+    if let Some(message) = unsupported {
+        let message = format!("Cannot decode pixel data: {message}");
+        log::warn!("{message}");
+        return Err(message);
     }
+
+    FrameImagePipeline::decode(object)
+        .map(|pixels| pixels.map(Arc::new))
+        .map_err(|err| {
+            let message = format!("{err} ({name})");
+            log::warn!("Unable to build frame preview: {message}");
+            message
+        })
 }
 
-fn attribute_text(object: &DefaultDicomObject, name: &str) -> Option<String> {
-    object
-        .element_by_name(name)
-        .ok()
-        .and_then(|element| element.to_str().ok())
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
+fn extract_frame(
+    object: &DefaultDicomObject,
+    pixels: &DecodedPixelData<'_>,
+) -> Result<RenderedFrame, String> {
+    FrameImagePipeline::render_frame(object, pixels, 0, None, ColorMap::default()).map_err(|err| {
+        log::warn!("Unable to build frame preview: {err}");
+        err
+    })
 }
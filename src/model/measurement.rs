@@ -0,0 +1,790 @@
+use super::attributes::attribute_f64s;
+use super::ultrasound::{self, RegionDelta};
+use super::FrameData;
+use dicom::object::InMemDicomObject;
+use iced::Point;
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasurementTool {
+    Line,
+    Angle,
+    Cobb,
+    Rectangle,
+    Ellipse,
+    Freehand,
+}
+
+impl MeasurementTool {
+    pub const ALL: [MeasurementTool; 6] = [
+        MeasurementTool::Line,
+        MeasurementTool::Angle,
+        MeasurementTool::Cobb,
+        MeasurementTool::Rectangle,
+        MeasurementTool::Ellipse,
+        MeasurementTool::Freehand,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MeasurementTool::Line => "Line",
+            MeasurementTool::Angle => "Angle",
+            MeasurementTool::Cobb => "Cobb",
+            MeasurementTool::Rectangle => "Rectangle",
+            MeasurementTool::Ellipse => "Ellipse",
+            MeasurementTool::Freehand => "Freehand",
+        }
+    }
+}
+
+/// Which attribute the pixel spacing of a frame was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationSource {
+    PixelSpacing,
+    /// Spacing at the detector plane; distances are not corrected for magnification.
+    ImagerPixelSpacing,
+    /// Physical Delta X/Y of the ultrasound region the measurement lies in.
+    UltrasoundRegion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Spacing between rows, then between columns, in millimetres.
+    pub spacing: [f64; 2],
+    pub source: CalibrationSource,
+}
+
+impl Calibration {
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        [
+            ("PixelSpacing", CalibrationSource::PixelSpacing),
+            ("ImagerPixelSpacing", CalibrationSource::ImagerPixelSpacing),
+        ]
+        .into_iter()
+        .find_map(|(name, source)| {
+            let spacing = attribute_f64s(object, name)?;
+            match spacing[..] {
+                [row, column, ..] if row > 0.0 && column > 0.0 => Some(Self {
+                    spacing: [row, column],
+                    source,
+                }),
+                _ => None,
+            }
+        })
+    }
+
+    /// Converts a displacement in image pixels into millimetres.
+    fn to_mm(self, dx: f64, dy: f64) -> (f64, f64) {
+        (dx * self.spacing[1], dy * self.spacing[0])
+    }
+}
+
+/// Geometry of a measurement in image pixel coordinates (x = column, y = row).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line(Point, Point),
+    Angle {
+        start: Point,
+        vertex: Point,
+        end: Point,
+    },
+    Cobb([Point; 4]),
+    Rectangle(Point, Point),
+    Ellipse(Point, Point),
+    Freehand(Vec<Point>),
+}
+
+impl Shape {
+    pub fn tool(&self) -> MeasurementTool {
+        match self {
+            Shape::Line(..) => MeasurementTool::Line,
+            Shape::Angle { .. } => MeasurementTool::Angle,
+            Shape::Cobb(_) => MeasurementTool::Cobb,
+            Shape::Rectangle(..) => MeasurementTool::Rectangle,
+            Shape::Ellipse(..) => MeasurementTool::Ellipse,
+            Shape::Freehand(_) => MeasurementTool::Freehand,
+        }
+    }
+
+    /// Points that define the shape.
+    pub fn points(&self) -> Vec<Point> {
+        match self {
+            Shape::Line(a, b) | Shape::Rectangle(a, b) | Shape::Ellipse(a, b) => vec![*a, *b],
+            Shape::Angle { start, vertex, end } => vec![*start, *vertex, *end],
+            Shape::Cobb(points) => points.to_vec(),
+            Shape::Freehand(points) => points.clone(),
+        }
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Shape::Rectangle(a, b) => {
+                let (min_x, max_x) = ordered(a.x as f64, b.x as f64);
+                let (min_y, max_y) = ordered(a.y as f64, b.y as f64);
+                (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+            }
+            Shape::Ellipse(a, b) => {
+                let (cx, cy) = ((a.x + b.x) as f64 / 2.0, (a.y + b.y) as f64 / 2.0);
+                let (rx, ry) = (
+                    (a.x - b.x).abs() as f64 / 2.0,
+                    (a.y - b.y).abs() as f64 / 2.0,
+                );
+                if rx <= 0.0 || ry <= 0.0 {
+                    return false;
+                }
+                ((x - cx) / rx).powi(2) + ((y - cy) / ry).powi(2) <= 1.0
+            }
+            Shape::Freehand(points) => polygon_contains(points, x, y),
+            Shape::Line(..) | Shape::Angle { .. } | Shape::Cobb(_) => false,
+        }
+    }
+
+    fn bounding_box(&self) -> Option<(Point, Point)> {
+        let points: &[Point] = match self {
+            Shape::Rectangle(a, b) | Shape::Ellipse(a, b) => &[*a, *b],
+            Shape::Freehand(points) => points,
+            Shape::Line(..) | Shape::Angle { .. } | Shape::Cobb(_) => return None,
+        };
+        let first = *points.first()?;
+        Some(points.iter().fold((first, first), |(min, max), point| {
+            (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(point.x), max.y.max(point.y)),
+            )
+        }))
+    }
+
+    /// Area in square pixels, for ROI shapes.
+    fn pixel_area(&self) -> Option<f64> {
+        match self {
+            Shape::Rectangle(a, b) => Some(((a.x - b.x) * (a.y - b.y)).abs() as f64),
+            Shape::Ellipse(a, b) => Some(PI * ((a.x - b.x) * (a.y - b.y)).abs() as f64 / 4.0),
+            Shape::Freehand(points) => Some(polygon_area(points)),
+            Shape::Line(..) | Shape::Angle { .. } | Shape::Cobb(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoiStats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub pixel_count: usize,
+}
+
+impl RoiStats {
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let (min, max) = values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+
+        Some(Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min,
+            max,
+            pixel_count: values.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementResult {
+    /// Primary value: a length, an angle in degrees or an area.
+    pub value: f64,
+    pub unit: &'static str,
+    pub stats: Option<RoiStats>,
+    pub stats_unit: Option<String>,
+    pub calibration: Option<Calibration>,
+    /// Change along both axes of a line in a Doppler or M-mode region, whose
+    /// row change is also the primary value.
+    pub region_delta: Option<RegionDelta>,
+}
+
+impl MeasurementResult {
+    /// Label drawn next to the shape on the image.
+    pub fn summary_short(&self) -> String {
+        if let Some(delta) = &self.region_delta {
+            return format!(
+                "{:.2} {} / {:.2} {}",
+                delta.dy,
+                delta.y_unit.symbol(),
+                delta.dx,
+                delta.x_unit.symbol()
+            );
+        }
+        let value = format!("{:.1} {}", self.value, self.unit);
+        match (&self.stats, &self.stats_unit) {
+            (Some(stats), Some(unit)) => format!("{value} · {:.1} {unit}", stats.mean),
+            (Some(stats), None) => format!("{value} · {:.1}", stats.mean),
+            (None, _) => value,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        if let Some(delta) = &self.region_delta {
+            return delta.to_string();
+        }
+        let mut summary = format!("{:.2} {}", self.value, self.unit);
+        if let Some(stats) = &self.stats {
+            let unit = self.stats_unit.as_deref().unwrap_or("");
+            summary.push_str(&format!(
+                " · mean {:.2} ± {:.2} {unit} · min {:.2} · max {:.2} · {} px",
+                stats.mean, stats.std_dev, stats.min, stats.max, stats.pixel_count
+            ));
+        }
+        summary
+    }
+
+    pub fn calibration_note(&self) -> Option<&'static str> {
+        if self.unit == "°" || self.region_delta.is_some() {
+            return None;
+        }
+        match self.calibration.map(|calibration| calibration.source) {
+            None => Some("Uncalibrated: no PixelSpacing or ImagerPixelSpacing, values in pixels"),
+            Some(CalibrationSource::ImagerPixelSpacing) => {
+                Some("Calibrated at the detector plane (ImagerPixelSpacing)")
+            }
+            Some(CalibrationSource::PixelSpacing | CalibrationSource::UltrasoundRegion) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub id: usize,
+    /// Index of the entry the measurement was drawn on.
+    pub instance: usize,
+    pub frame: u32,
+    pub shape: Shape,
+    pub result: MeasurementResult,
+}
+
+impl Measurement {
+    pub const CSV_HEADER: [&'static str; 14] = [
+        "ID",
+        "Frame",
+        "Type",
+        "Value",
+        "Unit",
+        "Mean",
+        "StdDev",
+        "Min",
+        "Max",
+        "StatsUnit",
+        "PixelCount",
+        "Calibration",
+        "DeltaX",
+        "DeltaXUnit",
+    ];
+
+    pub fn csv_fields(&self) -> Vec<String> {
+        let result = &self.result;
+        let stat = |value: fn(&RoiStats) -> f64| {
+            result
+                .stats
+                .as_ref()
+                .map(|stats| format!("{:.4}", value(stats)))
+                .unwrap_or_default()
+        };
+        let calibration = match result.calibration.map(|calibration| calibration.source) {
+            Some(CalibrationSource::PixelSpacing) => "PixelSpacing",
+            Some(CalibrationSource::ImagerPixelSpacing) => "ImagerPixelSpacing",
+            Some(CalibrationSource::UltrasoundRegion) => "UltrasoundRegion",
+            None if result.region_delta.is_some() => "UltrasoundRegion",
+            None => "None",
+        };
+
+        vec![
+            self.id.to_string(),
+            (self.frame + 1).to_string(),
+            self.shape.tool().label().to_string(),
+            format!("{:.4}", result.value),
+            result.unit.to_string(),
+            stat(|stats| stats.mean),
+            stat(|stats| stats.std_dev),
+            stat(|stats| stats.min),
+            stat(|stats| stats.max),
+            result.stats_unit.clone().unwrap_or_default(),
+            result
+                .stats
+                .map(|stats| stats.pixel_count.to_string())
+                .unwrap_or_default(),
+            calibration.to_string(),
+            result
+                .region_delta
+                .map(|delta| format!("{:.4}", delta.dx))
+                .unwrap_or_default(),
+            result
+                .region_delta
+                .map(|delta| delta.x_unit.symbol().to_string())
+                .unwrap_or_default(),
+        ]
+    }
+
+    pub fn new(
+        id: usize,
+        instance: usize,
+        frame_index: u32,
+        shape: Shape,
+        frame: Option<&FrameData>,
+    ) -> Self {
+        let result = measure(&shape, frame);
+        Self {
+            id,
+            instance,
+            frame: frame_index,
+            shape,
+            result,
+        }
+    }
+}
+
+pub fn measure(shape: &Shape, frame: Option<&FrameData>) -> MeasurementResult {
+    // Ultrasound images are calibrated per region rather than by Pixel Spacing.
+    let region = frame.and_then(|frame| {
+        ultrasound::region_containing(&frame.ultrasound_regions, &shape.points())
+    });
+    let calibration = match region {
+        Some(region) => region.calibration(),
+        None => frame.and_then(|frame| frame.calibration),
+    };
+    let length_unit = if calibration.is_some() { "mm" } else { "px" };
+    let scale = |dx: f64, dy: f64| match calibration {
+        Some(calibration) => calibration.to_mm(dx, dy),
+        None => (dx, dy),
+    };
+    let vector = |from: Point, to: Point| scale((to.x - from.x) as f64, (to.y - from.y) as f64);
+
+    let mut result = MeasurementResult {
+        value: 0.0,
+        unit: length_unit,
+        stats: None,
+        stats_unit: None,
+        calibration,
+        region_delta: None,
+    };
+
+    match shape {
+        Shape::Line(a, b) => {
+            let (dx, dy) = vector(*a, *b);
+            result.value = dx.hypot(dy);
+            if let Some(delta) = region.and_then(|region| region.delta(*a, *b)) {
+                result.value = delta.dy;
+                result.unit = delta.y_unit.symbol();
+                result.region_delta = Some(delta);
+            }
+        }
+        Shape::Angle { start, vertex, end } => {
+            result.value = angle_between(vector(*vertex, *start), vector(*vertex, *end));
+            result.unit = "°";
+        }
+        Shape::Cobb([a, b, c, d]) => {
+            let angle = angle_between(vector(*a, *b), vector(*c, *d));
+            result.value = angle.min(180.0 - angle);
+            result.unit = "°";
+        }
+        Shape::Rectangle(..) | Shape::Ellipse(..) | Shape::Freehand(_) => {
+            let area = shape.pixel_area().unwrap_or_default();
+            let (sx, sy) = scale(1.0, 1.0);
+            result.value = area * sx * sy;
+            result.unit = if calibration.is_some() {
+                "mm²"
+            } else {
+                "px²"
+            };
+            if let Some(frame) = frame.filter(|frame| frame.is_monochrome()) {
+                result.stats = roi_stats(shape, frame);
+                result.stats_unit = frame.rescale.unit.clone();
+            }
+        }
+    }
+
+    result
+}
+
+fn roi_stats(shape: &Shape, frame: &FrameData) -> Option<RoiStats> {
+    RoiStats::from_values(&roi_values(shape, frame))
+}
+
+/// Modality values of the pixels whose centres fall inside an ROI shape.
+pub fn roi_values(shape: &Shape, frame: &FrameData) -> Vec<f64> {
+    let Some((min, max)) = shape.bounding_box() else {
+        return Vec::new();
+    };
+    let first_row = min.y.floor().max(0.0) as u32;
+    let last_row = (max.y.ceil() as u32).min(frame.rows);
+    let first_column = min.x.floor().max(0.0) as u32;
+    let last_column = (max.x.ceil() as u32).min(frame.columns);
+
+    let mut values = Vec::new();
+    for row in first_row..last_row {
+        for column in first_column..last_column {
+            if !shape.contains(column as f64 + 0.5, row as f64 + 0.5) {
+                continue;
+            }
+            if let Some(&[stored]) = frame.stored_at(row, column) {
+                values.push(frame.rescale.apply(stored));
+            }
+        }
+    }
+    values
+}
+
+/// In-progress measurement built from canvas presses, drags and releases.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draft {
+    pub tool: MeasurementTool,
+    pub points: Vec<Point>,
+    dragging: bool,
+}
+
+impl Draft {
+    pub fn new(tool: MeasurementTool) -> Self {
+        Self {
+            tool,
+            points: Vec::new(),
+            dragging: false,
+        }
+    }
+
+    pub fn press(&mut self, point: Point) -> Option<Shape> {
+        self.dragging = true;
+        match self.tool {
+            MeasurementTool::Angle => {
+                self.points.push(point);
+                self.dragging = false;
+                if self.points.len() == 3 {
+                    return self.finish();
+                }
+            }
+            MeasurementTool::Freehand => self.points = vec![point],
+            MeasurementTool::Cobb => self.points.extend([point, point]),
+            MeasurementTool::Line | MeasurementTool::Rectangle | MeasurementTool::Ellipse => {
+                self.points = vec![point, point];
+            }
+        }
+        None
+    }
+
+    pub fn drag(&mut self, point: Point) {
+        if !self.dragging {
+            return;
+        }
+        match self.tool {
+            MeasurementTool::Freehand => {
+                if self
+                    .points
+                    .last()
+                    .is_none_or(|last| last.distance(point) >= 0.5)
+                {
+                    self.points.push(point);
+                }
+            }
+            _ => {
+                if let Some(last) = self.points.last_mut() {
+                    *last = point;
+                }
+            }
+        }
+    }
+
+    pub fn release(&mut self, point: Point) -> Option<Shape> {
+        if !self.dragging {
+            return None;
+        }
+        self.drag(point);
+        self.dragging = false;
+        match self.tool {
+            MeasurementTool::Cobb if self.points.len() < 4 => None,
+            _ => self.finish(),
+        }
+    }
+
+    /// Shape to preview while drawing, using `hovered` for the next click.
+    pub fn preview(&self, hovered: Option<Point>) -> Option<Shape> {
+        let mut points = self.points.clone();
+        if self.tool == MeasurementTool::Angle {
+            points.extend(hovered);
+        }
+        match (self.tool, points.as_slice()) {
+            (MeasurementTool::Angle, [a, b]) | (MeasurementTool::Cobb, [a, b]) => {
+                Some(Shape::Line(*a, *b))
+            }
+            (MeasurementTool::Cobb, [a, b, c, d]) => Some(Shape::Cobb([*a, *b, *c, *d])),
+            (MeasurementTool::Cobb, [a, b, ..]) => Some(Shape::Line(*a, *b)),
+            _ => shape_from_points(self.tool, &points),
+        }
+    }
+
+    fn finish(&mut self) -> Option<Shape> {
+        let points = std::mem::take(&mut self.points);
+        shape_from_points(self.tool, &points).filter(|shape| !is_degenerate(shape))
+    }
+}
+
+fn shape_from_points(tool: MeasurementTool, points: &[Point]) -> Option<Shape> {
+    match (tool, points) {
+        (MeasurementTool::Line, [a, b]) => Some(Shape::Line(*a, *b)),
+        (MeasurementTool::Angle, [start, vertex, end]) => Some(Shape::Angle {
+            start: *start,
+            vertex: *vertex,
+            end: *end,
+        }),
+        (MeasurementTool::Cobb, [a, b, c, d]) => Some(Shape::Cobb([*a, *b, *c, *d])),
+        (MeasurementTool::Rectangle, [a, b]) => Some(Shape::Rectangle(*a, *b)),
+        (MeasurementTool::Ellipse, [a, b]) => Some(Shape::Ellipse(*a, *b)),
+        (MeasurementTool::Freehand, points) if points.len() >= 2 => {
+            Some(Shape::Freehand(points.to_vec()))
+        }
+        _ => None,
+    }
+}
+
+fn is_degenerate(shape: &Shape) -> bool {
+    match shape {
+        Shape::Line(a, b) => a.distance(*b) < 1.0,
+        Shape::Angle { start, vertex, end } => {
+            vertex.distance(*start) < 1.0 || vertex.distance(*end) < 1.0
+        }
+        Shape::Cobb([a, b, c, d]) => a.distance(*b) < 1.0 || c.distance(*d) < 1.0,
+        Shape::Freehand(points) => points.len() < 3,
+        Shape::Rectangle(..) | Shape::Ellipse(..) => {
+            shape.pixel_area().is_some_and(|area| area < 1.0)
+        }
+    }
+}
+
+fn angle_between(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let dot = a.0 * b.0 + a.1 * b.1;
+    let norms = a.0.hypot(a.1) * b.0.hypot(b.1);
+    if norms == 0.0 {
+        return 0.0;
+    }
+    (dot / norms).clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+fn ordered(a: f64, b: f64) -> (f64, f64) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn polygon_contains(points: &[Point], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match points.last() {
+        Some(point) => *point,
+        None => return false,
+    };
+    for &current in points {
+        let (xi, yi) = (current.x as f64, current.y as f64);
+        let (xj, yj) = (previous.x as f64, previous.y as f64);
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+fn polygon_area(points: &[Point]) -> f64 {
+    let Some(&last) = points.last() else {
+        return 0.0;
+    };
+    let mut previous = last;
+    let mut twice_area = 0.0;
+    for &current in points {
+        twice_area += (previous.x * current.y - current.x * previous.y) as f64;
+        previous = current;
+    }
+    twice_area.abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Rescale;
+
+    fn frame(rows: u32, columns: u32, calibration: Option<Calibration>) -> FrameData {
+        FrameData {
+            rows,
+            columns,
+            samples_per_pixel: 1,
+            stored: (0..rows * columns).map(|value| value as i32).collect(),
+            rescale: Rescale {
+                slope: 2.0,
+                intercept: -10.0,
+                unit: Some(String::from("HU")),
+                suv_factor: None,
+            },
+            plane: None,
+            calibration,
+            ultrasound_regions: Vec::new(),
+            invert: false,
+            default_window: None,
+            overlays: Vec::new(),
+            palette: None,
+        }
+    }
+
+    fn spacing(row: f64, column: f64) -> Option<Calibration> {
+        Some(Calibration {
+            spacing: [row, column],
+            source: CalibrationSource::PixelSpacing,
+        })
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn line_length_in_pixels_without_calibration() {
+        let result = measure(
+            &Shape::Line(Point::new(0.0, 0.0), Point::new(3.0, 4.0)),
+            None,
+        );
+        assert_close(result.value, 5.0);
+        assert_eq!(result.unit, "px");
+    }
+
+    #[test]
+    fn line_length_uses_anisotropic_spacing() {
+        // Columns 0.5 mm apart, rows 2 mm apart.
+        let frame = frame(10, 10, spacing(2.0, 0.5));
+        let horizontal = measure(
+            &Shape::Line(Point::new(0.0, 0.0), Point::new(8.0, 0.0)),
+            Some(&frame),
+        );
+        assert_close(horizontal.value, 4.0);
+        assert_eq!(horizontal.unit, "mm");
+        let diagonal = measure(
+            &Shape::Line(Point::new(0.0, 0.0), Point::new(6.0, 2.0)),
+            Some(&frame),
+        );
+        assert_close(diagonal.value, 5.0);
+    }
+
+    #[test]
+    fn angle_follows_calibrated_geometry() {
+        let shape = Shape::Angle {
+            start: Point::new(4.0, 0.0),
+            vertex: Point::new(0.0, 0.0),
+            end: Point::new(0.0, 4.0),
+        };
+        assert_close(measure(&shape, None).value, 90.0);
+
+        let diagonal = Shape::Angle {
+            start: Point::new(4.0, 0.0),
+            vertex: Point::new(0.0, 0.0),
+            end: Point::new(4.0, 4.0),
+        };
+        assert_close(measure(&diagonal, None).value, 45.0);
+        // Rows twice as far apart as columns steepen the second arm to atan(2).
+        let frame = frame(10, 10, spacing(2.0, 1.0));
+        let result = measure(&diagonal, Some(&frame));
+        assert_close(result.value, 2.0_f64.atan().to_degrees());
+        assert_eq!(result.unit, "°");
+    }
+
+    #[test]
+    fn cobb_angle_is_acute() {
+        let shape = Shape::Cobb([
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 0.0),
+        ]);
+        assert_close(measure(&shape, None).value, 45.0);
+    }
+
+    #[test]
+    fn areas_scale_with_both_spacings() {
+        let frame = frame(10, 10, spacing(2.0, 0.5));
+        let rectangle = measure(
+            &Shape::Rectangle(Point::new(0.0, 0.0), Point::new(4.0, 2.0)),
+            Some(&frame),
+        );
+        assert_close(rectangle.value, 8.0);
+        assert_eq!(rectangle.unit, "mm²");
+
+        let ellipse = measure(
+            &Shape::Ellipse(Point::new(0.0, 0.0), Point::new(4.0, 2.0)),
+            Some(&frame),
+        );
+        assert_close(ellipse.value, 2.0 * PI);
+
+        let triangle = measure(
+            &Shape::Freehand(vec![
+                Point::new(0.0, 0.0),
+                Point::new(4.0, 0.0),
+                Point::new(0.0, 4.0),
+            ]),
+            None,
+        );
+        assert_close(triangle.value, 8.0);
+        assert_eq!(triangle.unit, "px²");
+    }
+
+    #[test]
+    fn roi_statistics_use_modality_values_of_enclosed_pixels() {
+        // Stored values count up row by row: 0..16 on a 4 × 4 frame.
+        let frame = frame(4, 4, None);
+        let shape = Shape::Rectangle(Point::new(1.0, 1.0), Point::new(3.0, 3.0));
+        let mut values = roi_values(&shape, &frame);
+        values.sort_by(f64::total_cmp);
+        // Stored 5, 6, 9 and 10, rescaled by 2x - 10.
+        assert_eq!(values, vec![0.0, 2.0, 8.0, 10.0]);
+
+        let stats = measure(&shape, Some(&frame)).stats.unwrap();
+        assert_close(stats.mean, 5.0);
+        assert_close(stats.min, 0.0);
+        assert_close(stats.max, 10.0);
+        assert_close(stats.std_dev, 17.0_f64.sqrt());
+        assert_eq!(stats.pixel_count, 4);
+    }
+
+    #[test]
+    fn degenerate_shapes_are_rejected() {
+        let point = Point::new(5.0, 5.0);
+        assert!(is_degenerate(&Shape::Line(point, point)));
+        assert!(is_degenerate(&Shape::Angle {
+            start: point,
+            vertex: point,
+            end: Point::new(10.0, 5.0),
+        }));
+        assert!(is_degenerate(&Shape::Angle {
+            start: Point::new(10.0, 5.0),
+            vertex: point,
+            end: Point::new(5.5, 5.0),
+        }));
+        assert!(!is_degenerate(&Shape::Angle {
+            start: Point::new(10.0, 5.0),
+            vertex: point,
+            end: Point::new(5.0, 10.0),
+        }));
+        assert!(is_degenerate(&Shape::Rectangle(
+            point,
+            Point::new(5.5, 5.5)
+        )));
+    }
+}
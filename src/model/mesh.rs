@@ -0,0 +1,235 @@
+use image::{Rgba, RgbaImage};
+
+const VIEW_SIZE: u32 = 512;
+const MARGIN: f64 = 16.0;
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 28, 255]);
+const SURFACE: [f64; 3] = [230.0, 220.0, 200.0];
+
+/// A triangle mesh of an encapsulated STL or OBJ model, in its own units
+/// (millimetres for DICOM encapsulated models).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub triangles: Vec<[[f64; 3]; 3]>,
+}
+
+impl Mesh {
+    /// Reads binary or ASCII STL.
+    pub fn from_stl(bytes: &[u8]) -> Result<Self, String> {
+        // ASCII files also start with "solid", so trust the binary size first.
+        if bytes.len() >= 84 {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            if count.checked_mul(50).and_then(|size| size.checked_add(84)) == Some(bytes.len()) {
+                let triangles = bytes[84..]
+                    .chunks_exact(50)
+                    .map(|record| {
+                        let float = |offset: usize| {
+                            let raw = [
+                                record[offset],
+                                record[offset + 1],
+                                record[offset + 2],
+                                record[offset + 3],
+                            ];
+                            f32::from_le_bytes(raw) as f64
+                        };
+                        // The facet normal comes first and is recomputed when shading.
+                        let vertex = |index: usize| {
+                            let offset = 12 + index * 12;
+                            [float(offset), float(offset + 4), float(offset + 8)]
+                        };
+                        [vertex(0), vertex(1), vertex(2)]
+                    })
+                    .collect();
+                return Ok(Self { triangles });
+            }
+        }
+
+        let text = String::from_utf8_lossy(bytes);
+        if !text.trim_start().starts_with("solid") {
+            return Err(String::from("Not a binary or ASCII STL file"));
+        }
+        let mut vertices = Vec::new();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("vertex") {
+                vertices.push(parse_point(fields).ok_or("Malformed STL vertex")?);
+            }
+        }
+        Ok(Self {
+            triangles: vertices
+                .chunks_exact(3)
+                .map(|corners| [corners[0], corners[1], corners[2]])
+                .collect(),
+        })
+    }
+
+    /// Reads the vertices and faces of a Wavefront OBJ file; polygons are
+    /// split into fans of triangles.
+    pub fn from_obj(bytes: &[u8]) -> Result<Self, String> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("v") => vertices.push(parse_point(fields).ok_or("Malformed OBJ vertex")?),
+                Some("f") => {
+                    let corners = fields
+                        .map(|field| {
+                            // Vertex index, optionally followed by /texture/normal.
+                            let index = field.split('/').next()?.parse::<i64>().ok()?;
+                            let index = match index {
+                                index if index > 0 => index - 1,
+                                index if index < 0 => vertices.len() as i64 + index,
+                                _ => return None,
+                            };
+                            vertices.get(usize::try_from(index).ok()?).copied()
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or("OBJ face references a missing vertex")?;
+                    for pair in corners.windows(2).skip(1) {
+                        triangles.push([corners[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { triangles })
+    }
+
+    /// Axis-aligned extent along x, y and z.
+    pub fn size(&self) -> [f64; 3] {
+        let (min, max) = self.bounds();
+        [0, 1, 2].map(|axis| (max[axis] - min[axis]).max(0.0))
+    }
+
+    fn bounds(&self) -> ([f64; 3], [f64; 3]) {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for point in self.triangles.iter().flatten() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(point[axis]);
+                max[axis] = max[axis].max(point[axis]);
+            }
+        }
+        (min, max)
+    }
+
+    /// Orthographic view from the front, turned 30° about the vertical axis
+    /// and tilted 20° from above, shaded by the angle to the viewer.
+    pub fn render(&self) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(VIEW_SIZE, VIEW_SIZE, BACKGROUND);
+        if self.triangles.is_empty() {
+            return image;
+        }
+        let (yaw, pitch) = (30f64.to_radians(), 20f64.to_radians());
+        let (min, max) = self.bounds();
+        let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) / 2.0);
+        // Patient coordinates: x to the left, y to the back, z to the head, so
+        // the viewer looks along +y with z up.
+        let view = |point: &[f64; 3]| {
+            let [x, y, z] = [0, 1, 2].map(|axis| point[axis] - center[axis]);
+            let (x, y) = (x * yaw.cos() - y * yaw.sin(), x * yaw.sin() + y * yaw.cos());
+            let (depth, up) = (
+                y * pitch.cos() - z * pitch.sin(),
+                y * pitch.sin() + z * pitch.cos(),
+            );
+            [x, up, depth]
+        };
+        let projected = self
+            .triangles
+            .iter()
+            .map(|triangle| triangle.each_ref().map(view))
+            .collect::<Vec<_>>();
+
+        let extent = projected.iter().flatten().fold(0f64, |extent, point| {
+            extent.max(point[0].abs()).max(point[1].abs())
+        });
+        let scale = if extent > 0.0 {
+            (VIEW_SIZE as f64 / 2.0 - MARGIN) / extent
+        } else {
+            1.0
+        };
+        let half = VIEW_SIZE as f64 / 2.0;
+        let mut depth = vec![f64::INFINITY; (VIEW_SIZE * VIEW_SIZE) as usize];
+        for triangle in projected {
+            let normal = normal(&triangle);
+            // Two-sided, as exported meshes do not agree on winding order.
+            let shade = 0.25 + 0.75 * normal[2].abs();
+            let [red, green, blue] = SURFACE.map(|channel| (channel * shade) as u8);
+            // Facing the patient, their left is on the viewer's right.
+            let screen =
+                triangle.map(|point| [half + point[0] * scale, half - point[1] * scale, point[2]]);
+            fill_triangle(
+                &mut image,
+                &mut depth,
+                &screen,
+                Rgba([red, green, blue, 255]),
+            );
+        }
+        image
+    }
+}
+
+fn parse_point<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<[f64; 3]> {
+    let mut coordinate = || fields.next()?.parse::<f64>().ok();
+    Some([coordinate()?, coordinate()?, coordinate()?])
+}
+
+fn normal(triangle: &[[f64; 3]; 3]) -> [f64; 3] {
+    let [a, b, c] = triangle;
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = cross.iter().map(|value| value * value).sum::<f64>().sqrt();
+    if length > 0.0 {
+        cross.map(|value| value / length)
+    } else {
+        [0.0; 3]
+    }
+}
+
+/// Fills the pixels whose centres lie in the triangle and are nearer than
+/// what was drawn before; `screen` holds (column, row, depth) corners.
+fn fill_triangle(
+    image: &mut RgbaImage,
+    depth: &mut [f64],
+    screen: &[[f64; 3]; 3],
+    color: Rgba<u8>,
+) {
+    let [a, b, c] = screen;
+    let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+    if area.abs() < f64::EPSILON {
+        return;
+    }
+    let (width, height) = image.dimensions();
+    let low = |axis: usize| screen.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min);
+    let high = |axis: usize| {
+        screen
+            .iter()
+            .map(|p| p[axis])
+            .fold(f64::NEG_INFINITY, f64::max)
+    };
+    let columns = low(0).floor().max(0.0) as u32..(high(0).ceil().max(0.0) as u32).min(width);
+    let rows = low(1).floor().max(0.0) as u32..(high(1).ceil().max(0.0) as u32).min(height);
+    for row in rows {
+        for column in columns.clone() {
+            let (x, y) = (column as f64 + 0.5, row as f64 + 0.5);
+            let wa = ((b[0] - x) * (c[1] - y) - (b[1] - y) * (c[0] - x)) / area;
+            let wb = ((c[0] - x) * (a[1] - y) - (c[1] - y) * (a[0] - x)) / area;
+            let wc = 1.0 - wa - wb;
+            if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                continue;
+            }
+            let z = wa * a[2] + wb * b[2] + wc * c[2];
+            let index = (row * width + column) as usize;
+            if z < depth[index] {
+                depth[index] = z;
+                image.put_pixel(column, row, color);
+            }
+        }
+    }
+}
@@ -1,6 +1,56 @@
+pub mod annotation;
+pub mod attributes;
+pub mod cine;
 pub mod dicom_entry;
+pub mod dose_report;
+pub mod encapsulated_document;
+pub mod export;
+pub mod frame;
+pub mod functional_groups;
+pub mod fusion;
+pub mod geometry;
+pub mod histogram;
 pub mod loader;
+pub mod measurement;
+pub mod mesh;
+pub mod mpr;
+pub mod orientation;
+pub mod overlay;
+pub mod palette;
+pub mod presentation_export;
+pub mod presentation_state;
+pub mod rt_structure;
+pub mod segmentation;
+pub mod structured_report;
+pub mod suv;
+pub mod thumbnail;
 pub mod tree;
+pub mod ultrasound;
+pub mod voi;
+pub mod volume;
+pub mod waveform;
 
-pub use dicom_entry::{DicomEntry, DicomView, MetadataRow};
+pub use annotation::{AnnotationConfig, AnnotationContext, Corner, CornerTemplates};
+pub use dicom_entry::{DicomEntry, DicomView, FramePreview, MetadataRow};
+pub use dose_report::{CtDoseSummary, StudyDoseTotal};
+pub use encapsulated_document::{DocumentPreview, EncapsulatedDocument};
+pub use export::{DisplaySettings, ExportMode, ExportScale, ExportScope, ExportSource};
+pub use frame::{FrameData, PixelProbe, Rescale};
+pub use functional_groups::FunctionalGroup;
+pub use fusion::{Fusion, FusionCandidate};
+pub use geometry::ImagePlane;
+pub use histogram::{Histogram, HistogramScope};
+pub use measurement::{Calibration, Draft, Measurement, MeasurementTool, Shape};
+pub use mpr::{MprAxis, MprSession, MprView, Projection, Reslice};
+pub use orientation::{EdgeLabels, Orientation, ViewTransform};
+pub use overlay::{OverlayBitmap, OverlayColor, OverlayPlane, OverlayStyle};
+pub use palette::{ColorMap, PaletteLut};
+pub use presentation_state::PresentationState;
+pub use rt_structure::{RoiStructure, StructureSet};
+pub use segmentation::{Segment, Segmentation};
+pub use structured_report::{SrNode, StructuredReport};
 pub use tree::{TreeNodeKey, TreeViewMode};
+pub use ultrasound::UltrasoundRegion;
+pub use voi::Window;
+pub use volume::Volume;
+pub use waveform::{MultiplexGroup, PaperSpeed, Waveform, WaveformChannel, WaveformGain};
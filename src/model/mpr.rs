@@ -0,0 +1,326 @@
+use super::geometry::{add, cross, dot, normalize, scale, sub};
+use super::{FramePreview, Volume, Window};
+use iced::{Point, Vector};
+use std::fmt;
+use std::sync::Arc;
+
+/// Largest width or height of a reformatted image, in pixels.
+const MAX_RESLICE_SIZE: f64 = 512.0;
+
+/// The volume axis an MPR plane is perpendicular to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MprAxis {
+    /// The acquired plane.
+    Slice,
+    /// Perpendicular to the column direction of the slices.
+    Column,
+    /// Perpendicular to the row direction of the slices.
+    Row,
+}
+
+impl MprAxis {
+    pub const ALL: [MprAxis; 3] = [MprAxis::Slice, MprAxis::Column, MprAxis::Row];
+}
+
+/// How samples across a slab are combined into one pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    #[default]
+    Average,
+    Maximum,
+    Minimum,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 3] = [
+        Projection::Average,
+        Projection::Maximum,
+        Projection::Minimum,
+    ];
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Projection::Average => "AvgIP",
+            Projection::Maximum => "MIP",
+            Projection::Minimum => "MinIP",
+        }
+    }
+
+    fn combine(self, values: impl Iterator<Item = f32>) -> f32 {
+        match self {
+            Projection::Average => {
+                let (sum, count) = values.fold((0.0f32, 0u32), |(sum, count), value| {
+                    (sum + value, count + 1)
+                });
+                if count == 0 {
+                    f32::NAN
+                } else {
+                    sum / count as f32
+                }
+            }
+            Projection::Maximum => values.fold(f32::NAN, f32::max),
+            Projection::Minimum => values.fold(f32::NAN, f32::min),
+        }
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Projection::Average => "Average (AvgIP)",
+            Projection::Maximum => "Maximum (MIP)",
+            Projection::Minimum => "Minimum (MinIP)",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Crosshair position and oblique rotation shared by the three MPR planes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MprGeometry {
+    pub center: [f64; 3],
+    /// Rotation about the slice normal, in degrees.
+    pub yaw: f64,
+    /// Rotation about the rotated row direction, in degrees.
+    pub pitch: f64,
+}
+
+impl MprGeometry {
+    pub fn new(volume: &Volume) -> Self {
+        Self {
+            center: volume.center(),
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    pub fn is_oblique(&self) -> bool {
+        self.yaw != 0.0 || self.pitch != 0.0
+    }
+
+    /// Volume axes after the oblique rotation.
+    fn basis(&self, volume: &Volume) -> [[f64; 3]; 3] {
+        let [row, column, normal] = volume.axes;
+        let (sin, cos) = self.yaw.to_radians().sin_cos();
+        let (row, column) = (
+            add(scale(row, cos), scale(column, sin)),
+            add(scale(column, cos), scale(row, -sin)),
+        );
+        let (sin, cos) = self.pitch.to_radians().sin_cos();
+        let (column, normal) = (
+            add(scale(column, cos), scale(normal, sin)),
+            add(scale(normal, cos), scale(column, -sin)),
+        );
+        [normalize(row), normalize(column), normalize(normal)]
+    }
+
+    /// Orientation of the plane perpendicular to `axis`, with display axes
+    /// chosen so that the slice normal points up in the other two planes.
+    pub fn plane(&self, volume: &Volume, axis: MprAxis) -> MprPlane {
+        let [row, column, normal] = self.basis(volume);
+        let (u, v, n) = match axis {
+            MprAxis::Slice => (row, column, normal),
+            MprAxis::Column => (row, scale(normal, -1.0), column),
+            MprAxis::Row => (column, scale(normal, -1.0), row),
+        };
+        MprPlane {
+            axis,
+            u,
+            v,
+            normal: n,
+        }
+    }
+}
+
+/// An open MPR viewer: the volume, shared crosshair and the rendered planes.
+pub struct MprSession {
+    pub volume: Arc<Volume>,
+    pub title: String,
+    pub geometry: MprGeometry,
+    /// Slab thickness in millimetres; 0 samples a single plane.
+    pub slab: f64,
+    pub projection: Projection,
+    pub window: Window,
+    pub views: Vec<MprView>,
+    /// Number of the latest reslice request, so stale results are dropped.
+    pub generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MprView {
+    pub reslice: Reslice,
+    pub preview: FramePreview,
+}
+
+impl MprSession {
+    pub fn new(volume: Arc<Volume>, title: String) -> Self {
+        Self {
+            geometry: MprGeometry::new(&volume),
+            window: volume.default_window,
+            volume,
+            title,
+            slab: 0.0,
+            projection: Projection::default(),
+            views: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    pub fn view(&self, axis: MprAxis) -> Option<&MprView> {
+        self.views
+            .iter()
+            .find(|view| view.reslice.plane.axis == axis)
+    }
+}
+
+/// Display axes of one MPR plane in patient space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MprPlane {
+    pub axis: MprAxis,
+    /// Direction of increasing display column.
+    pub u: [f64; 3],
+    /// Direction of increasing display row.
+    pub v: [f64; 3],
+    pub normal: [f64; 3],
+}
+
+impl MprPlane {
+    /// Anatomical name of the plane from its dominant normal component.
+    pub fn name(&self) -> &'static str {
+        let [x, y, z] = self.normal.map(f64::abs);
+        if x >= y && x >= z {
+            "Sagittal"
+        } else if y >= z {
+            "Coronal"
+        } else {
+            "Axial"
+        }
+    }
+}
+
+/// A plane sampled from the volume, centred on the projection of the volume
+/// centre so the image does not shift as the crosshair moves.
+#[derive(Debug, Clone)]
+pub struct Reslice {
+    pub plane: MprPlane,
+    pub width: u32,
+    pub height: u32,
+    /// Size of one reformatted pixel, in millimetres.
+    pub pixel_size: f64,
+    /// Patient position of the image centre.
+    pub center: [f64; 3],
+    /// Modality values row by row, NaN outside the volume.
+    pub values: Vec<f32>,
+}
+
+impl Reslice {
+    /// Samples `plane` through `geometry.center`, combining a slab of `slab`
+    /// millimetres along its normal with `projection`. Rows are split across
+    /// the available CPU cores.
+    pub fn new(
+        volume: &Volume,
+        geometry: &MprGeometry,
+        axis: MprAxis,
+        slab: f64,
+        projection: Projection,
+    ) -> Self {
+        let plane = geometry.plane(volume, axis);
+        let extent = volume.diagonal();
+        let pixel_size = volume.min_spacing().max(extent / MAX_RESLICE_SIZE);
+        let size = (extent / pixel_size).ceil().max(1.0) as usize;
+        let volume_center = volume.center();
+        let depth = dot(sub(geometry.center, volume_center), plane.normal);
+        let center = add(volume_center, scale(plane.normal, depth));
+
+        let offsets = slab_offsets(slab, volume.min_spacing());
+        let half = size as f64 / 2.0;
+        let pixel = |x: usize, y: usize| {
+            let point = add(
+                center,
+                add(
+                    scale(plane.u, (x as f64 + 0.5 - half) * pixel_size),
+                    scale(plane.v, (y as f64 + 0.5 - half) * pixel_size),
+                ),
+            );
+            projection.combine(
+                offsets
+                    .iter()
+                    .filter_map(|offset| volume.sample(add(point, scale(plane.normal, *offset)))),
+            )
+        };
+
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |threads| threads.get())
+            .min(size);
+        let rows_per_thread = size.div_ceil(threads);
+        let mut values = vec![f32::NAN; size * size];
+        std::thread::scope(|scope| {
+            for (chunk_index, chunk) in values.chunks_mut(rows_per_thread * size).enumerate() {
+                let pixel = &pixel;
+                scope.spawn(move || {
+                    let first_row = chunk_index * rows_per_thread;
+                    for (index, value) in chunk.iter_mut().enumerate() {
+                        *value = pixel(index % size, first_row + index / size);
+                    }
+                });
+            }
+        });
+
+        Self {
+            plane,
+            width: size as u32,
+            height: size as u32,
+            pixel_size,
+            center,
+            values,
+        }
+    }
+
+    /// Image coordinates (x = column) of a patient position projected onto the plane.
+    pub fn to_image(&self, point: [f64; 3]) -> Point {
+        let offset = sub(point, self.center);
+        Point::new(
+            (dot(offset, self.plane.u) / self.pixel_size + self.width as f64 / 2.0) as f32,
+            (dot(offset, self.plane.v) / self.pixel_size + self.height as f64 / 2.0) as f32,
+        )
+    }
+
+    /// Patient position of an image point on the plane.
+    pub fn to_patient(&self, point: Point) -> [f64; 3] {
+        add(
+            self.center,
+            add(
+                scale(
+                    self.plane.u,
+                    (point.x as f64 - self.width as f64 / 2.0) * self.pixel_size,
+                ),
+                scale(
+                    self.plane.v,
+                    (point.y as f64 - self.height as f64 / 2.0) * self.pixel_size,
+                ),
+            ),
+        )
+    }
+
+    /// Display direction of the line where `other` intersects this plane.
+    pub fn intersection_direction(&self, other: &MprPlane) -> Vector {
+        let direction = cross(self.plane.normal, other.normal);
+        Vector::new(
+            dot(direction, self.plane.u) as f32,
+            dot(direction, self.plane.v) as f32,
+        )
+    }
+}
+
+/// Offsets along the normal sampled for a slab, one per `step` millimetres.
+fn slab_offsets(slab: f64, step: f64) -> Vec<f64> {
+    let count = (slab / step).round().max(0.0) as usize + 1;
+    if count == 1 {
+        return vec![0.0];
+    }
+    let first = -slab / 2.0;
+    let spacing = slab / (count - 1) as f64;
+    (0..count)
+        .map(|index| first + index as f64 * spacing)
+        .collect()
+}
@@ -0,0 +1,212 @@
+use super::attributes::{attribute_f64s, attribute_text};
+use super::ImagePlane;
+use dicom::object::InMemDicomObject;
+use iced::{Point, Size};
+
+/// Direction cosines below this magnitude do not contribute a letter to an
+/// oblique orientation label.
+const OBLIQUE_THRESHOLD: f64 = 0.25;
+
+/// Display orientation of a frame: an optional horizontal mirror followed by
+/// a number of clockwise quarter turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ViewTransform {
+    pub quarter_turns: u8,
+    pub flipped: bool,
+}
+
+impl ViewTransform {
+    pub const ROTATE_CLOCKWISE: Self = Self {
+        quarter_turns: 1,
+        flipped: false,
+    };
+    pub const ROTATE_COUNTER_CLOCKWISE: Self = Self {
+        quarter_turns: 3,
+        flipped: false,
+    };
+    pub const FLIP_HORIZONTAL: Self = Self {
+        quarter_turns: 0,
+        flipped: true,
+    };
+    pub const FLIP_VERTICAL: Self = Self {
+        quarter_turns: 2,
+        flipped: true,
+    };
+
+    pub fn is_identity(self) -> bool {
+        self == Self::default()
+    }
+
+    /// The transform that applies `self` and then `next` in display space.
+    pub fn then(self, next: Self) -> Self {
+        let turns = if next.flipped {
+            next.quarter_turns + 4 - self.quarter_turns
+        } else {
+            next.quarter_turns + self.quarter_turns
+        };
+        Self {
+            quarter_turns: turns % 4,
+            flipped: self.flipped != next.flipped,
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        if self.flipped {
+            self
+        } else {
+            Self {
+                quarter_turns: (4 - self.quarter_turns) % 4,
+                flipped: false,
+            }
+        }
+    }
+
+    /// Whether display columns run along image rows.
+    pub fn swaps_axes(self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    pub fn display_size(self, image: Size) -> Size {
+        if self.swaps_axes() {
+            Size::new(image.height, image.width)
+        } else {
+            image
+        }
+    }
+
+    /// Maps a point of an image of `image` size into display coordinates.
+    pub fn to_display(self, point: Point, image: Size) -> Point {
+        let mut point = point;
+        let mut size = image;
+        if self.flipped {
+            point.x = size.width - point.x;
+        }
+        for _ in 0..self.quarter_turns {
+            point = Point::new(size.height - point.y, point.x);
+            size = Size::new(size.height, size.width);
+        }
+        point
+    }
+
+    /// Maps a display point back into the coordinates of an image of `image` size.
+    pub fn to_image(self, point: Point, image: Size) -> Point {
+        self.inverse().to_display(point, self.display_size(image))
+    }
+
+    /// Maps an image direction, e.g. `(1, 0)` towards the last column, onto
+    /// the display.
+    fn direction(self, (mut dx, mut dy): (i8, i8)) -> (i8, i8) {
+        if self.flipped {
+            dx = -dx;
+        }
+        for _ in 0..self.quarter_turns {
+            (dx, dy) = (-dy, dx);
+        }
+        (dx, dy)
+    }
+}
+
+/// Patient directions of increasing column (`row`) and increasing row
+/// (`column`) index, as letters such as `L`, `P` or `HL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orientation {
+    pub row: String,
+    pub column: String,
+}
+
+/// Orientation letters for the four display edges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EdgeLabels {
+    pub left: String,
+    pub right: String,
+    pub top: String,
+    pub bottom: String,
+}
+
+impl Orientation {
+    /// Reads Image Orientation (Patient), falling back to Patient Orientation
+    /// (0020,0020) used by projection radiographs.
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        if let Some(cosines) = attribute_f64s(object, "ImageOrientationPatient") {
+            if let [rx, ry, rz, cx, cy, cz, ..] = cosines[..] {
+                return Self::from_directions([rx, ry, rz], [cx, cy, cz]);
+            }
+        }
+
+        let value = attribute_text(object, "PatientOrientation")?;
+        let (row, column) = value.split_once('\\')?;
+        let valid = |letters: &str| {
+            !letters.is_empty() && letters.chars().all(|letter| opposite(letter).is_some())
+        };
+        (valid(row.trim()) && valid(column.trim())).then(|| Self {
+            row: row.trim().to_string(),
+            column: column.trim().to_string(),
+        })
+    }
+
+    pub fn from_plane(plane: &ImagePlane) -> Option<Self> {
+        Self::from_directions(plane.row_direction, plane.column_direction)
+    }
+
+    pub fn from_directions(row: [f64; 3], column: [f64; 3]) -> Option<Self> {
+        Some(Self {
+            row: direction_label(row)?,
+            column: direction_label(column)?,
+        })
+    }
+
+    pub fn edge_labels(&self, transform: ViewTransform) -> EdgeLabels {
+        let mut labels = EdgeLabels::default();
+        let edges = [
+            ((1, 0), self.row.clone()),
+            ((-1, 0), opposite_label(&self.row)),
+            ((0, 1), self.column.clone()),
+            ((0, -1), opposite_label(&self.column)),
+        ];
+        for (direction, label) in edges {
+            let edge = match transform.direction(direction) {
+                (1, 0) => &mut labels.right,
+                (-1, 0) => &mut labels.left,
+                (0, 1) => &mut labels.bottom,
+                _ => &mut labels.top,
+            };
+            *edge = label;
+        }
+        labels
+    }
+}
+
+/// Letters for a patient-space direction, most significant axis first.
+fn direction_label(direction: [f64; 3]) -> Option<String> {
+    let mut axes = [
+        (direction[0], 'L', 'R'),
+        (direction[1], 'P', 'A'),
+        (direction[2], 'H', 'F'),
+    ];
+    axes.sort_by(|a, b| b.0.abs().total_cmp(&a.0.abs()));
+    let label: String = axes
+        .iter()
+        .filter(|(cosine, ..)| cosine.abs() >= OBLIQUE_THRESHOLD)
+        .map(|&(cosine, positive, negative)| if cosine > 0.0 { positive } else { negative })
+        .collect();
+    (!label.is_empty()).then_some(label)
+}
+
+fn opposite(letter: char) -> Option<char> {
+    match letter {
+        'L' => Some('R'),
+        'R' => Some('L'),
+        'A' => Some('P'),
+        'P' => Some('A'),
+        'H' => Some('F'),
+        'F' => Some('H'),
+        _ => None,
+    }
+}
+
+fn opposite_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|letter| opposite(letter).unwrap_or(letter))
+        .collect()
+}
@@ -0,0 +1,235 @@
+use dicom::core::Tag;
+use dicom::object::InMemDicomObject;
+use iced::Color;
+use std::fmt;
+
+/// Repeating groups 6000–601E, one per overlay plane.
+pub const OVERLAY_GROUPS: std::ops::RangeInclusive<u16> = 0x6000..=0x601E;
+
+/// Overlay Plane module (PS3.3 C.9.2) attributes of one 60xx group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayPlane {
+    pub group: u16,
+    pub rows: u32,
+    pub columns: u32,
+    /// 1-based row and column of the first overlay point relative to the image.
+    pub origin: (i32, i32),
+    pub frame_count: u32,
+    /// 1-based image frame the first overlay frame applies to.
+    pub image_frame_origin: u32,
+    /// Whether Number of Frames in Overlay was present; single-frame overlays
+    /// without it are shown on every frame.
+    pub multi_frame: bool,
+    pub kind: String,
+    pub label: Option<String>,
+    pub source: OverlaySource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverlaySource {
+    /// Bit-packed Overlay Data (60xx,3000), least significant bit first.
+    Data(Vec<u8>),
+    /// Retired encoding using an unused high bit of the Pixel Data samples.
+    PixelDataBit(u16),
+}
+
+/// One overlay frame as a bitmap, positioned relative to the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayBitmap {
+    pub group: u16,
+    pub rows: u32,
+    pub columns: u32,
+    /// 0-based row and column of the top-left overlay point in the image.
+    pub origin: (i32, i32),
+    pub bits: Vec<bool>,
+}
+
+impl OverlayPlane {
+    pub fn read_all(object: &InMemDicomObject) -> Vec<Self> {
+        OVERLAY_GROUPS
+            .step_by(2)
+            .filter_map(|group| Self::from_group(object, group))
+            .collect()
+    }
+
+    fn from_group(object: &InMemDicomObject, group: u16) -> Option<Self> {
+        let uint = |element: u16| -> Option<u32> {
+            object
+                .element_opt(Tag(group, element))
+                .ok()
+                .flatten()
+                .and_then(|element| element.to_int::<u32>().ok())
+        };
+        let text = |element: u16| -> Option<String> {
+            object
+                .element_opt(Tag(group, element))
+                .ok()
+                .flatten()
+                .and_then(|element| element.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let rows = uint(0x0010)?;
+        let columns = uint(0x0011)?;
+        let origin = object
+            .element_opt(Tag(group, 0x0050))
+            .ok()
+            .flatten()
+            .and_then(|element| element.to_multi_int::<i32>().ok())
+            .and_then(|values| Some((*values.first()?, *values.get(1)?)))
+            .unwrap_or((1, 1));
+
+        let data = object
+            .element_opt(Tag(group, 0x3000))
+            .ok()
+            .flatten()
+            .and_then(|element| element.to_bytes().ok())
+            .map(|bytes| bytes.into_owned());
+        let source = match data {
+            Some(bytes) if !bytes.is_empty() => OverlaySource::Data(bytes),
+            _ => {
+                let bits_allocated = uint(0x0100).unwrap_or(1);
+                let bit_position = uint(0x0102).unwrap_or(0);
+                if bits_allocated <= 1 {
+                    return None;
+                }
+                OverlaySource::PixelDataBit(bit_position as u16)
+            }
+        };
+        let frame_count = uint(0x0015);
+
+        Some(Self {
+            group,
+            rows,
+            columns,
+            origin,
+            frame_count: frame_count.unwrap_or(1).max(1),
+            image_frame_origin: uint(0x0051).unwrap_or(1).max(1),
+            multi_frame: frame_count.is_some(),
+            kind: text(0x0040).unwrap_or_else(|| "G".to_string()),
+            label: text(0x1500).or_else(|| text(0x0022)),
+            source,
+        })
+    }
+
+    /// Index of the overlay frame shown on 0-based `image_frame`, if any.
+    pub fn overlay_frame(&self, image_frame: u32) -> Option<u32> {
+        if !self.multi_frame {
+            return Some(0);
+        }
+        let index = (image_frame + 1).checked_sub(self.image_frame_origin)?;
+        (index < self.frame_count).then_some(index)
+    }
+
+    pub fn name(&self) -> String {
+        let kind = match self.kind.as_str() {
+            "R" => "ROI",
+            _ => "Graphics",
+        };
+        match &self.label {
+            Some(label) => format!("{:04X} {kind}: {label}", self.group),
+            None => format!("{:04X} {kind}", self.group),
+        }
+    }
+
+    /// Extracts the bitmap for a 0-based image frame. `raw_samples` holds the
+    /// unmasked Pixel Data samples of that frame, used for embedded overlays.
+    pub fn bitmap(&self, image_frame: u32, raw_samples: Option<&[u32]>) -> Option<OverlayBitmap> {
+        let overlay_frame = self.overlay_frame(image_frame)?;
+        let pixel_count = (self.rows * self.columns) as usize;
+        let bits = match &self.source {
+            OverlaySource::Data(bytes) => {
+                let start = overlay_frame as usize * pixel_count;
+                (start..start + pixel_count)
+                    .map(|bit| {
+                        bytes
+                            .get(bit / 8)
+                            .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+                    })
+                    .collect()
+            }
+            OverlaySource::PixelDataBit(position) => {
+                let samples = raw_samples?;
+                if samples.len() < pixel_count {
+                    return None;
+                }
+                samples[..pixel_count]
+                    .iter()
+                    .map(|sample| sample & (1 << position) != 0)
+                    .collect()
+            }
+        };
+
+        Some(OverlayBitmap {
+            group: self.group,
+            rows: self.rows,
+            columns: self.columns,
+            origin: (self.origin.0 - 1, self.origin.1 - 1),
+            bits,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayColor {
+    Yellow,
+    Green,
+    Cyan,
+    Magenta,
+    Red,
+    White,
+}
+
+impl OverlayColor {
+    pub const ALL: [OverlayColor; 6] = [
+        OverlayColor::Yellow,
+        OverlayColor::Green,
+        OverlayColor::Cyan,
+        OverlayColor::Magenta,
+        OverlayColor::Red,
+        OverlayColor::White,
+    ];
+
+    pub fn to_color(self) -> Color {
+        match self {
+            OverlayColor::Yellow => Color::from_rgb(1.0, 0.9, 0.1),
+            OverlayColor::Green => Color::from_rgb(0.2, 1.0, 0.3),
+            OverlayColor::Cyan => Color::from_rgb(0.2, 0.9, 1.0),
+            OverlayColor::Magenta => Color::from_rgb(1.0, 0.3, 1.0),
+            OverlayColor::Red => Color::from_rgb(1.0, 0.25, 0.2),
+            OverlayColor::White => Color::WHITE,
+        }
+    }
+}
+
+impl fmt::Display for OverlayColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OverlayColor::Yellow => "Yellow",
+            OverlayColor::Green => "Green",
+            OverlayColor::Cyan => "Cyan",
+            OverlayColor::Magenta => "Magenta",
+            OverlayColor::Red => "Red",
+            OverlayColor::White => "White",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Display settings of one overlay group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayStyle {
+    pub visible: bool,
+    pub color: OverlayColor,
+}
+
+impl OverlayStyle {
+    pub fn for_group(group: u16) -> Self {
+        let index = ((group - OVERLAY_GROUPS.start()) / 2) as usize;
+        Self {
+            visible: true,
+            color: OverlayColor::ALL[index % OverlayColor::ALL.len()],
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use super::attributes::attribute_text;
+use dicom::object::InMemDicomObject;
+use std::fmt;
+
+/// Nesting limit for indirect segments, guarding against offset cycles.
+const MAX_SEGMENT_DEPTH: usize = 8;
+
+/// Red, green and blue Palette Color LUTs (PS3.3 C.7.6.3.1.5), expanded and
+/// scaled to 8 bits per channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteLut {
+    /// Stored pixel value mapped to the first entry.
+    pub first_mapped: i32,
+    /// A Supplemental Palette on a monochrome image colours only the values
+    /// it maps; PALETTE COLOR images are coloured entirely.
+    pub supplemental: bool,
+    red: Vec<u8>,
+    green: Vec<u8>,
+    blue: Vec<u8>,
+}
+
+impl PaletteLut {
+    /// Reads the palette of an object, if it has one. `signed` tells whether
+    /// the pixel data, and so the first mapped value, is signed.
+    pub fn from_object(object: &InMemDicomObject, signed: bool) -> Result<Option<Self>, String> {
+        let Some(red_descriptor) = descriptor(object, "Red", signed)? else {
+            return Ok(None);
+        };
+        let channel = |color: &str| -> Result<Vec<u8>, String> {
+            let descriptor = descriptor(object, color, signed)?
+                .ok_or_else(|| format!("{color} Palette Color LUT Descriptor is missing"))?;
+            let entries = lut_data(object, color, &descriptor)?;
+            Ok(scale_to_8_bits(&entries, descriptor.bits))
+        };
+
+        Ok(Some(Self {
+            first_mapped: red_descriptor.first_mapped,
+            supplemental: attribute_text(object, "PhotometricInterpretation")
+                .is_some_and(|photometric| photometric.starts_with("MONOCHROME")),
+            red: channel("Red")?,
+            green: channel("Green")?,
+            blue: channel("Blue")?,
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.red.len().min(self.green.len()).min(self.blue.len())
+    }
+
+    pub fn contains(&self, stored: i32) -> bool {
+        let index = stored as i64 - self.first_mapped as i64;
+        index >= 0 && index < self.len() as i64
+    }
+
+    /// Colour of a stored value; values outside the table take the first or
+    /// last entry.
+    pub fn lookup(&self, stored: i32) -> [u8; 3] {
+        let last = self.len().saturating_sub(1) as i64;
+        let index = (stored as i64 - self.first_mapped as i64).clamp(0, last) as usize;
+        [self.red[index], self.green[index], self.blue[index]]
+    }
+}
+
+struct Descriptor {
+    entries: usize,
+    first_mapped: i32,
+    bits: u16,
+}
+
+fn descriptor(
+    object: &InMemDicomObject,
+    color: &str,
+    signed: bool,
+) -> Result<Option<Descriptor>, String> {
+    let name = format!("{color}PaletteColorLookupTableDescriptor");
+    let Ok(element) = object.element_by_name(&name) else {
+        return Ok(None);
+    };
+    let values = element
+        .to_multi_int::<i32>()
+        .map_err(|err| format!("Invalid {color} Palette Color LUT Descriptor: {err}"))?;
+    let [entries, first_mapped, bits] = values[..] else {
+        return Err(format!(
+            "{color} Palette Color LUT Descriptor has {} values, expected 3",
+            values.len()
+        ));
+    };
+
+    // The descriptor may be encoded as US even for signed pixel data.
+    let first_mapped = if signed && first_mapped > i16::MAX as i32 {
+        first_mapped - 0x1_0000
+    } else {
+        first_mapped
+    };
+    Ok(Some(Descriptor {
+        entries: match entries & 0xFFFF {
+            0 => 0x1_0000,
+            entries => entries as usize,
+        },
+        first_mapped,
+        bits: if bits == 8 { 8 } else { 16 },
+    }))
+}
+
+/// Reads the plain or segmented LUT data of one channel as one word per entry.
+fn lut_data(
+    object: &InMemDicomObject,
+    color: &str,
+    descriptor: &Descriptor,
+) -> Result<Vec<u16>, String> {
+    let words = |name: String| -> Option<Result<Vec<u16>, String>> {
+        let element = object.element_by_name(&name).ok()?;
+        Some(
+            element
+                .to_multi_int::<u16>()
+                .map_err(|err| format!("Invalid {name}: {err}")),
+        )
+    };
+
+    let mut entries = if let Some(data) = words(format!("{color}PaletteColorLookupTableData")) {
+        let data = data?;
+        if descriptor.bits == 8 && data.len() * 2 == descriptor.entries {
+            // Two 8-bit entries packed into each word, low byte first.
+            data.iter()
+                .flat_map(|word| [word & 0xFF, word >> 8])
+                .collect()
+        } else {
+            data
+        }
+    } else if let Some(data) = words(format!("Segmented{color}PaletteColorLookupTableData")) {
+        let mut entries = Vec::new();
+        expand_segments(&data?, 0, None, 0, &mut entries)?;
+        entries
+    } else {
+        return Err(format!("{color} Palette Color LUT Data is missing"));
+    };
+
+    if entries.is_empty() {
+        return Err(format!("{color} Palette Color LUT Data is empty"));
+    }
+    let last = *entries.last().unwrap_or(&0);
+    entries.resize(descriptor.entries, last);
+    Ok(entries)
+}
+
+/// Expands Segmented Palette Color LUT Data (PS3.3 C.7.9.2) into `entries`,
+/// starting at word `start` and replaying at most `limit` segments.
+fn expand_segments(
+    words: &[u16],
+    start: usize,
+    limit: Option<usize>,
+    depth: usize,
+    entries: &mut Vec<u16>,
+) -> Result<(), String> {
+    if depth > MAX_SEGMENT_DEPTH {
+        return Err("Segmented Palette Color LUT nests too deeply".to_string());
+    }
+    let truncated = || "Segmented Palette Color LUT Data is truncated".to_string();
+    let mut position = start;
+    let mut segments = 0;
+
+    while position < words.len() && limit.is_none_or(|limit| segments < limit) {
+        let opcode = words[position];
+        let length = *words.get(position + 1).ok_or_else(truncated)? as usize;
+        match opcode {
+            0 => {
+                let values = words
+                    .get(position + 2..position + 2 + length)
+                    .ok_or_else(truncated)?;
+                entries.extend_from_slice(values);
+                position += 2 + length;
+            }
+            1 => {
+                let end = *words.get(position + 2).ok_or_else(truncated)? as f64;
+                let begin = *entries.last().ok_or_else(|| {
+                    "Segmented Palette Color LUT starts with a linear segment".to_string()
+                })? as f64;
+                entries.extend((1..=length).map(|step| {
+                    (begin + (end - begin) * step as f64 / length as f64).round() as u16
+                }));
+                position += 3;
+            }
+            2 => {
+                let low = *words.get(position + 2).ok_or_else(truncated)? as usize;
+                let high = *words.get(position + 3).ok_or_else(truncated)? as usize;
+                let byte_offset = low | (high << 16);
+                expand_segments(words, byte_offset / 2, Some(length), depth + 1, entries)?;
+                position += 4;
+            }
+            other => {
+                return Err(format!(
+                    "Unknown Segmented Palette Color LUT opcode {other}"
+                ))
+            }
+        }
+        segments += 1;
+    }
+    Ok(())
+}
+
+fn scale_to_8_bits(entries: &[u16], bits: u16) -> Vec<u8> {
+    // Some writers store 8-bit palettes in the high byte of each word.
+    let high_byte = bits == 16 || entries.iter().any(|entry| *entry > 0xFF);
+    entries
+        .iter()
+        .map(|&entry| {
+            if high_byte {
+                (entry >> 8) as u8
+            } else {
+                entry as u8
+            }
+        })
+        .collect()
+}
+
+/// Pseudocolor map applied to windowed monochrome values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMap {
+    #[default]
+    Grayscale,
+    HotIron,
+    Pet,
+    Rainbow,
+}
+
+impl ColorMap {
+    pub const ALL: [ColorMap; 4] = [
+        ColorMap::Grayscale,
+        ColorMap::HotIron,
+        ColorMap::Pet,
+        ColorMap::Rainbow,
+    ];
+
+    pub fn apply(self, gray: u8) -> [u8; 3] {
+        let i = gray as i32;
+        let ramp = |value: i32| value.clamp(0, 255) as u8;
+        match self {
+            ColorMap::Grayscale => [gray, gray, gray],
+            // Hot Iron standard color palette (PS3.6 B.1.1).
+            ColorMap::HotIron => [ramp(2 * i), ramp(2 * (i - 128)), ramp(4 * (i - 192))],
+            // PET standard color palette (PS3.6 B.1.2).
+            ColorMap::Pet => {
+                let red = ramp(2 * (i - 64) + 1);
+                let green = if i < 128 {
+                    ramp(2 * i)
+                } else if i < 192 {
+                    ramp(255 - 4 * (i - 128))
+                } else {
+                    ramp(4 * (i - 192))
+                };
+                let blue = if i < 64 {
+                    ramp(4 * i)
+                } else if i < 128 {
+                    ramp(255 - 4 * (i - 64))
+                } else if i < 192 {
+                    0
+                } else {
+                    ramp(4 * (i - 192))
+                };
+                [red, green, blue]
+            }
+            // Hue sweep from blue (low) to red (high).
+            ColorMap::Rainbow => {
+                let hue = (1.0 - gray as f32 / 255.0) * 240.0;
+                let sector = hue / 60.0;
+                let fraction = ((1.0 - (sector % 2.0 - 1.0).abs()) * 255.0).round() as u8;
+                match sector as u32 {
+                    0 => [255, fraction, 0],
+                    1 => [fraction, 255, 0],
+                    2 => [0, 255, fraction],
+                    _ => [0, fraction, 255],
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColorMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorMap::Grayscale => "Grayscale",
+            ColorMap::HotIron => "Hot iron",
+            ColorMap::Pet => "PET",
+            ColorMap::Rainbow => "Rainbow",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,523 @@
+use super::attributes::attribute_text;
+use super::presentation_state::GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE;
+use super::{FrameData, Shape, ViewTransform, Window};
+use dicom::core::value::{DataSetSequence, PrimitiveValue};
+use dicom::core::{DataElement, Tag, VR};
+use dicom::dictionary_std::{tags, uids};
+use dicom::object::mem::InMemElement;
+use dicom::object::meta::FileMetaTableBuilder;
+use dicom::object::{DefaultDicomObject, InMemDicomObject};
+use iced::{Point, Rectangle};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CONTENT_LABEL: &str = "DICOMANCER";
+const CONTENT_DESCRIPTION: &str = "Window, orientation and measurements";
+const MEASUREMENT_LAYER: &str = "MEASUREMENTS";
+const PRESENTATION_SERIES_NUMBER: &str = "9001";
+/// Patient and General Study attributes copied from the referenced images,
+/// with the VR to write them empty when the images lack them.
+const COPIED_ATTRIBUTES: [(Tag, VR); 10] = [
+    (tags::PATIENT_NAME, VR::PN),
+    (tags::PATIENT_ID, VR::LO),
+    (tags::PATIENT_BIRTH_DATE, VR::DA),
+    (tags::PATIENT_SEX, VR::CS),
+    (tags::STUDY_INSTANCE_UID, VR::UI),
+    (tags::STUDY_DATE, VR::DA),
+    (tags::STUDY_TIME, VR::TM),
+    (tags::REFERRING_PHYSICIAN_NAME, VR::PN),
+    (tags::STUDY_ID, VR::SH),
+    (tags::ACCESSION_NUMBER, VR::SH),
+];
+
+/// A measurement to store as a graphic annotation, in image pixel
+/// coordinates.
+pub struct PresentedAnnotation {
+    pub frame_index: u32,
+    pub shape: Shape,
+    pub label: String,
+}
+
+/// How one image is displayed, to store in a presentation state.
+pub struct PresentedImage<'a> {
+    pub object: &'a InMemDicomObject,
+    pub frame: &'a FrameData,
+    pub frame_count: u32,
+    pub window: Option<Window>,
+    /// Region shown; the whole image when `None`.
+    pub displayed_area: Option<Rectangle>,
+    pub annotations: Vec<PresentedAnnotation>,
+}
+
+/// Builds a Grayscale Softcopy Presentation State (PS3.3 A.33.1) that
+/// references `images` and records their windows, displayed areas and
+/// measurements with the spatial transformation `transform`.
+///
+/// The images must belong to one study and share the rescale of the first,
+/// which becomes the presentation state's Modality LUT.
+pub fn build_presentation_state(
+    images: &[PresentedImage<'_>],
+    transform: ViewTransform,
+) -> Result<DefaultDicomObject, String> {
+    let first = images.first().ok_or("No images to reference")?;
+    if !first.frame.is_monochrome() {
+        return Err(String::from(
+            "Grayscale presentation states apply to monochrome images only",
+        ));
+    }
+    // Study Instance UID is Type 1; an empty value is rejected by readers.
+    if attribute_text(first.object, "StudyInstanceUID").is_none() {
+        return Err(String::from("The images have no Study Instance UID"));
+    }
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| format!("System clock is before 1970 ({err})"))?
+        .as_secs();
+    let (date, time) = date_time(seconds);
+    let sop_instance_uid = new_uid();
+
+    let mut object = InMemDicomObject::new_empty();
+    // Strings are held decoded, so the whole object is written as UTF-8.
+    object.put(element(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 192"));
+    object.put(element(
+        tags::SOP_CLASS_UID,
+        VR::UI,
+        GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
+    ));
+    object.put(element(
+        tags::SOP_INSTANCE_UID,
+        VR::UI,
+        sop_instance_uid.as_str(),
+    ));
+    for (tag, vr) in COPIED_ATTRIBUTES {
+        match first.object.get(tag) {
+            Some(copied) => object.put(copied.clone()),
+            None => object.put(DataElement::new(tag, vr, PrimitiveValue::Empty)),
+        };
+    }
+
+    // Presentation Series, General Equipment and Presentation State
+    // Identification.
+    object.put(element(tags::MODALITY, VR::CS, "PR"));
+    object.put(element(
+        tags::SERIES_INSTANCE_UID,
+        VR::UI,
+        new_uid().as_str(),
+    ));
+    object.put(element(
+        tags::SERIES_NUMBER,
+        VR::IS,
+        PRESENTATION_SERIES_NUMBER,
+    ));
+    object.put(element(tags::MANUFACTURER, VR::LO, "Dicomancer"));
+    object.put(element(
+        tags::SOFTWARE_VERSIONS,
+        VR::LO,
+        env!("CARGO_PKG_VERSION"),
+    ));
+    object.put(element(tags::INSTANCE_NUMBER, VR::IS, "1"));
+    object.put(element(tags::CONTENT_LABEL, VR::CS, CONTENT_LABEL));
+    object.put(element(
+        tags::CONTENT_DESCRIPTION,
+        VR::LO,
+        CONTENT_DESCRIPTION,
+    ));
+    object.put(DataElement::new(
+        tags::CONTENT_CREATOR_NAME,
+        VR::PN,
+        PrimitiveValue::Empty,
+    ));
+    object.put(element(
+        tags::PRESENTATION_CREATION_DATE,
+        VR::DA,
+        date.as_str(),
+    ));
+    object.put(element(
+        tags::PRESENTATION_CREATION_TIME,
+        VR::TM,
+        time.as_str(),
+    ));
+    object.put(element(tags::TIMEZONE_OFFSET_FROM_UTC, VR::SH, "+0000"));
+
+    // Presentation State Relationship, by series.
+    let mut series: BTreeMap<String, Vec<InMemDicomObject>> = BTreeMap::new();
+    for image in images {
+        let series_instance_uid = attribute_text(image.object, "SeriesInstanceUID")
+            .ok_or("An image has no Series Instance UID")?;
+        series
+            .entry(series_instance_uid)
+            .or_default()
+            .push(image_reference(image, None)?);
+    }
+    object.put(sequence(
+        tags::REFERENCED_SERIES_SEQUENCE,
+        series
+            .into_iter()
+            .map(|(series_instance_uid, references)| {
+                InMemDicomObject::from_element_iter([
+                    element(
+                        tags::SERIES_INSTANCE_UID,
+                        VR::UI,
+                        series_instance_uid.as_str(),
+                    ),
+                    sequence(tags::REFERENCED_IMAGE_SEQUENCE, references),
+                ])
+            })
+            .collect(),
+    ));
+
+    // Modality LUT, Softcopy VOI LUT and Softcopy Presentation LUT.
+    let rescale = &first.frame.rescale;
+    object.put(element(
+        tags::RESCALE_INTERCEPT,
+        VR::DS,
+        decimal_string(rescale.intercept).as_str(),
+    ));
+    object.put(element(
+        tags::RESCALE_SLOPE,
+        VR::DS,
+        decimal_string(rescale.slope).as_str(),
+    ));
+    object.put(element(
+        tags::RESCALE_TYPE,
+        VR::LO,
+        rescale.unit.as_deref().unwrap_or("US"),
+    ));
+    let mut windows = Vec::new();
+    for image in images {
+        let Some(window) = image.window else {
+            continue;
+        };
+        windows.push(InMemDicomObject::from_element_iter([
+            sequence(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                vec![image_reference(image, None)?],
+            ),
+            element(
+                tags::WINDOW_CENTER,
+                VR::DS,
+                decimal_string(window.center).as_str(),
+            ),
+            element(
+                tags::WINDOW_WIDTH,
+                VR::DS,
+                decimal_string(window.width).as_str(),
+            ),
+        ]));
+    }
+    if !windows.is_empty() {
+        object.put(sequence(tags::SOFTCOPY_VOILUT_SEQUENCE, windows));
+    }
+    let shape = if first.frame.invert {
+        "INVERSE"
+    } else {
+        "IDENTITY"
+    };
+    object.put(element(tags::PRESENTATION_LUT_SHAPE, VR::CS, shape));
+
+    // Displayed Area, one item per image as their sizes may differ.
+    let mut areas = Vec::new();
+    for image in images {
+        let area = image.displayed_area.unwrap_or(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: image.frame.columns as f32,
+            height: image.frame.rows as f32,
+        });
+        // Corners are 1-based (column, row) pixel indices, inclusive.
+        let top_left = [area.x.round() as i32 + 1, area.y.round() as i32 + 1];
+        let bottom_right = [
+            (area.x + area.width).round() as i32,
+            (area.y + area.height).round() as i32,
+        ];
+        let scale = match image.frame.calibration {
+            Some(calibration) => element(
+                tags::PRESENTATION_PIXEL_SPACING,
+                VR::DS,
+                format!(
+                    "{}\\{}",
+                    decimal_string(calibration.spacing[0]),
+                    decimal_string(calibration.spacing[1])
+                )
+                .as_str(),
+            ),
+            None => element(tags::PRESENTATION_PIXEL_ASPECT_RATIO, VR::IS, "1\\1"),
+        };
+        areas.push(InMemDicomObject::from_element_iter([
+            sequence(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                vec![image_reference(image, None)?],
+            ),
+            DataElement::new(
+                tags::DISPLAYED_AREA_TOP_LEFT_HAND_CORNER,
+                VR::SL,
+                PrimitiveValue::I32(top_left.into_iter().collect()),
+            ),
+            DataElement::new(
+                tags::DISPLAYED_AREA_BOTTOM_RIGHT_HAND_CORNER,
+                VR::SL,
+                PrimitiveValue::I32(bottom_right.into_iter().collect()),
+            ),
+            element(tags::PRESENTATION_SIZE_MODE, VR::CS, "SCALE TO FIT"),
+            scale,
+        ]));
+    }
+    object.put(sequence(tags::DISPLAYED_AREA_SELECTION_SEQUENCE, areas));
+
+    // Graphic Annotation and Graphic Layer, one item per annotated frame.
+    let mut annotations = Vec::new();
+    for image in images {
+        let mut frames: BTreeMap<u32, Vec<&PresentedAnnotation>> = BTreeMap::new();
+        for annotation in &image.annotations {
+            frames
+                .entry(annotation.frame_index)
+                .or_default()
+                .push(annotation);
+        }
+        for (frame_index, drawn) in frames {
+            let frame = (image.frame_count > 1).then_some(frame_index);
+            let graphics = drawn
+                .iter()
+                .flat_map(|annotation| graphic_objects(&annotation.shape))
+                .collect();
+            let texts = drawn
+                .iter()
+                .filter_map(|annotation| text_object(annotation))
+                .collect();
+            annotations.push(InMemDicomObject::from_element_iter([
+                sequence(
+                    tags::REFERENCED_IMAGE_SEQUENCE,
+                    vec![image_reference(image, frame)?],
+                ),
+                element(tags::GRAPHIC_LAYER, VR::CS, MEASUREMENT_LAYER),
+                sequence(tags::GRAPHIC_OBJECT_SEQUENCE, graphics),
+                sequence(tags::TEXT_OBJECT_SEQUENCE, texts),
+            ]));
+        }
+    }
+    if !annotations.is_empty() {
+        object.put(sequence(tags::GRAPHIC_ANNOTATION_SEQUENCE, annotations));
+        object.put(sequence(
+            tags::GRAPHIC_LAYER_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([
+                element(tags::GRAPHIC_LAYER, VR::CS, MEASUREMENT_LAYER),
+                DataElement::new(tags::GRAPHIC_LAYER_ORDER, VR::IS, PrimitiveValue::from("1")),
+                DataElement::new(
+                    tags::GRAPHIC_LAYER_RECOMMENDED_DISPLAY_GRAYSCALE_VALUE,
+                    VR::US,
+                    PrimitiveValue::from(u16::MAX),
+                ),
+                element(tags::GRAPHIC_LAYER_DESCRIPTION, VR::LO, "Measurements"),
+            ])],
+        ));
+    }
+
+    // Spatial Transformation: the flip is applied before the rotation.
+    object.put(DataElement::new(
+        tags::IMAGE_ROTATION,
+        VR::US,
+        PrimitiveValue::from(u16::from(transform.quarter_turns) * 90),
+    ));
+    object.put(element(
+        tags::IMAGE_HORIZONTAL_FLIP,
+        VR::CS,
+        if transform.flipped { "Y" } else { "N" },
+    ));
+
+    object
+        .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+        .map_err(|err| format!("Unable to build the file meta group ({err})"))
+}
+
+/// Writes a presentation state as a DICOM Part 10 file.
+pub fn save_presentation_state(object: &DefaultDicomObject, path: &Path) -> Result<(), String> {
+    object.write_to_file(path).map_err(|err| {
+        format!(
+            "{}: failed to write presentation state ({err})",
+            path.display()
+        )
+    })
+}
+
+fn element(tag: Tag, vr: VR, value: &str) -> InMemElement {
+    DataElement::new(tag, vr, PrimitiveValue::from(value))
+}
+
+fn sequence(tag: Tag, items: Vec<InMemDicomObject>) -> InMemElement {
+    DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+}
+
+/// DS value of at most the 16 characters the VR allows.
+fn decimal_string(value: f64) -> String {
+    let text = value.to_string();
+    if text.len() <= 16 {
+        return text;
+    }
+    (0..=10)
+        .rev()
+        .map(|precision| format!("{value:.precision$}"))
+        .find(|text| text.len() <= 16)
+        .unwrap_or_else(|| format!("{value:.6e}"))
+}
+
+/// Referenced Image Sequence item for an image, or one frame of it.
+fn image_reference(
+    image: &PresentedImage<'_>,
+    frame: Option<u32>,
+) -> Result<InMemDicomObject, String> {
+    let sop_class_uid =
+        attribute_text(image.object, "SOPClassUID").ok_or("An image has no SOP Class UID")?;
+    let sop_instance_uid =
+        attribute_text(image.object, "SOPInstanceUID").ok_or("An image has no SOP Instance UID")?;
+    let mut reference = InMemDicomObject::from_element_iter([
+        element(
+            tags::REFERENCED_SOP_CLASS_UID,
+            VR::UI,
+            sop_class_uid.as_str(),
+        ),
+        element(
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            VR::UI,
+            sop_instance_uid.as_str(),
+        ),
+    ]);
+    if let Some(frame_index) = frame {
+        reference.put(element(
+            tags::REFERENCED_FRAME_NUMBER,
+            VR::IS,
+            (frame_index + 1).to_string().as_str(),
+        ));
+    }
+    Ok(reference)
+}
+
+/// Graphic Object Sequence items tracing a measurement.
+fn graphic_objects(shape: &Shape) -> Vec<InMemDicomObject> {
+    let graphics: Vec<(&str, Vec<Point>)> = match shape {
+        Shape::Line(a, b) => vec![("POLYLINE", vec![*a, *b])],
+        Shape::Angle { start, vertex, end } => vec![("POLYLINE", vec![*start, *vertex, *end])],
+        Shape::Cobb([a, b, c, d]) => vec![("POLYLINE", vec![*a, *b]), ("POLYLINE", vec![*c, *d])],
+        Shape::Rectangle(a, b) => vec![(
+            "POLYLINE",
+            vec![*a, Point::new(b.x, a.y), *b, Point::new(a.x, b.y), *a],
+        )],
+        Shape::Ellipse(a, b) => {
+            let center = Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+            let (rx, ry) = ((a.x - b.x).abs() / 2.0, (a.y - b.y).abs() / 2.0);
+            let horizontal = [
+                Point::new(center.x - rx, center.y),
+                Point::new(center.x + rx, center.y),
+            ];
+            let vertical = [
+                Point::new(center.x, center.y - ry),
+                Point::new(center.x, center.y + ry),
+            ];
+            if rx <= 0.0 || ry <= 0.0 {
+                vec![("POLYLINE", vec![*a, *b])]
+            } else if rx >= ry {
+                vec![("ELLIPSE", [horizontal, vertical].concat())]
+            } else {
+                vec![("ELLIPSE", [vertical, horizontal].concat())]
+            }
+        }
+        Shape::Freehand(points) => {
+            let mut closed = points.clone();
+            closed.extend(points.first().copied());
+            vec![("POLYLINE", closed)]
+        }
+    };
+    graphics
+        .into_iter()
+        .filter(|(_, points)| !points.is_empty())
+        .map(|(kind, points)| {
+            InMemDicomObject::from_element_iter([
+                element(tags::GRAPHIC_ANNOTATION_UNITS, VR::CS, "PIXEL"),
+                DataElement::new(tags::GRAPHIC_DIMENSIONS, VR::US, PrimitiveValue::from(2u16)),
+                DataElement::new(
+                    tags::NUMBER_OF_GRAPHIC_POINTS,
+                    VR::US,
+                    PrimitiveValue::from(points.len() as u16),
+                ),
+                DataElement::new(
+                    tags::GRAPHIC_DATA,
+                    VR::FL,
+                    PrimitiveValue::F32(
+                        points.iter().flat_map(|point| [point.x, point.y]).collect(),
+                    ),
+                ),
+                element(tags::GRAPHIC_TYPE, VR::CS, kind),
+                element(tags::GRAPHIC_FILLED, VR::CS, "N"),
+            ])
+        })
+        .collect()
+}
+
+/// Text Object Sequence item labelling a measurement at its first point.
+fn text_object(annotation: &PresentedAnnotation) -> Option<InMemDicomObject> {
+    let anchor = match &annotation.shape {
+        Shape::Line(point, _) | Shape::Rectangle(point, _) | Shape::Ellipse(point, _) => *point,
+        Shape::Angle { vertex, .. } => *vertex,
+        Shape::Cobb(points) => points[0],
+        Shape::Freehand(points) => *points.first()?,
+    };
+    Some(InMemDicomObject::from_element_iter([
+        element(tags::ANCHOR_POINT_ANNOTATION_UNITS, VR::CS, "PIXEL"),
+        element(
+            tags::UNFORMATTED_TEXT_VALUE,
+            VR::ST,
+            annotation.label.as_str(),
+        ),
+        DataElement::new(
+            tags::ANCHOR_POINT,
+            VR::FL,
+            PrimitiveValue::F32([anchor.x, anchor.y].into_iter().collect()),
+        ),
+        element(tags::ANCHOR_POINT_VISIBILITY, VR::CS, "N"),
+    ]))
+}
+
+/// A UID under the 2.25 root made of a random 128-bit integer (PS3.5 B.2).
+fn new_uid() -> String {
+    let now = SystemTime::now();
+    let high = RandomState::new().hash_one(now);
+    let low = RandomState::new().hash_one((now, std::process::id()));
+    format!("2.25.{}", (u128::from(high) << 64) | u128::from(low))
+}
+
+/// DA and TM values of a time in seconds since 1970-01-01 UTC.
+fn date_time(seconds: u64) -> (String, String) {
+    let days = (seconds / 86_400) as i64;
+    let second_of_day = seconds % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    (
+        format!("{year:04}{month:02}{day:02}"),
+        format!(
+            "{:02}{:02}{:02}",
+            second_of_day / 3600,
+            second_of_day / 60 % 60,
+            second_of_day % 60
+        ),
+    )
+}
+
+/// Proleptic Gregorian date of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,460 @@
+use super::attributes::{attribute_f64, attribute_f64s, attribute_text, sequence_items};
+use super::segmentation::lab_to_rgb;
+use super::{ViewTransform, Window};
+use dicom::object::InMemDicomObject;
+use iced::{Color, Point, Rectangle, Size, Vector};
+use std::fmt;
+
+/// Grayscale Softcopy Presentation State Storage SOP Class UID.
+pub const GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.1";
+/// Colour of graphic layers without a recommended display value.
+const DEFAULT_LAYER_COLOR: Color = Color::from_rgb(0.3, 1.0, 0.5);
+/// Straight segments approximating circles and ellipses.
+const CURVE_SEGMENTS: usize = 64;
+
+/// An image, or some of its frames, that an item applies to.
+#[derive(Debug, Clone, PartialEq)]
+struct ImageReference {
+    sop_instance_uid: String,
+    /// 1-based frame numbers; empty for every frame.
+    frames: Vec<u32>,
+}
+
+impl ImageReference {
+    fn read_all(object: &InMemDicomObject) -> Vec<Self> {
+        sequence_items(object, "ReferencedImageSequence")
+            .iter()
+            .filter_map(|item| {
+                Some(Self {
+                    sop_instance_uid: attribute_text(item, "ReferencedSOPInstanceUID")?,
+                    frames: attribute_f64s(item, "ReferencedFrameNumber")
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|number| number as u32)
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn matches(&self, sop_instance_uid: &str, frame_index: u32) -> bool {
+        self.sop_instance_uid == sop_instance_uid
+            && (self.frames.is_empty() || self.frames.contains(&(frame_index + 1)))
+    }
+}
+
+/// An item that applies to the listed images, or to every image the
+/// presentation state references when the list is empty.
+#[derive(Debug, Clone, PartialEq)]
+struct Scoped<T> {
+    images: Vec<ImageReference>,
+    value: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnnotationUnits {
+    /// Image pixel coordinates with the origin at the top left corner of
+    /// the top left pixel.
+    Pixel,
+    /// Fractions of the displayed area, after the spatial transformation.
+    Display,
+}
+
+impl AnnotationUnits {
+    fn read(object: &InMemDicomObject, name: &str) -> Self {
+        match attribute_text(object, name).as_deref() {
+            Some("DISPLAY") => AnnotationUnits::Display,
+            _ => AnnotationUnits::Pixel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Graphic {
+    /// POINT, POLYLINE and INTERPOLATED graphics.
+    Polyline {
+        points: Vec<Point>,
+        closed: bool,
+    },
+    Circle {
+        center: Point,
+        edge: Point,
+    },
+    Ellipse {
+        major: [Point; 2],
+        minor: [Point; 2],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GraphicObject {
+    units: AnnotationUnits,
+    graphic: Graphic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TextObject {
+    units: AnnotationUnits,
+    position: Point,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GraphicAnnotation {
+    layer: String,
+    graphics: Vec<GraphicObject>,
+    texts: Vec<TextObject>,
+}
+
+/// A graphic annotation resolved to image pixel coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationGraphic {
+    pub points: Vec<Point>,
+    pub closed: bool,
+    pub color: Color,
+}
+
+/// A text annotation resolved to image pixel coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationText {
+    pub position: Point,
+    pub text: String,
+    pub color: Color,
+}
+
+/// Grayscale Softcopy Presentation State (PS3.3 A.33.1): how its referenced
+/// images should be displayed and annotated.
+///
+/// Presentation Size Mode is not honoured; every displayed area is scaled to
+/// fit the viewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationState {
+    pub sop_instance_uid: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// Presentation Creation Date and Time, as `YYYYMMDDHHMMSS`.
+    pub created: String,
+    pub transform: ViewTransform,
+    references: Vec<ImageReference>,
+    displayed_areas: Vec<Scoped<Rectangle>>,
+    windows: Vec<Scoped<Window>>,
+    annotations: Vec<Scoped<GraphicAnnotation>>,
+    layer_colors: Vec<(String, Color)>,
+}
+
+impl PresentationState {
+    /// Reads a Grayscale Softcopy Presentation State; other objects give `None`.
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        if attribute_text(object, "SOPClassUID").as_deref()
+            != Some(GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE)
+        {
+            return None;
+        }
+
+        let references = sequence_items(object, "ReferencedSeriesSequence")
+            .iter()
+            .flat_map(ImageReference::read_all)
+            .collect();
+
+        let displayed_areas = sequence_items(object, "DisplayedAreaSelectionSequence")
+            .iter()
+            .filter_map(|item| {
+                let top_left = attribute_f64s(item, "DisplayedAreaTopLeftHandCorner")?;
+                let bottom_right = attribute_f64s(item, "DisplayedAreaBottomRightHandCorner")?;
+                if top_left.len() < 2 || bottom_right.len() < 2 {
+                    return None;
+                }
+                // Corners are 1-based (column, row) pixel indices, inclusive.
+                let width = bottom_right[0] - top_left[0] + 1.0;
+                let height = bottom_right[1] - top_left[1] + 1.0;
+                (width > 0.0 && height > 0.0).then(|| Scoped {
+                    images: ImageReference::read_all(item),
+                    value: Rectangle::new(
+                        Point::new(top_left[0] as f32 - 1.0, top_left[1] as f32 - 1.0),
+                        Size::new(width as f32, height as f32),
+                    ),
+                })
+            })
+            .collect();
+
+        let windows = sequence_items(object, "SoftcopyVOILUTSequence")
+            .iter()
+            .filter_map(|item| {
+                let window = Window::from_object(item);
+                if window.is_none() && item.element_by_name("VOILUTSequence").is_ok() {
+                    log::info!("Presentation state VOI LUT tables are not supported");
+                }
+                Some(Scoped {
+                    images: ImageReference::read_all(item),
+                    value: window?,
+                })
+            })
+            .collect();
+
+        let annotations = sequence_items(object, "GraphicAnnotationSequence")
+            .iter()
+            .map(|item| Scoped {
+                images: ImageReference::read_all(item),
+                value: GraphicAnnotation {
+                    layer: attribute_text(item, "GraphicLayer").unwrap_or_default(),
+                    graphics: sequence_items(item, "GraphicObjectSequence")
+                        .iter()
+                        .filter_map(read_graphic)
+                        .collect(),
+                    texts: sequence_items(item, "TextObjectSequence")
+                        .iter()
+                        .filter_map(read_text)
+                        .collect(),
+                },
+            })
+            .collect();
+
+        let layer_colors = sequence_items(object, "GraphicLayerSequence")
+            .iter()
+            .filter_map(|item| Some((attribute_text(item, "GraphicLayer")?, layer_color(item)?)))
+            .collect();
+
+        let rotation = attribute_f64(object, "ImageRotation").unwrap_or(0.0);
+        let transform = ViewTransform {
+            quarter_turns: ((rotation / 90.0).round() as i64).rem_euclid(4) as u8,
+            flipped: attribute_text(object, "ImageHorizontalFlip").as_deref() == Some("Y"),
+        };
+
+        let created = format!(
+            "{}{}",
+            attribute_text(object, "PresentationCreationDate").unwrap_or_default(),
+            attribute_text(object, "PresentationCreationTime").unwrap_or_default()
+        );
+        Some(Self {
+            sop_instance_uid: attribute_text(object, "SOPInstanceUID").unwrap_or_default(),
+            label: attribute_text(object, "ContentLabel").unwrap_or_else(|| String::from("GSPS")),
+            description: attribute_text(object, "ContentDescription"),
+            created,
+            transform,
+            references,
+            displayed_areas,
+            windows,
+            annotations,
+            layer_colors,
+        })
+    }
+
+    /// Whether the presentation state references the image.
+    pub fn applies_to(&self, sop_instance_uid: &str) -> bool {
+        self.references
+            .iter()
+            .any(|reference| reference.sop_instance_uid == sop_instance_uid)
+    }
+
+    /// Region of the image to show, in image pixel coordinates.
+    pub fn displayed_area(&self, sop_instance_uid: &str, frame_index: u32) -> Option<Rectangle> {
+        self.scoped(&self.displayed_areas, sop_instance_uid, frame_index)
+            .next()
+            .copied()
+    }
+
+    pub fn window(&self, sop_instance_uid: &str, frame_index: u32) -> Option<Window> {
+        self.scoped(&self.windows, sop_instance_uid, frame_index)
+            .next()
+            .copied()
+    }
+
+    /// Graphic and text annotations on a frame of an image of `image` size,
+    /// in image pixel coordinates.
+    pub fn annotations(
+        &self,
+        sop_instance_uid: &str,
+        frame_index: u32,
+        image: Size,
+    ) -> (Vec<PresentationGraphic>, Vec<PresentationText>) {
+        let area = self
+            .displayed_area(sop_instance_uid, frame_index)
+            .unwrap_or(Rectangle::new(Point::ORIGIN, image));
+        let resolve = |units: AnnotationUnits, point: Point| match units {
+            AnnotationUnits::Pixel => point,
+            AnnotationUnits::Display => self.display_to_image(point, area, image),
+        };
+
+        let mut graphics = Vec::new();
+        let mut texts = Vec::new();
+        for annotation in self.scoped(&self.annotations, sop_instance_uid, frame_index) {
+            let color = self.layer_color(&annotation.layer);
+            for object in &annotation.graphics {
+                let (points, closed) = match &object.graphic {
+                    Graphic::Polyline { points, closed } => (points.clone(), *closed),
+                    Graphic::Circle { center, edge } => {
+                        let radius = *edge - *center;
+                        let across = Vector::new(-radius.y, radius.x);
+                        (ellipse(*center, radius, across), true)
+                    }
+                    Graphic::Ellipse { major, minor } => {
+                        let center = Point::new(
+                            (major[0].x + major[1].x) / 2.0,
+                            (major[0].y + major[1].y) / 2.0,
+                        );
+                        let semi_major = (major[1] - major[0]) * 0.5;
+                        let semi_minor = (minor[1] - minor[0]) * 0.5;
+                        (ellipse(center, semi_major, semi_minor), true)
+                    }
+                };
+                graphics.push(PresentationGraphic {
+                    points: points
+                        .into_iter()
+                        .map(|point| resolve(object.units, point))
+                        .collect(),
+                    closed,
+                    color,
+                });
+            }
+            for text in &annotation.texts {
+                texts.push(PresentationText {
+                    position: resolve(text.units, text.position),
+                    text: text.text.clone(),
+                    color,
+                });
+            }
+        }
+        (graphics, texts)
+    }
+
+    fn scoped<'a, T>(
+        &'a self,
+        items: &'a [Scoped<T>],
+        sop_instance_uid: &'a str,
+        frame_index: u32,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        items
+            .iter()
+            .filter(move |item| {
+                if item.images.is_empty() {
+                    self.applies_to(sop_instance_uid)
+                } else {
+                    item.images
+                        .iter()
+                        .any(|image| image.matches(sop_instance_uid, frame_index))
+                }
+            })
+            .map(|item| &item.value)
+    }
+
+    fn layer_color(&self, layer: &str) -> Color {
+        self.layer_colors
+            .iter()
+            .find(|(name, _)| name == layer)
+            .map_or(DEFAULT_LAYER_COLOR, |(_, color)| *color)
+    }
+
+    /// Maps a point given as fractions of the displayed area, as shown after
+    /// the spatial transformation, back into image pixel coordinates.
+    fn display_to_image(&self, point: Point, area: Rectangle, image: Size) -> Point {
+        let a = self.transform.to_display(area.position(), image);
+        let b = self
+            .transform
+            .to_display(Point::new(area.x + area.width, area.y + area.height), image);
+        let display = Point::new(
+            a.x.min(b.x) + point.x * (a.x - b.x).abs(),
+            a.y.min(b.y) + point.y * (a.y - b.y).abs(),
+        );
+        self.transform.to_image(display, image)
+    }
+}
+
+impl fmt::Display for PresentationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{} – {description}", self.label),
+            None => f.write_str(&self.label),
+        }
+    }
+}
+
+fn read_graphic(item: &InMemDicomObject) -> Option<GraphicObject> {
+    let data = attribute_f64s(item, "GraphicData")?;
+    let points = data
+        .chunks_exact(2)
+        .map(|pair| Point::new(pair[0] as f32, pair[1] as f32))
+        .collect::<Vec<_>>();
+    let graphic = match attribute_text(item, "GraphicType").as_deref() {
+        Some("CIRCLE") if points.len() >= 2 => Graphic::Circle {
+            center: points[0],
+            edge: points[1],
+        },
+        Some("ELLIPSE") if points.len() >= 4 => Graphic::Ellipse {
+            major: [points[0], points[1]],
+            minor: [points[2], points[3]],
+        },
+        Some("POINT" | "POLYLINE" | "INTERPOLATED") if !points.is_empty() => Graphic::Polyline {
+            points,
+            closed: false,
+        },
+        other => {
+            log::info!("Skipping presentation state graphic of type {other:?}");
+            return None;
+        }
+    };
+    Some(GraphicObject {
+        units: AnnotationUnits::read(item, "GraphicAnnotationUnits"),
+        graphic,
+    })
+}
+
+fn read_text(item: &InMemDicomObject) -> Option<TextObject> {
+    let text = attribute_text(item, "UnformattedTextValue")?;
+    let point = |name: &str| {
+        attribute_f64s(item, name)
+            .filter(|values| values.len() >= 2)
+            .map(|values| Point::new(values[0] as f32, values[1] as f32))
+    };
+    let (units, position) = match point("BoundingBoxTopLeftHandCorner") {
+        Some(position) => (
+            AnnotationUnits::read(item, "BoundingBoxAnnotationUnits"),
+            position,
+        ),
+        None => (
+            AnnotationUnits::read(item, "AnchorPointAnnotationUnits"),
+            point("AnchorPoint")?,
+        ),
+    };
+    Some(TextObject {
+        units,
+        position,
+        text,
+    })
+}
+
+fn layer_color(item: &InMemDicomObject) -> Option<Color> {
+    if let Some(lab) =
+        attribute_f64s(item, "GraphicLayerRecommendedDisplayCIELabValue").filter(|v| v.len() >= 3)
+    {
+        let [r, g, b] = lab_to_rgb([lab[0], lab[1], lab[2]]);
+        return Some(Color::from_rgb8(r, g, b));
+    }
+    if let Some(rgb) =
+        attribute_f64s(item, "GraphicLayerRecommendedDisplayRGBValue").filter(|v| v.len() >= 3)
+    {
+        let max = if rgb.iter().any(|value| *value > 255.0) {
+            65535.0
+        } else {
+            255.0
+        };
+        return Some(Color::from_rgb(
+            (rgb[0] / max) as f32,
+            (rgb[1] / max) as f32,
+            (rgb[2] / max) as f32,
+        ));
+    }
+    attribute_f64(item, "GraphicLayerRecommendedDisplayGrayscaleValue").map(|gray| {
+        let gray = (gray / 65535.0) as f32;
+        Color::from_rgb(gray, gray, gray)
+    })
+}
+
+/// Closed polygon around `center` with the given semi-axis vectors.
+fn ellipse(center: Point, semi_major: Vector, semi_minor: Vector) -> Vec<Point> {
+    (0..CURVE_SEGMENTS)
+        .map(|index| {
+            let angle = index as f32 / CURVE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + semi_major * angle.cos() + semi_minor * angle.sin()
+        })
+        .collect()
+}
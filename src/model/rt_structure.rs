@@ -0,0 +1,213 @@
+use super::attributes::{attribute_f64, attribute_f64s, attribute_text, sequence_items};
+use super::segmentation::{FrameReference, ImageRef};
+use dicom::object::InMemDicomObject;
+use iced::{Color, Point};
+
+/// RT Structure Set Storage SOP Class UID.
+pub const RT_STRUCTURE_SET_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.3";
+/// Largest distance (mm) of contour points from an image plane for a contour
+/// without image references to be drawn on it.
+const PLANE_TOLERANCE_MM: f64 = 0.5;
+/// Colour of ROIs without an ROI Display Color.
+const DEFAULT_ROI_COLOR: [u8; 3] = [255, 255, 0];
+
+/// One contour of the Contour Sequence (3006,0040), in patient coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    /// CLOSED_PLANAR contours are drawn closed; POINT, OPEN_PLANAR and
+    /// OPEN_NONPLANAR contours are not.
+    pub closed: bool,
+    pub points: Vec<[f64; 3]>,
+    /// Images and frames from the Contour Image Sequence.
+    pub images: Vec<FrameReference>,
+}
+
+/// A structure (ROI) of the set with its contours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoiStructure {
+    pub number: u32,
+    pub name: String,
+    /// RT ROI Interpreted Type, e.g. ORGAN or PTV.
+    pub kind: Option<String>,
+    pub color: [u8; 3],
+    pub frame_of_reference: Option<String>,
+    pub contours: Vec<Contour>,
+}
+
+impl RoiStructure {
+    pub fn to_color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::from_rgb8(r, g, b)
+    }
+}
+
+/// A contour mapped into the pixel space of the displayed frame
+/// (x = column, y = row).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourOutline {
+    pub roi: u32,
+    pub closed: bool,
+    pub points: Vec<Point>,
+}
+
+/// RT Structure Set IOD (PS3.3 A.19) ROIs and their contours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureSet {
+    pub sop_instance_uid: String,
+    pub label: Option<String>,
+    pub rois: Vec<RoiStructure>,
+}
+
+impl StructureSet {
+    /// Reads an RT Structure Set; other objects give `None`.
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        let is_structure_set = attribute_text(object, "SOPClassUID").as_deref()
+            == Some(RT_STRUCTURE_SET_STORAGE)
+            || attribute_text(object, "Modality").as_deref() == Some("RTSTRUCT");
+        if !is_structure_set {
+            return None;
+        }
+
+        let observations = sequence_items(object, "RTROIObservationsSequence");
+        let roi_contours = sequence_items(object, "ROIContourSequence");
+        let rois = sequence_items(object, "StructureSetROISequence")
+            .iter()
+            .filter_map(|item| {
+                let number = attribute_f64(item, "ROINumber")? as u32;
+                let referencing = |other: &&InMemDicomObject| {
+                    attribute_f64(other, "ReferencedROINumber") == Some(number as f64)
+                };
+                let contour_item = roi_contours.iter().find(referencing);
+                let color = contour_item
+                    .and_then(|item| attribute_f64s(item, "ROIDisplayColor"))
+                    .filter(|color| color.len() >= 3)
+                    .map(|color| {
+                        [color[0], color[1], color[2]]
+                            .map(|channel| channel.clamp(0.0, 255.0) as u8)
+                    })
+                    .unwrap_or(DEFAULT_ROI_COLOR);
+                let contours = contour_item
+                    .map(|item| sequence_items(item, "ContourSequence"))
+                    .unwrap_or(&[])
+                    .iter()
+                    .filter_map(Contour::from_item)
+                    .collect();
+                Some(RoiStructure {
+                    number,
+                    name: attribute_text(item, "ROIName")
+                        .unwrap_or_else(|| format!("ROI {number}")),
+                    kind: observations
+                        .iter()
+                        .find(referencing)
+                        .and_then(|observation| {
+                            attribute_text(observation, "RTROIInterpretedType")
+                        }),
+                    color,
+                    frame_of_reference: attribute_text(item, "ReferencedFrameOfReferenceUID"),
+                    contours,
+                })
+            })
+            .collect();
+
+        Some(Self {
+            sop_instance_uid: attribute_text(object, "SOPInstanceUID").unwrap_or_default(),
+            label: attribute_text(object, "StructureSetLabel"),
+            rois,
+        })
+    }
+
+    pub fn roi(&self, number: u32) -> Option<&RoiStructure> {
+        self.rois.iter().find(|roi| roi.number == number)
+    }
+
+    /// Whether any contour references the image or shares its frame of reference.
+    pub fn applies_to(&self, image: &ImageRef) -> bool {
+        self.rois.iter().any(|roi| {
+            shares_frame_of_reference(roi, image)
+                || roi
+                    .contours
+                    .iter()
+                    .any(|contour| contour.references_instance(image))
+        })
+    }
+
+    /// Contours lying on the displayed frame, in its pixel coordinates.
+    /// Contours that reference images are drawn on those images and frames
+    /// only; others are drawn on planes of the same frame of reference they
+    /// lie in. A reference to a whole multi-frame image also requires the
+    /// contour to lie in the frame's plane.
+    pub fn outlines_on(&self, image: &ImageRef) -> Vec<ContourOutline> {
+        let Some(plane) = image.plane else {
+            return Vec::new();
+        };
+        let mut outlines = Vec::new();
+        for roi in &self.rois {
+            for contour in &roi.contours {
+                let projected = contour
+                    .points
+                    .iter()
+                    .map(|&point| plane.project(point))
+                    .collect::<Vec<_>>();
+                let in_plane = || {
+                    projected
+                        .iter()
+                        .all(|(_, _, distance)| distance.abs() <= PLANE_TOLERANCE_MM)
+                };
+                let on_plane = if contour.images.is_empty() {
+                    shares_frame_of_reference(roi, image) && in_plane()
+                } else {
+                    contour.images.iter().any(|reference| {
+                        reference.matches(image)
+                            && (!image.multi_frame || !reference.frames.is_empty() || in_plane())
+                    })
+                };
+                if !on_plane {
+                    continue;
+                }
+                outlines.push(ContourOutline {
+                    roi: roi.number,
+                    closed: contour.closed,
+                    // Pixel centres sit half a pixel into the image canvas.
+                    points: projected
+                        .iter()
+                        .map(|&(row, column, _)| Point::new(column as f32 + 0.5, row as f32 + 0.5))
+                        .collect(),
+                });
+            }
+        }
+        outlines
+    }
+}
+
+impl Contour {
+    fn from_item(item: &InMemDicomObject) -> Option<Self> {
+        let data = attribute_f64s(item, "ContourData")?;
+        let points = data
+            .chunks_exact(3)
+            .map(|point| [point[0], point[1], point[2]])
+            .collect::<Vec<_>>();
+        if points.is_empty() {
+            return None;
+        }
+        Some(Self {
+            closed: attribute_text(item, "ContourGeometricType").as_deref()
+                == Some("CLOSED_PLANAR"),
+            points,
+            images: sequence_items(item, "ContourImageSequence")
+                .iter()
+                .filter_map(FrameReference::from_item)
+                .collect(),
+        })
+    }
+
+    fn references_instance(&self, image: &ImageRef) -> bool {
+        self.images
+            .iter()
+            .any(|reference| reference.sop_instance_uid == image.sop_instance_uid)
+    }
+}
+
+fn shares_frame_of_reference(roi: &RoiStructure, image: &ImageRef) -> bool {
+    roi.frame_of_reference.is_some()
+        && roi.frame_of_reference.as_deref() == image.frame_of_reference.as_deref()
+}
@@ -0,0 +1,396 @@
+use super::attributes::{attribute_f64, attribute_f64s, attribute_text, sequence_items};
+use super::{DicomView, ImagePlane};
+use dicom::core::value::Value;
+use dicom::object::InMemDicomObject;
+use dicom::pixeldata::DecodedPixelData;
+use iced::Color;
+
+/// Segmentation Storage SOP Class UID.
+pub const SEGMENTATION_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.4";
+/// Opacity of a fully covered pixel when drawn over the referenced image.
+pub const SEGMENT_OPACITY: f32 = 0.45;
+/// Largest distance (mm) between Image Position (Patient) values of a
+/// segmentation frame and an image frame that still counts as the same plane.
+const POSITION_TOLERANCE_MM: f64 = 0.05;
+/// Colours for segments without a Recommended Display CIELab Value.
+const FALLBACK_COLORS: [[u8; 3]; 6] = [
+    [255, 80, 64],
+    [64, 200, 255],
+    [120, 230, 80],
+    [255, 210, 40],
+    [230, 90, 230],
+    [255, 150, 60],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentationType {
+    /// One bit per pixel, packed least significant bit first across frames.
+    Binary,
+    /// One byte per pixel, a probability or occupancy up to `max`.
+    Fractional { max: u8 },
+}
+
+/// One item of the Segment Sequence (0062,0002).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub number: u16,
+    pub label: String,
+    pub color: [u8; 3],
+}
+
+impl Segment {
+    pub fn to_color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::from_rgb8(r, g, b)
+    }
+}
+
+/// Which segment a segmentation frame holds and where it belongs.
+#[derive(Debug, Clone, PartialEq)]
+struct SegmentFrame {
+    segment: u16,
+    /// Source images from the Derivation Image Sequence.
+    references: Vec<FrameReference>,
+    position: Option<[f64; 3]>,
+}
+
+/// An image reference with optional Referenced Frame Numbers, as found in
+/// Source Image and Contour Image Sequence items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameReference {
+    pub sop_instance_uid: String,
+    /// 1-based frame numbers; empty when the whole instance is referenced.
+    pub frames: Vec<u32>,
+}
+
+impl FrameReference {
+    pub fn from_item(item: &InMemDicomObject) -> Option<Self> {
+        Some(Self {
+            sop_instance_uid: attribute_text(item, "ReferencedSOPInstanceUID")?,
+            frames: attribute_f64s(item, "ReferencedFrameNumber")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|number| number as u32)
+                .collect(),
+        })
+    }
+
+    /// Whether the reference covers the displayed frame.
+    pub fn matches(&self, image: &ImageRef) -> bool {
+        self.sop_instance_uid == image.sop_instance_uid
+            && (self.frames.is_empty() || self.frames.contains(&(image.frame_index + 1)))
+    }
+}
+
+/// Coverage of one segment on one frame of a referenced image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentMask {
+    pub segment: u16,
+    pub rows: u32,
+    pub columns: u32,
+    /// Coverage of each pixel from 0 (outside) to 255 (fully inside).
+    pub coverage: Vec<u8>,
+}
+
+/// Segmentation IOD (PS3.3 A.51) frames and segments, kept so masks can be
+/// extracted for whichever referenced frame is displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation {
+    pub sop_instance_uid: String,
+    pub frame_of_reference: Option<String>,
+    pub kind: SegmentationType,
+    pub rows: u32,
+    pub columns: u32,
+    pub segments: Vec<Segment>,
+    frames: Vec<SegmentFrame>,
+    /// Bit-packed or 8-bit Pixel Data of every frame.
+    data: Vec<u8>,
+}
+
+impl Segmentation {
+    /// Reads a Segmentation object; other objects give `Ok(None)`.
+    /// `pixels` is used for fractional segmentations with encapsulated Pixel Data.
+    pub fn from_object(
+        object: &InMemDicomObject,
+        pixels: Option<&DecodedPixelData<'_>>,
+    ) -> Result<Option<Self>, String> {
+        let is_segmentation = attribute_text(object, "SOPClassUID").as_deref()
+            == Some(SEGMENTATION_STORAGE)
+            || attribute_text(object, "Modality").as_deref() == Some("SEG");
+        if !is_segmentation {
+            return Ok(None);
+        }
+
+        let rows = attribute_f64(object, "Rows").ok_or("Segmentation has no Rows")? as u32;
+        let columns = attribute_f64(object, "Columns").ok_or("Segmentation has no Columns")? as u32;
+        let frame_count = attribute_f64(object, "NumberOfFrames")
+            .unwrap_or(1.0)
+            .max(1.0) as usize;
+        let kind = match attribute_text(object, "SegmentationType").as_deref() {
+            Some("BINARY") | None => SegmentationType::Binary,
+            Some("FRACTIONAL") => SegmentationType::Fractional {
+                max: attribute_f64(object, "MaximumFractionalValue")
+                    .unwrap_or(255.0)
+                    .clamp(1.0, 255.0) as u8,
+            },
+            Some(other) => return Err(format!("Unknown Segmentation Type {other}")),
+        };
+
+        let segments = sequence_items(object, "SegmentSequence")
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let number = attribute_f64(item, "SegmentNumber")? as u16;
+                let color = attribute_f64s(item, "RecommendedDisplayCIELabValue")
+                    .filter(|lab| lab.len() >= 3)
+                    .map(|lab| lab_to_rgb([lab[0], lab[1], lab[2]]))
+                    .unwrap_or(FALLBACK_COLORS[index % FALLBACK_COLORS.len()]);
+                Some(Segment {
+                    number,
+                    label: attribute_text(item, "SegmentLabel")
+                        .unwrap_or_else(|| format!("Segment {number}")),
+                    color,
+                })
+            })
+            .collect::<Vec<_>>();
+        if segments.is_empty() {
+            return Err(String::from("Segmentation has no Segment Sequence"));
+        }
+
+        let shared = sequence_items(object, "SharedFunctionalGroupsSequence").first();
+        let per_frame = sequence_items(object, "PerFrameFunctionalGroupsSequence");
+        let frames = (0..frame_count)
+            .map(|index| {
+                let groups = [per_frame.get(index), shared];
+                let macro_item = |sequence: &str| {
+                    groups
+                        .into_iter()
+                        .flatten()
+                        .find_map(|group| sequence_items(group, sequence).first())
+                };
+                let segment = macro_item("SegmentIdentificationSequence")
+                    .and_then(|item| attribute_f64(item, "ReferencedSegmentNumber"))
+                    .map(|number| number as u16)
+                    .ok_or_else(|| {
+                        format!("Frame {} has no Referenced Segment Number", index + 1)
+                    })?;
+                let references = groups
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|group| sequence_items(group, "DerivationImageSequence"))
+                    .flat_map(|derivation| sequence_items(derivation, "SourceImageSequence"))
+                    .filter_map(FrameReference::from_item)
+                    .collect();
+                let position = macro_item("PlanePositionSequence")
+                    .and_then(|item| attribute_f64s(item, "ImagePositionPatient"))
+                    .filter(|position| position.len() >= 3)
+                    .map(|position| [position[0], position[1], position[2]]);
+                Ok(SegmentFrame {
+                    segment,
+                    references,
+                    position,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let data = pixel_bytes(object, pixels, kind)?;
+        let pixel_count = rows as usize * columns as usize * frame_count;
+        let needed = match kind {
+            SegmentationType::Binary => pixel_count.div_ceil(8),
+            SegmentationType::Fractional { .. } => pixel_count,
+        };
+        if data.len() < needed {
+            return Err(format!(
+                "Segmentation Pixel Data has {} bytes, expected {needed}",
+                data.len()
+            ));
+        }
+
+        Ok(Some(Self {
+            sop_instance_uid: attribute_text(object, "SOPInstanceUID").unwrap_or_default(),
+            frame_of_reference: attribute_text(object, "FrameOfReferenceUID"),
+            kind,
+            rows,
+            columns,
+            segments,
+            frames,
+            data,
+        }))
+    }
+
+    pub fn segment(&self, number: u16) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.number == number)
+    }
+
+    /// Whether any segmentation frame applies to the image, either by
+    /// reference or by sharing its frame of reference.
+    pub fn applies_to(&self, image: &ImageRef) -> bool {
+        self.frames.iter().any(|frame| {
+            frame
+                .references
+                .iter()
+                .any(|reference| reference.sop_instance_uid == image.sop_instance_uid)
+        }) || self.shares_frame_of_reference(image)
+    }
+
+    /// Segment numbers with a frame on the displayed image frame.
+    pub fn segments_on(&self, image: &ImageRef) -> Vec<u16> {
+        let mut numbers: Vec<u16> = self
+            .matching_frames(image)
+            .map(|(_, frame)| frame.segment)
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        numbers
+    }
+
+    /// Masks of every segmentation frame that lies on the displayed image
+    /// frame. Frames on a different pixel grid are skipped.
+    pub fn masks_for(&self, image: &ImageRef, rows: u32, columns: u32) -> Vec<SegmentMask> {
+        if (rows, columns) != (self.rows, self.columns) {
+            return Vec::new();
+        }
+        self.matching_frames(image)
+            .map(|(index, frame)| SegmentMask {
+                segment: frame.segment,
+                rows: self.rows,
+                columns: self.columns,
+                coverage: self.coverage(index),
+            })
+            .collect()
+    }
+
+    fn matching_frames<'a>(
+        &'a self,
+        image: &'a ImageRef,
+    ) -> impl Iterator<Item = (usize, &'a SegmentFrame)> + 'a {
+        let by_position = self.shares_frame_of_reference(image);
+        self.frames.iter().enumerate().filter(move |(_, frame)| {
+            if !frame.references.is_empty() {
+                return frame
+                    .references
+                    .iter()
+                    .any(|reference| reference.matches(image));
+            }
+            by_position
+                && match (frame.position, image.plane) {
+                    (Some(position), Some(plane)) => {
+                        let distance = (0..3)
+                            .map(|axis| (position[axis] - plane.position[axis]).powi(2))
+                            .sum::<f64>()
+                            .sqrt();
+                        distance <= POSITION_TOLERANCE_MM
+                    }
+                    _ => false,
+                }
+        })
+    }
+
+    fn shares_frame_of_reference(&self, image: &ImageRef) -> bool {
+        self.frame_of_reference.is_some()
+            && self.frame_of_reference.as_deref() == image.frame_of_reference.as_deref()
+    }
+
+    fn coverage(&self, frame: usize) -> Vec<u8> {
+        let pixel_count = self.rows as usize * self.columns as usize;
+        let start = frame * pixel_count;
+        match self.kind {
+            SegmentationType::Binary => (start..start + pixel_count)
+                .map(|bit| {
+                    if self.data[bit / 8] >> (bit % 8) & 1 == 1 {
+                        255
+                    } else {
+                        0
+                    }
+                })
+                .collect(),
+            SegmentationType::Fractional { max } => self.data[start..start + pixel_count]
+                .iter()
+                .map(|&value| (value.min(max) as u32 * 255 / max as u32) as u8)
+                .collect(),
+        }
+    }
+}
+
+/// The displayed image frame segmentations are matched against.
+#[derive(Debug, Clone)]
+pub struct ImageRef {
+    pub sop_instance_uid: String,
+    pub frame_of_reference: Option<String>,
+    /// 0-based frame of the image.
+    pub frame_index: u32,
+    /// Whether the image has more than one frame.
+    pub multi_frame: bool,
+    pub plane: Option<ImagePlane>,
+}
+
+impl ImageRef {
+    pub fn displayed(view: &DicomView) -> Self {
+        Self {
+            sop_instance_uid: attribute_text(&view.source, "SOPInstanceUID").unwrap_or_default(),
+            frame_of_reference: attribute_text(&view.source, "FrameOfReferenceUID"),
+            frame_index: view.frame_index,
+            multi_frame: attribute_f64(&view.source, "NumberOfFrames").is_some_and(|n| n > 1.0),
+            plane: view.frame.as_ref().and_then(|frame| frame.plane),
+        }
+    }
+}
+
+/// Copies the Pixel Data bytes. Native data is used as stored; encapsulated
+/// fractional data is taken from the decoded pixels.
+fn pixel_bytes(
+    object: &InMemDicomObject,
+    pixels: Option<&DecodedPixelData<'_>>,
+    kind: SegmentationType,
+) -> Result<Vec<u8>, String> {
+    let element = object
+        .element_by_name("PixelData")
+        .map_err(|_| String::from("Segmentation has no Pixel Data"))?;
+    match element.value() {
+        Value::Primitive(value) => Ok(value.to_bytes().into_owned()),
+        _ => match (kind, pixels) {
+            (SegmentationType::Fractional { .. }, Some(pixels)) => Ok(pixels.data().to_vec()),
+            _ => Err(String::from(
+                "Compressed binary segmentations are not supported",
+            )),
+        },
+    }
+}
+
+/// Converts a DICOM-scaled CIELab value (PS3.3 C.10.7.1.1) to sRGB with a
+/// D65 white point.
+pub(super) fn lab_to_rgb([l, a, b]: [f64; 3]) -> [u8; 3] {
+    let l = l * 100.0 / 65535.0;
+    let a = a * 255.0 / 65535.0 - 128.0;
+    let b = b * 255.0 / 65535.0 - 128.0;
+
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |t: f64| {
+        if t.powi(3) > 0.008856 {
+            t.powi(3)
+        } else {
+            (t - 16.0 / 116.0) / 7.787
+        }
+    };
+    let x = 0.95047 * inverse(fx);
+    let y = inverse(fy);
+    let z = 1.08883 * inverse(fz);
+
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|channel| {
+        let encoded = if channel <= 0.0031308 {
+            12.92 * channel
+        } else {
+            1.055 * channel.powf(1.0 / 2.4) - 0.055
+        };
+        (encoded.clamp(0.0, 1.0) * 255.0).round() as u8
+    })
+}
@@ -0,0 +1,351 @@
+use super::attributes::{attribute_f64s, attribute_text, sequence_items};
+use dicom::object::InMemDicomObject;
+use std::fmt;
+
+/// A coded entry: Code Value, Coding Scheme Designator and Code Meaning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodedConcept {
+    pub value: String,
+    pub scheme: String,
+    pub meaning: String,
+}
+
+impl CodedConcept {
+    /// Reads the first item of a code sequence such as Concept Name Code Sequence.
+    pub fn from_sequence(object: &InMemDicomObject, name: &str) -> Option<Self> {
+        Self::from_item(sequence_items(object, name).first()?)
+    }
+
+    pub fn from_item(item: &InMemDicomObject) -> Option<Self> {
+        let value = attribute_text(item, "CodeValue")
+            .or_else(|| attribute_text(item, "LongCodeValue"))
+            .or_else(|| attribute_text(item, "URNCodeValue"))?;
+        Some(Self {
+            meaning: attribute_text(item, "CodeMeaning").unwrap_or_else(|| value.clone()),
+            scheme: attribute_text(item, "CodingSchemeDesignator").unwrap_or_default(),
+            value,
+        })
+    }
+
+    pub fn is(&self, scheme: &str, value: &str) -> bool {
+        self.scheme == scheme && self.value == value
+    }
+}
+
+impl fmt::Display for CodedConcept {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} {})", self.meaning, self.scheme, self.value)
+    }
+}
+
+/// An instance, or some of its frames, that an IMAGE, COMPOSITE or WAVEFORM
+/// item references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrReference {
+    pub sop_class_uid: Option<String>,
+    pub sop_instance_uid: String,
+    /// 1-based frame numbers; empty for the whole instance.
+    pub frames: Vec<u32>,
+}
+
+impl SrReference {
+    fn from_item(item: &InMemDicomObject) -> Option<Self> {
+        let reference = sequence_items(item, "ReferencedSOPSequence").first()?;
+        Some(Self {
+            sop_class_uid: attribute_text(reference, "ReferencedSOPClassUID"),
+            sop_instance_uid: attribute_text(reference, "ReferencedSOPInstanceUID")?,
+            frames: attribute_f64s(reference, "ReferencedFrameNumber")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|number| number as u32)
+                .collect(),
+        })
+    }
+}
+
+/// Value of a content item, by Value Type (PS3.3 C.17.3.2.1).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SrValue {
+    Container {
+        /// CONTINUOUS rather than SEPARATE Continuity Of Content.
+        continuous: bool,
+    },
+    Text(String),
+    Num {
+        /// Numeric Value as written; `None` when only a qualifier is given.
+        value: Option<String>,
+        unit: Option<CodedConcept>,
+        /// Why the value is missing, e.g. "Not a number".
+        qualifier: Option<CodedConcept>,
+    },
+    Code(Option<CodedConcept>),
+    DateTime(String),
+    Date(String),
+    Time(String),
+    UidRef(String),
+    PersonName(String),
+    Image(Option<SrReference>),
+    Composite(Option<SrReference>),
+    Waveform(Option<SrReference>),
+    /// Image coordinates as (column, row) pairs.
+    Scoord {
+        graphic_type: String,
+        points: Vec<[f64; 2]>,
+    },
+    Scoord3d {
+        graphic_type: String,
+        points: Vec<[f64; 3]>,
+        frame_of_reference: Option<String>,
+    },
+    Tcoord {
+        range_type: String,
+    },
+    /// Content items of other value types, or by-reference relationships.
+    Other(String),
+}
+
+/// A content item of the document tree and its children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrNode {
+    /// Relationship Type to the parent; `None` for the root container.
+    pub relationship: Option<String>,
+    pub value_type: String,
+    pub concept: Option<CodedConcept>,
+    pub value: SrValue,
+    pub children: Vec<SrNode>,
+}
+
+impl SrNode {
+    fn from_item(item: &InMemDicomObject) -> Self {
+        let relationship = attribute_text(item, "RelationshipType");
+        let children = sequence_items(item, "ContentSequence")
+            .iter()
+            .map(SrNode::from_item)
+            .collect();
+        let Some(value_type) = attribute_text(item, "ValueType") else {
+            // By-reference relationships point at another item of the tree.
+            let target = attribute_f64s(item, "ReferencedContentItemIdentifier")
+                .unwrap_or_default()
+                .iter()
+                .map(|number| (*number as u32).to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+            return Self {
+                relationship,
+                value_type: String::from("REFERENCE"),
+                concept: None,
+                value: SrValue::Other(format!("Content item {target}")),
+                children,
+            };
+        };
+
+        let text = |name: &str| attribute_text(item, name).unwrap_or_default();
+        let value = match value_type.as_str() {
+            "CONTAINER" => SrValue::Container {
+                continuous: attribute_text(item, "ContinuityOfContent").as_deref()
+                    == Some("CONTINUOUS"),
+            },
+            "TEXT" => SrValue::Text(text("TextValue")),
+            "NUM" => {
+                let measured = sequence_items(item, "MeasuredValueSequence").first();
+                SrValue::Num {
+                    value: measured.and_then(|measured| attribute_text(measured, "NumericValue")),
+                    unit: measured.and_then(|measured| {
+                        CodedConcept::from_sequence(measured, "MeasurementUnitsCodeSequence")
+                    }),
+                    qualifier: CodedConcept::from_sequence(
+                        item,
+                        "NumericValueQualifierCodeSequence",
+                    ),
+                }
+            }
+            "CODE" => SrValue::Code(CodedConcept::from_sequence(item, "ConceptCodeSequence")),
+            "DATETIME" => SrValue::DateTime(text("DateTime")),
+            "DATE" => SrValue::Date(text("Date")),
+            "TIME" => SrValue::Time(text("Time")),
+            "UIDREF" => SrValue::UidRef(text("UID")),
+            "PNAME" => SrValue::PersonName(text("PersonName")),
+            "IMAGE" => SrValue::Image(SrReference::from_item(item)),
+            "COMPOSITE" => SrValue::Composite(SrReference::from_item(item)),
+            "WAVEFORM" => SrValue::Waveform(SrReference::from_item(item)),
+            "SCOORD" => SrValue::Scoord {
+                graphic_type: text("GraphicType"),
+                points: attribute_f64s(item, "GraphicData")
+                    .unwrap_or_default()
+                    .chunks_exact(2)
+                    .map(|point| [point[0], point[1]])
+                    .collect(),
+            },
+            "SCOORD3D" => SrValue::Scoord3d {
+                graphic_type: text("GraphicType"),
+                points: attribute_f64s(item, "GraphicData")
+                    .unwrap_or_default()
+                    .chunks_exact(3)
+                    .map(|point| [point[0], point[1], point[2]])
+                    .collect(),
+                frame_of_reference: attribute_text(item, "ReferencedFrameOfReferenceUID"),
+            },
+            "TCOORD" => SrValue::Tcoord {
+                range_type: text("TemporalRangeType"),
+            },
+            other => SrValue::Other(format!("Unsupported value type {other}")),
+        };
+        Self {
+            relationship,
+            value_type,
+            concept: CodedConcept::from_sequence(item, "ConceptNameCodeSequence"),
+            value,
+            children,
+        }
+    }
+
+    /// Whether the item's concept name has the given code.
+    pub fn is(&self, scheme: &str, value: &str) -> bool {
+        self.concept
+            .as_ref()
+            .is_some_and(|concept| concept.is(scheme, value))
+    }
+
+    /// Direct children whose concept name has the given code.
+    pub fn children_named<'a>(
+        &'a self,
+        scheme: &'a str,
+        value: &'a str,
+    ) -> impl Iterator<Item = &'a SrNode> + 'a {
+        self.children
+            .iter()
+            .filter(move |child| child.is(scheme, value))
+    }
+
+    /// First direct child whose concept name has the given code.
+    pub fn child_named(&self, scheme: &str, value: &str) -> Option<&SrNode> {
+        self.children.iter().find(|child| child.is(scheme, value))
+    }
+
+    /// Numeric value and unit of a NUM item.
+    pub fn numeric(&self) -> Option<(f64, Option<&CodedConcept>)> {
+        match &self.value {
+            SrValue::Num {
+                value: Some(value),
+                unit,
+                ..
+            } => Some((value.parse().ok()?, unit.as_ref())),
+            _ => None,
+        }
+    }
+
+    /// The image the item points at: its own reference for IMAGE items, or
+    /// the image a SCOORD is SELECTED FROM.
+    pub fn image_reference(&self) -> Option<&SrReference> {
+        match &self.value {
+            SrValue::Image(reference) => reference.as_ref(),
+            SrValue::Scoord { .. } => self
+                .children
+                .iter()
+                .filter(|child| child.relationship.as_deref() == Some("SELECTED FROM"))
+                .find_map(|child| child.image_reference()),
+            _ => None,
+        }
+    }
+
+    /// The item's value as shown in the document tree.
+    pub fn value_text(&self) -> String {
+        match &self.value {
+            SrValue::Container { continuous } => {
+                if *continuous {
+                    String::from("continuous")
+                } else {
+                    String::new()
+                }
+            }
+            SrValue::Text(text)
+            | SrValue::DateTime(text)
+            | SrValue::Date(text)
+            | SrValue::Time(text)
+            | SrValue::UidRef(text)
+            | SrValue::PersonName(text)
+            | SrValue::Other(text) => text.clone(),
+            SrValue::Num {
+                value,
+                unit,
+                qualifier,
+            } => match (value, unit, qualifier) {
+                // UCUM "1" is the unit of dimensionless values.
+                (Some(value), Some(unit), _) if unit.value != "1" => {
+                    format!("{value} {}", unit.value)
+                }
+                (Some(value), _, _) => value.clone(),
+                (None, _, Some(qualifier)) => qualifier.meaning.clone(),
+                (None, _, None) => String::from("(no value)"),
+            },
+            SrValue::Code(code) => code.as_ref().map(ToString::to_string).unwrap_or_default(),
+            SrValue::Image(reference)
+            | SrValue::Composite(reference)
+            | SrValue::Waveform(reference) => match reference {
+                Some(reference) if reference.frames.is_empty() => {
+                    reference.sop_instance_uid.clone()
+                }
+                Some(reference) => format!(
+                    "{} (frames {})",
+                    reference.sop_instance_uid,
+                    reference
+                        .frames
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None => String::from("(no reference)"),
+            },
+            SrValue::Scoord {
+                graphic_type,
+                points,
+            } => format!("{graphic_type}, {} point(s)", points.len()),
+            SrValue::Scoord3d {
+                graphic_type,
+                points,
+                ..
+            } => format!("{graphic_type}, {} point(s) in patient space", points.len()),
+            SrValue::Tcoord { range_type } => range_type.clone(),
+        }
+    }
+}
+
+/// A Structured Report document (PS3.3 C.17.3) as a tree of content items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredReport {
+    pub sop_instance_uid: String,
+    pub completion: Option<String>,
+    pub verification: Option<String>,
+    /// The root CONTAINER, whose concept name is the document title.
+    pub root: SrNode,
+}
+
+impl StructuredReport {
+    /// Reads an SR document; other objects give `None`.
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        let is_report = attribute_text(object, "Modality").as_deref() == Some("SR")
+            || attribute_text(object, "ValueType").as_deref() == Some("CONTAINER");
+        if !is_report || object.element_by_name("ContentSequence").is_err() {
+            return None;
+        }
+        Some(Self {
+            sop_instance_uid: attribute_text(object, "SOPInstanceUID").unwrap_or_default(),
+            completion: attribute_text(object, "CompletionFlag"),
+            verification: attribute_text(object, "VerificationFlag"),
+            root: SrNode::from_item(object),
+        })
+    }
+
+    pub fn title(&self) -> Option<&CodedConcept> {
+        self.root.concept.as_ref()
+    }
+
+    /// Number of content items below the root.
+    pub fn item_count(&self) -> usize {
+        fn count(node: &SrNode) -> usize {
+            node.children.iter().map(|child| 1 + count(child)).sum()
+        }
+        count(&self.root)
+    }
+}
@@ -0,0 +1,135 @@
+use super::attributes::{attribute_f64, attribute_text, sequence_items};
+use dicom::object::InMemDicomObject;
+
+/// Unit of body-weight SUV values.
+pub const SUV_UNIT: &str = "g/ml";
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Factor taking PET activity concentration (Bq/ml) to body-weight SUV
+/// (g/ml): patient weight over the injected dose decayed to the time the
+/// image is decay corrected to (PS3.3 C.8.9.1 and C.8.9.4).
+///
+/// Returns `None` for non-PET objects and for PET images that lack the
+/// weight, dose, half-life or timing needed.
+pub fn body_weight_factor(object: &InMemDicomObject) -> Option<f64> {
+    if attribute_text(object, "Modality").as_deref() != Some("PT") {
+        return None;
+    }
+    match body_weight_factor_of_pet(object) {
+        Ok(factor) => Some(factor),
+        Err(err) => {
+            log::info!("SUV unavailable: {err}");
+            None
+        }
+    }
+}
+
+fn body_weight_factor_of_pet(object: &InMemDicomObject) -> Result<f64, String> {
+    match attribute_text(object, "Units").as_deref() {
+        Some("BQML") => {}
+        Some("GML") => return Ok(1.0),
+        Some(other) => return Err(format!("Units are {other}, not BQML")),
+        None => return Err(String::from("Units are missing")),
+    }
+    let weight = attribute_f64(object, "PatientWeight")
+        .filter(|weight| *weight > 0.0)
+        .ok_or("Patient Weight is missing")?;
+    let info = sequence_items(object, "RadiopharmaceuticalInformationSequence")
+        .first()
+        .ok_or("Radiopharmaceutical Information Sequence is missing")?;
+    let dose = attribute_f64(info, "RadionuclideTotalDose")
+        .filter(|dose| *dose > 0.0)
+        .ok_or("Radionuclide Total Dose is missing")?;
+    let half_life = attribute_f64(info, "RadionuclideHalfLife")
+        .filter(|half_life| *half_life > 0.0)
+        .ok_or("Radionuclide Half Life is missing")?;
+
+    // Images are decay corrected to the series start (START), not corrected
+    // and so measured at acquisition (NONE), or corrected to injection (ADMIN).
+    let reference = match attribute_text(object, "DecayCorrection").as_deref() {
+        Some("ADMIN") => None,
+        Some("NONE") => Some(("AcquisitionDate", "AcquisitionTime")),
+        _ => Some(("SeriesDate", "SeriesTime")),
+    };
+    let elapsed = match reference {
+        None => 0.0,
+        Some((date, time)) => {
+            let scan = timestamp(
+                attribute_text(object, date).as_deref(),
+                &attribute_text(object, time).ok_or(format!("{time} is missing"))?,
+            )
+            .ok_or(format!("{time} is not a valid time"))?;
+            let start_date_time = attribute_text(info, "RadiopharmaceuticalStartDateTime");
+            // A DT may hold a date alone (or with an offset), leaving the time
+            // to Radiopharmaceutical Start Time.
+            let start = start_date_time
+                .as_deref()
+                .and_then(|date_time| date_time.get(..8).zip(date_time.get(8..)))
+                .filter(|(_, time)| !time.is_empty() && !time.starts_with(['+', '-']));
+            let injection = match start {
+                Some((date, time)) => timestamp(Some(date), time),
+                None => timestamp(
+                    attribute_text(object, date).as_deref(),
+                    &attribute_text(info, "RadiopharmaceuticalStartTime")
+                        .ok_or("Radiopharmaceutical Start Time is missing")?,
+                ),
+            }
+            .ok_or("Radiopharmaceutical start time is not a valid time")?;
+            let mut elapsed = scan - injection;
+            // Times without dates that cross midnight.
+            if elapsed < 0.0 && elapsed > -SECONDS_PER_DAY {
+                elapsed += SECONDS_PER_DAY;
+            }
+            elapsed
+        }
+    };
+
+    let decayed_dose = dose * 0.5f64.powf(elapsed / half_life);
+    Ok(weight * 1000.0 / decayed_dose)
+}
+
+/// Seconds since 1970-01-01 of a DA date and TM time, or since midnight when
+/// the date is missing.
+fn timestamp(date: Option<&str>, time: &str) -> Option<f64> {
+    let days = match date {
+        Some(date) if date.len() >= 8 => {
+            let year: i64 = date.get(..4)?.parse().ok()?;
+            let month: i64 = date.get(4..6)?.parse().ok()?;
+            let day: i64 = date.get(6..8)?.parse().ok()?;
+            days_from_civil(year, month, day) as f64
+        }
+        _ => 0.0,
+    };
+    Some(days * SECONDS_PER_DAY + seconds_of_day(time)?)
+}
+
+/// Parses a TM value, `HHMMSS.FFFFFF` or the ACR-NEMA `HH:MM:SS` form, where
+/// every component after the hour is optional.
+fn seconds_of_day(time: &str) -> Option<f64> {
+    let time = time.trim().replace(':', "");
+    // A DT value may carry a UTC offset after the time.
+    let time = time.split(['+', '-']).next()?;
+    let (whole, fraction) = time.split_once('.').unwrap_or((time, ""));
+    if whole.len() < 2 || whole.len() % 2 != 0 || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |index: usize| -> f64 {
+        whole
+            .get(index * 2..index * 2 + 2)
+            .and_then(|digits| digits.parse().ok())
+            .unwrap_or(0.0)
+    };
+    let fraction: f64 = format!("0.{fraction}").parse().unwrap_or(0.0);
+    Some(field(0) * 3600.0 + field(1) * 60.0 + field(2) + fraction)
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
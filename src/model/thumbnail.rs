@@ -0,0 +1,189 @@
+use super::attributes::{attribute_f64, attribute_text};
+use super::{ColorMap, DicomEntry};
+use crate::image_pipeline::FrameImagePipeline;
+use dicom::object::DefaultDicomObject;
+use dicom::pixeldata::DecodedPixelData;
+use iced::widget::image::Handle;
+use image::{imageops, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Longest side of a thumbnail in pixels.
+pub const THUMBNAIL_SIZE: u32 = 96;
+const CACHE_DIR_ENV_VAR: &str = "DICOMANCER_CACHE";
+/// Bumped whenever rendering changes, so thumbnails cached by older versions
+/// are rendered again rather than reused.
+const CACHE_VERSION: u32 = 2;
+
+/// Thumbnail of one instance, rendered in the background.
+#[derive(Debug, Clone)]
+pub enum Thumbnail {
+    Pending,
+    Ready(Handle),
+    Failed,
+}
+
+/// What is needed to render the thumbnail of one instance off the UI thread.
+#[derive(Debug, Clone)]
+pub struct ThumbnailJob {
+    pub key: String,
+    pub object: Arc<DefaultDicomObject>,
+    pub pixels: Arc<DecodedPixelData<'static>>,
+}
+
+impl ThumbnailJob {
+    pub fn for_entry(entry: &DicomEntry) -> Option<Self> {
+        Some(Self {
+            key: key(entry),
+            object: entry.view.source.clone(),
+            pixels: entry.view.pixels.clone()?,
+        })
+    }
+
+    /// Reads the thumbnail from the disk cache, or renders the middle frame
+    /// through its default window and caches it.
+    pub fn load_or_render(&self) -> Result<Handle, String> {
+        let frame = self.pixels.number_of_frames() / 2;
+        let cached = cache_path(&self.key, frame);
+        if let Some(path) = &cached {
+            if let Ok(image) = image::open(path) {
+                return Ok(to_handle(image.into_rgba8()));
+            }
+        }
+
+        let image = self.render(frame)?;
+        if let Some(path) = &cached {
+            let saved = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(|err| err.to_string())
+                .and_then(|_| image.save(path).map_err(|err| err.to_string()));
+            if let Err(err) = saved {
+                log::warn!("{}: failed to cache thumbnail ({err})", path.display());
+            }
+        }
+        Ok(to_handle(image))
+    }
+
+    fn render(&self, frame: u32) -> Result<RgbaImage, String> {
+        let rendered = FrameImagePipeline::render_frame(
+            &self.object,
+            &self.pixels,
+            frame,
+            None,
+            ColorMap::default(),
+        )?;
+        let Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } = rendered.preview.handle
+        else {
+            return Err(String::from("Rendered frame is not an RGBA image"));
+        };
+        let image = RgbaImage::from_raw(width, height, pixels.to_vec())
+            .ok_or_else(|| String::from("Rendered frame has an unexpected size"))?;
+
+        let scale = THUMBNAIL_SIZE as f32 / width.max(height).max(1) as f32;
+        if scale >= 1.0 {
+            return Ok(image);
+        }
+        let width = ((width as f32 * scale).round() as u32).max(1);
+        let height = ((height as f32 * scale).round() as u32).max(1);
+        Ok(imageops::thumbnail(&image, width, height))
+    }
+}
+
+/// Identifies the thumbnail of an entry: its SOP Instance UID, or its path
+/// when the UID is missing.
+pub fn key(entry: &DicomEntry) -> String {
+    if entry.sop_instance_uid == "Unknown" {
+        entry.view.file_path.display().to_string()
+    } else {
+        entry.sop_instance_uid.clone()
+    }
+}
+
+/// Loaded instances of one series, ordered by Instance Number.
+#[derive(Debug, Clone)]
+pub struct SeriesGroup {
+    pub series_instance_uid: String,
+    pub label: String,
+    /// Indices into the loaded entries.
+    pub instances: Vec<usize>,
+}
+
+impl SeriesGroup {
+    /// The middle instance, standing in for the series.
+    pub fn representative(&self) -> usize {
+        self.instances[self.instances.len() / 2]
+    }
+}
+
+/// Groups loaded entries by series, in the order series were first loaded.
+pub fn group_series(entries: &[DicomEntry]) -> Vec<SeriesGroup> {
+    let mut groups: Vec<SeriesGroup> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|group| group.series_instance_uid == entry.series_instance_uid)
+        {
+            Some(group) => group.instances.push(index),
+            None => {
+                let object = &entry.view.source;
+                let number = attribute_text(object, "SeriesNumber");
+                let description = attribute_text(object, "SeriesDescription")
+                    .or_else(|| attribute_text(object, "Modality"));
+                let label = match (number, description) {
+                    (Some(number), Some(description)) => format!("Se {number} · {description}"),
+                    (Some(number), None) => format!("Se {number}"),
+                    (None, Some(description)) => description,
+                    (None, None) => String::from("Series"),
+                };
+                groups.push(SeriesGroup {
+                    series_instance_uid: entry.series_instance_uid.clone(),
+                    label,
+                    instances: vec![index],
+                });
+            }
+        }
+    }
+    for group in &mut groups {
+        group.instances.sort_by(|&a, &b| {
+            let number = |index: usize| {
+                attribute_f64(&entries[index].view.source, "InstanceNumber")
+                    .unwrap_or(f64::INFINITY)
+            };
+            number(a).total_cmp(&number(b))
+        });
+    }
+    groups
+}
+
+fn to_handle(image: RgbaImage) -> Handle {
+    let (width, height) = image.dimensions();
+    Handle::from_rgba(width, height, image.into_raw())
+}
+
+/// Cache file for a thumbnail, when the key is a UID that is safe to use as a
+/// file name.
+fn cache_path(key: &str, frame: u32) -> Option<PathBuf> {
+    if key.is_empty() || !key.chars().all(|ch| ch.is_ascii_digit() || ch == '.') {
+        return None;
+    }
+    Some(cache_dir()?.join(format!(
+        "v{CACHE_VERSION}-{key}-{frame}-{THUMBNAIL_SIZE}.png"
+    )))
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CACHE_DIR_ENV_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(base.join("dicomancer").join("thumbnails"))
+}
@@ -0,0 +1,281 @@
+use super::attributes::{attribute_f64, sequence_items};
+use super::measurement::{Calibration, CalibrationSource};
+use dicom::object::InMemDicomObject;
+use iced::Point;
+use std::fmt;
+
+/// Physical Units X/Y Direction of an ultrasound region (PS3.3 C.8.5.5.1.15).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicalUnit {
+    None,
+    Percent,
+    Decibel,
+    Centimetre,
+    Second,
+    Hertz,
+    DecibelPerSecond,
+    CentimetrePerSecond,
+    SquareCentimetre,
+    SquareCentimetrePerSecond,
+    CubicCentimetre,
+    CubicCentimetrePerSecond,
+    Degree,
+}
+
+impl PhysicalUnit {
+    fn from_code(code: u16) -> Self {
+        match code {
+            0x0001 => Self::Percent,
+            0x0002 => Self::Decibel,
+            0x0003 => Self::Centimetre,
+            0x0004 => Self::Second,
+            0x0005 => Self::Hertz,
+            0x0006 => Self::DecibelPerSecond,
+            0x0007 => Self::CentimetrePerSecond,
+            0x0008 => Self::SquareCentimetre,
+            0x0009 => Self::SquareCentimetrePerSecond,
+            0x000A => Self::CubicCentimetre,
+            0x000B => Self::CubicCentimetrePerSecond,
+            0x000C => Self::Degree,
+            _ => Self::None,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Percent => "%",
+            Self::Decibel => "dB",
+            Self::Centimetre => "cm",
+            Self::Second => "s",
+            Self::Hertz => "Hz",
+            Self::DecibelPerSecond => "dB/s",
+            Self::CentimetrePerSecond => "cm/s",
+            Self::SquareCentimetre => "cm²",
+            Self::SquareCentimetrePerSecond => "cm²/s",
+            Self::CubicCentimetre => "cm³",
+            Self::CubicCentimetrePerSecond => "cm³/s",
+            Self::Degree => "°",
+        }
+    }
+}
+
+/// A calibrated area of an ultrasound image from the Sequence of Ultrasound
+/// Regions (PS3.3 C.8.5.5), such as a 2D sector or a spectral Doppler strip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UltrasoundRegion {
+    /// Top-left and bottom-right pixels, inclusive, as (column, row).
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+    pub spatial_format: u16,
+    pub data_type: u16,
+    /// Units along the columns, then along the rows.
+    pub units: [PhysicalUnit; 2],
+    /// Physical change per pixel along the columns, then along the rows.
+    pub delta: [f64; 2],
+    /// Pixel with a known physical value, relative to `min`.
+    pub reference_pixel: [Option<f64>; 2],
+    pub reference_value: [f64; 2],
+}
+
+/// Physical values at a pixel of an ultrasound region.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionReading {
+    pub region: String,
+    /// Values along the columns and rows, for axes with a reference pixel
+    /// and a unit.
+    pub values: [Option<(f64, PhysicalUnit)>; 2],
+}
+
+/// Physical change along both axes of a line drawn in a region whose axes
+/// have different units, such as time and velocity in a Doppler strip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionDelta {
+    pub dx: f64,
+    pub x_unit: PhysicalUnit,
+    pub dy: f64,
+    pub y_unit: PhysicalUnit,
+}
+
+impl RegionDelta {
+    /// Change along the rows per change along the columns, e.g. acceleration.
+    pub fn slope(&self) -> Option<f64> {
+        (self.dx != 0.0).then(|| self.dy / self.dx)
+    }
+}
+
+impl fmt::Display for RegionDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Δ {:.3} {} over Δ {:.3} {}",
+            self.dy,
+            self.y_unit.symbol(),
+            self.dx,
+            self.x_unit.symbol()
+        )?;
+        if let Some(slope) = self.slope() {
+            write!(
+                f,
+                " · slope {slope:.2} {}/{}",
+                self.y_unit.symbol(),
+                self.x_unit.symbol()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl UltrasoundRegion {
+    /// Reads every item of the Sequence of Ultrasound Regions.
+    pub fn read_all(object: &InMemDicomObject) -> Vec<Self> {
+        sequence_items(object, "SequenceOfUltrasoundRegions")
+            .iter()
+            .filter_map(|item| {
+                let number = |name: &str| attribute_f64(item, name);
+                let code = |name: &str| number(name).map_or(0, |value| value as u16);
+                Some(Self {
+                    min: (
+                        number("RegionLocationMinX0")? as u32,
+                        number("RegionLocationMinY0")? as u32,
+                    ),
+                    max: (
+                        number("RegionLocationMaxX1")? as u32,
+                        number("RegionLocationMaxY1")? as u32,
+                    ),
+                    spatial_format: code("RegionSpatialFormat"),
+                    data_type: code("RegionDataType"),
+                    units: [
+                        PhysicalUnit::from_code(code("PhysicalUnitsXDirection")),
+                        PhysicalUnit::from_code(code("PhysicalUnitsYDirection")),
+                    ],
+                    delta: [
+                        number("PhysicalDeltaX").unwrap_or(0.0),
+                        number("PhysicalDeltaY").unwrap_or(0.0),
+                    ],
+                    reference_pixel: [number("ReferencePixelX0"), number("ReferencePixelY0")],
+                    reference_value: [
+                        number("ReferencePixelPhysicalValueX").unwrap_or(0.0),
+                        number("ReferencePixelPhysicalValueY").unwrap_or(0.0),
+                    ],
+                })
+            })
+            .collect()
+    }
+
+    /// Region Spatial Format and Region Data Type, e.g. "2D tissue" or
+    /// "PW spectral Doppler".
+    pub fn name(&self) -> String {
+        let data = match self.data_type {
+            0x0001 => "tissue",
+            0x0002 => "color flow",
+            0x0003 => "PW spectral Doppler",
+            0x0004 => "CW spectral Doppler",
+            0x0005 => "Doppler mean trace",
+            0x0006 => "Doppler mode trace",
+            0x0007 => "Doppler max trace",
+            0x0008 => "volume trace",
+            0x000A => "ECG trace",
+            0x000B => "pulse trace",
+            0x000C => "phonocardiogram trace",
+            0x000D => "gray bar",
+            0x000E => "color bar",
+            0x000F => "integrated backscatter",
+            0x0010 => "area trace",
+            0x0012 => "physiological input",
+            _ => "region",
+        };
+        match self.spatial_format {
+            0x0001 => format!("2D {data}"),
+            0x0002 => format!("M-mode {data}"),
+            _ => data.to_string(),
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let (x, y) = (point.x as f64, point.y as f64);
+        x >= self.min.0 as f64
+            && x < self.max.0 as f64 + 1.0
+            && y >= self.min.1 as f64
+            && y < self.max.1 as f64 + 1.0
+    }
+
+    fn area(&self) -> u64 {
+        (self.max.0.saturating_sub(self.min.0) as u64 + 1)
+            * (self.max.1.saturating_sub(self.min.1) as u64 + 1)
+    }
+
+    /// Corners of the region's outline in image pixel coordinates.
+    pub fn outline(&self) -> Vec<Point> {
+        let (left, top) = (self.min.0 as f32, self.min.1 as f32);
+        let (right, bottom) = (self.max.0 as f32 + 1.0, self.max.1 as f32 + 1.0);
+        vec![
+            Point::new(left, top),
+            Point::new(right, top),
+            Point::new(right, bottom),
+            Point::new(left, bottom),
+        ]
+    }
+
+    /// Millimetre calibration of regions measured in centimetres along both axes.
+    pub fn calibration(&self) -> Option<Calibration> {
+        let spacing = [self.delta[1].abs() * 10.0, self.delta[0].abs() * 10.0];
+        (self.units == [PhysicalUnit::Centimetre; 2] && spacing.iter().all(|value| *value > 0.0))
+            .then_some(Calibration {
+                spacing,
+                source: CalibrationSource::UltrasoundRegion,
+            })
+    }
+
+    /// Physical change between two image points along both axes, for regions
+    /// whose axes are calibrated in different units.
+    pub fn delta(&self, from: Point, to: Point) -> Option<RegionDelta> {
+        let [x_unit, y_unit] = self.units;
+        if x_unit == PhysicalUnit::None
+            || y_unit == PhysicalUnit::None
+            || self.calibration().is_some()
+            || self.delta.contains(&0.0)
+        {
+            return None;
+        }
+        Some(RegionDelta {
+            dx: ((to.x - from.x) as f64 * self.delta[0]).abs(),
+            x_unit,
+            dy: ((to.y - from.y) as f64 * self.delta[1]).abs(),
+            y_unit,
+        })
+    }
+
+    /// Physical values at a pixel, from the reference pixel of each axis.
+    pub fn reading(&self, column: u32, row: u32) -> RegionReading {
+        let pixel = [
+            column as f64 - self.min.0 as f64,
+            row as f64 - self.min.1 as f64,
+        ];
+        let values = [0, 1].map(|axis| {
+            let reference = self.reference_pixel[axis]?;
+            (self.units[axis] != PhysicalUnit::None).then(|| {
+                (
+                    self.reference_value[axis] + (pixel[axis] - reference) * self.delta[axis],
+                    self.units[axis],
+                )
+            })
+        });
+        RegionReading {
+            region: self.name(),
+            values,
+        }
+    }
+}
+
+/// The smallest region containing every point, as a colour flow box lies
+/// within its 2D region.
+pub fn region_containing<'a>(
+    regions: &'a [UltrasoundRegion],
+    points: &[Point],
+) -> Option<&'a UltrasoundRegion> {
+    regions
+        .iter()
+        .filter(|region| points.iter().all(|point| region.contains(*point)))
+        .min_by_key(|region| region.area())
+}
@@ -0,0 +1,58 @@
+use super::attributes::attribute_f64s;
+use super::FrameData;
+use dicom::object::InMemDicomObject;
+
+/// Linear VOI window in modality units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub center: f64,
+    pub width: f64,
+}
+
+impl Window {
+    pub fn from_object(object: &InMemDicomObject) -> Option<Self> {
+        let center = *attribute_f64s(object, "WindowCenter")?.first()?;
+        let width = *attribute_f64s(object, "WindowWidth")?.first()?;
+        (width >= 1.0).then_some(Self { center, width })
+    }
+
+    /// Window spanning every modality value of the frame.
+    pub fn full_range(frame: &FrameData) -> Option<Self> {
+        let (min, max) = frame.modality_range()?;
+        Some(Self::from_bounds(min, max))
+    }
+
+    pub fn from_bounds(lower: f64, upper: f64) -> Self {
+        let (lower, upper) = if lower <= upper {
+            (lower, upper)
+        } else {
+            (upper, lower)
+        };
+        Self {
+            center: (lower + upper) / 2.0,
+            width: (upper - lower).max(1.0),
+        }
+    }
+
+    pub fn lower(&self) -> f64 {
+        self.center - self.width / 2.0
+    }
+
+    pub fn upper(&self) -> f64 {
+        self.center + self.width / 2.0
+    }
+
+    /// Maps a modality value to display intensity as per the linear VOI LUT
+    /// function of PS3.3 C.11.2.1.2.1.
+    pub fn apply(&self, value: f64) -> u8 {
+        let center = self.center - 0.5;
+        let width = (self.width - 1.0).max(f64::EPSILON);
+        if value <= center - width / 2.0 {
+            0
+        } else if value > center + width / 2.0 {
+            255
+        } else {
+            (((value - center) / width + 0.5) * 255.0).round() as u8
+        }
+    }
+}
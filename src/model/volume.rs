@@ -0,0 +1,246 @@
+use super::geometry::{cross, dot, normalize, scale, sub};
+use super::{FrameData, Window};
+use crate::image_pipeline::FrameImagePipeline;
+use dicom::object::DefaultDicomObject;
+use dicom::pixeldata::DecodedPixelData;
+use std::sync::Arc;
+
+const MIN_SLICES: usize = 3;
+/// Largest difference between direction cosines of slices in one volume.
+const ORIENTATION_TOLERANCE: f64 = 1e-3;
+/// Largest relative difference between pixel spacings or slice gaps.
+const SPACING_TOLERANCE: f64 = 0.01;
+
+/// A series stacked into a 3D grid of modality values.
+#[derive(Debug)]
+pub struct Volume {
+    /// Number of columns, rows and slices.
+    pub dims: [usize; 3],
+    /// Spacing along columns, rows and slices, in millimetres.
+    pub spacing: [f64; 3],
+    /// Patient position of the centre of the first voxel.
+    pub origin: [f64; 3],
+    /// Patient directions of increasing column, row and slice index.
+    pub axes: [[f64; 3]; 3],
+    /// Modality values, slice by slice, each slice row by row.
+    pub values: Vec<f32>,
+    pub unit: Option<String>,
+    pub default_window: Window,
+}
+
+impl Volume {
+    /// Decodes the first frame of every instance and stacks them.
+    pub fn assemble(
+        instances: Vec<(Arc<DefaultDicomObject>, Arc<DecodedPixelData<'static>>)>,
+    ) -> Result<Self, String> {
+        let slices = instances
+            .iter()
+            .map(|(object, pixels)| {
+                if pixels.number_of_frames() > 1 {
+                    return Err("Multi-frame instances cannot be stacked into a volume".to_string());
+                }
+                FrameImagePipeline::frame_data(object, pixels, 0)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_slices(slices)
+    }
+
+    /// Stacks parallel, equally spaced slices ordered along their normal.
+    pub fn from_slices(slices: Vec<FrameData>) -> Result<Self, String> {
+        if slices.len() < MIN_SLICES {
+            return Err(format!(
+                "A volume needs at least {MIN_SLICES} slices, the series has {}",
+                slices.len()
+            ));
+        }
+        let mut planes = Vec::with_capacity(slices.len());
+        for (index, slice) in slices.iter().enumerate() {
+            if !slice.is_monochrome() {
+                return Err(format!("Slice {} is not monochrome", index + 1));
+            }
+            let plane = slice.plane.ok_or_else(|| {
+                format!(
+                    "Slice {} lacks Image Position/Orientation (Patient) or Pixel Spacing",
+                    index + 1
+                )
+            })?;
+            planes.push(plane);
+        }
+
+        let first = &slices[0];
+        let reference = planes[0];
+        for (index, (slice, plane)) in slices.iter().zip(&planes).enumerate().skip(1) {
+            if slice.rows != first.rows || slice.columns != first.columns {
+                return Err(format!(
+                    "Slice {} is {}x{}, expected {}x{}",
+                    index + 1,
+                    slice.columns,
+                    slice.rows,
+                    first.columns,
+                    first.rows
+                ));
+            }
+            let same_orientation = (0..3).all(|axis| {
+                (plane.row_direction[axis] - reference.row_direction[axis]).abs()
+                    <= ORIENTATION_TOLERANCE
+                    && (plane.column_direction[axis] - reference.column_direction[axis]).abs()
+                        <= ORIENTATION_TOLERANCE
+            });
+            if !same_orientation {
+                return Err(format!(
+                    "Slice {} has a different Image Orientation (Patient)",
+                    index + 1
+                ));
+            }
+            let same_spacing = (0..2).all(|axis| {
+                relative_difference(plane.pixel_spacing[axis], reference.pixel_spacing[axis])
+                    <= SPACING_TOLERANCE
+            });
+            if !same_spacing {
+                return Err(format!("Slice {} has a different Pixel Spacing", index + 1));
+            }
+        }
+
+        let row_direction = normalize(reference.row_direction);
+        let column_direction = normalize(reference.column_direction);
+        let normal = normalize(cross(row_direction, column_direction));
+        let mut order: Vec<usize> = (0..slices.len()).collect();
+        order.sort_by(|&a, &b| {
+            dot(planes[a].position, normal).total_cmp(&dot(planes[b].position, normal))
+        });
+
+        let gaps: Vec<f64> = order
+            .windows(2)
+            .map(|pair| {
+                dot(planes[pair[1]].position, normal) - dot(planes[pair[0]].position, normal)
+            })
+            .collect();
+        let slice_spacing = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        if gaps.iter().any(|gap| *gap <= f64::EPSILON) {
+            return Err("Two slices share the same position".to_string());
+        }
+        if gaps
+            .iter()
+            .any(|gap| relative_difference(*gap, slice_spacing) > SPACING_TOLERANCE)
+        {
+            let (min, max) = gaps.iter().fold((f64::MAX, f64::MIN), |(min, max), gap| {
+                (min.min(*gap), max.max(*gap))
+            });
+            return Err(format!(
+                "Slices are unevenly spaced ({min:.2}–{max:.2} mm apart)"
+            ));
+        }
+
+        let origin = planes[order[0]].position;
+        let [row_spacing, column_spacing] = reference.pixel_spacing;
+        let shear_tolerance = 0.5 * row_spacing.min(column_spacing);
+        for &index in &order {
+            let offset = sub(planes[index].position, origin);
+            let along = dot(offset, normal);
+            let shear = sub(offset, scale(normal, along));
+            if dot(shear, shear).sqrt() > shear_tolerance {
+                return Err(
+                    "Slice positions are not perpendicular to the slices (gantry tilt?)"
+                        .to_string(),
+                );
+            }
+        }
+
+        let values: Vec<f32> = order
+            .iter()
+            .flat_map(|&index| slices[index].modality_values().map(|value| value as f32))
+            .collect();
+        let default_window = slices[order[order.len() / 2]]
+            .default_window
+            .unwrap_or_else(|| {
+                let (min, max) = values
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                        (min.min(*value), max.max(*value))
+                    });
+                Window::from_bounds(min as f64, max as f64)
+            });
+
+        Ok(Self {
+            dims: [first.columns as usize, first.rows as usize, slices.len()],
+            spacing: [column_spacing, row_spacing, slice_spacing],
+            origin,
+            axes: [row_direction, column_direction, normal],
+            values,
+            unit: first.rescale.unit.clone(),
+            default_window,
+        })
+    }
+
+    /// Patient position of the centre of the volume.
+    pub fn center(&self) -> [f64; 3] {
+        self.voxel_to_patient(std::array::from_fn(|axis| {
+            (self.dims[axis] as f64 - 1.0) / 2.0
+        }))
+    }
+
+    /// Length of the diagonal of the volume, in millimetres.
+    pub fn diagonal(&self) -> f64 {
+        (0..3)
+            .map(|axis| (self.dims[axis] as f64 * self.spacing[axis]).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    pub fn min_spacing(&self) -> f64 {
+        self.spacing.iter().copied().fold(f64::MAX, f64::min)
+    }
+
+    pub fn voxel_to_patient(&self, voxel: [f64; 3]) -> [f64; 3] {
+        std::array::from_fn(|component| {
+            self.origin[component]
+                + (0..3)
+                    .map(|axis| self.axes[axis][component] * self.spacing[axis] * voxel[axis])
+                    .sum::<f64>()
+        })
+    }
+
+    pub fn patient_to_voxel(&self, point: [f64; 3]) -> [f64; 3] {
+        let offset = sub(point, self.origin);
+        std::array::from_fn(|axis| dot(offset, self.axes[axis]) / self.spacing[axis])
+    }
+
+    /// Trilinearly interpolated value at a patient position, or `None`
+    /// outside the volume.
+    pub fn sample(&self, point: [f64; 3]) -> Option<f32> {
+        let voxel = self.patient_to_voxel(point);
+        let mut base = [0usize; 3];
+        let mut fraction = [0f32; 3];
+        for axis in 0..3 {
+            let last = (self.dims[axis] - 1) as f64;
+            if !(0.0..=last).contains(&voxel[axis]) {
+                return None;
+            }
+            let floor = voxel[axis].floor().min((last - 1.0).max(0.0));
+            base[axis] = floor as usize;
+            fraction[axis] = (voxel[axis] - floor) as f32;
+        }
+
+        let [columns, rows, _] = self.dims;
+        let at = |i: usize, j: usize, k: usize| {
+            let i = (base[0] + i).min(self.dims[0] - 1);
+            let j = (base[1] + j).min(self.dims[1] - 1);
+            let k = (base[2] + k).min(self.dims[2] - 1);
+            self.values[(k * rows + j) * columns + i]
+        };
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let [fx, fy, fz] = fraction;
+        let plane = |k| {
+            lerp(
+                lerp(at(0, 0, k), at(1, 0, k), fx),
+                lerp(at(0, 1, k), at(1, 1, k), fx),
+                fy,
+            )
+        };
+        Some(lerp(plane(0), plane(1), fz))
+    }
+}
+
+fn relative_difference(a: f64, b: f64) -> f64 {
+    (a - b).abs() / a.abs().max(b.abs()).max(f64::EPSILON)
+}
@@ -0,0 +1,320 @@
+use super::attributes::{attribute_f64, attribute_text, sequence_items};
+use super::structured_report::CodedConcept;
+use dicom::object::InMemDicomObject;
+use std::fmt;
+
+/// One channel of a multiplex group, in physical units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformChannel {
+    /// Channel Label, or else the meaning of the channel source, e.g. "Lead II".
+    pub label: String,
+    /// UCUM code of the sensitivity units, e.g. "uV" or "mm[Hg]"; `None`
+    /// when the channel has no sensitivity and samples are raw values.
+    pub unit: Option<String>,
+    /// Sample values; padding samples are NaN.
+    pub samples: Vec<f64>,
+}
+
+impl WaveformChannel {
+    /// Factor that converts the samples to millivolts, for voltage channels.
+    pub fn millivolts_per_unit(&self) -> Option<f64> {
+        match self.unit.as_deref()? {
+            "uV" => Some(0.001),
+            "mV" => Some(1.0),
+            "V" => Some(1000.0),
+            _ => None,
+        }
+    }
+
+    /// Lowest and highest sample, ignoring padding.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.samples.iter().filter(|value| value.is_finite()).fold(
+            None,
+            |range, &value| match range {
+                None => Some((value, value)),
+                Some((low, high)) => Some((value.min(low), value.max(high))),
+            },
+        )
+    }
+}
+
+/// A multiplex group of the Waveform Sequence: channels sampled together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiplexGroup {
+    pub label: Option<String>,
+    /// ORIGINAL or DERIVED.
+    pub originality: Option<String>,
+    /// Samples per second.
+    pub sampling_frequency: f64,
+    pub sample_count: usize,
+    pub channels: Vec<WaveformChannel>,
+}
+
+impl MultiplexGroup {
+    fn from_item(item: &InMemDicomObject) -> Result<Self, String> {
+        let channel_count = attribute_f64(item, "NumberOfWaveformChannels")
+            .ok_or("Multiplex group without Number of Waveform Channels")?
+            as usize;
+        let sample_count = attribute_f64(item, "NumberOfWaveformSamples")
+            .ok_or("Multiplex group without Number of Waveform Samples")?
+            as usize;
+        let sampling_frequency = attribute_f64(item, "SamplingFrequency")
+            .filter(|frequency| *frequency > 0.0)
+            .ok_or("Multiplex group without Sampling Frequency")?;
+        let bits = attribute_f64(item, "WaveformBitsAllocated").unwrap_or(16.0) as usize;
+        let interpretation = attribute_text(item, "WaveformSampleInterpretation")
+            .unwrap_or_else(|| String::from("SS"));
+        let data = item
+            .element_by_name("WaveformData")
+            .map_err(|_| "Multiplex group without Waveform Data")?
+            .to_bytes()
+            .map_err(|err| format!("Unreadable Waveform Data ({err})"))?;
+
+        let raw = decode_samples(&data, bits, &interpretation)?;
+        if raw.len() < channel_count * sample_count {
+            return Err(format!(
+                "Waveform Data holds {} samples, expected {channel_count} channels × {sample_count}",
+                raw.len()
+            ));
+        }
+        let padding = item
+            .element_by_name("WaveformPaddingValue")
+            .ok()
+            .and_then(|element| element.to_bytes().ok())
+            .and_then(|bytes| decode_samples(&bytes, bits, &interpretation).ok())
+            .and_then(|values| values.first().copied());
+
+        let definitions = sequence_items(item, "ChannelDefinitionSequence");
+        let channels = (0..channel_count)
+            .map(|index| {
+                let definition = definitions.get(index);
+                let label = definition
+                    .and_then(|definition| attribute_text(definition, "ChannelLabel"))
+                    .or_else(|| {
+                        definition
+                            .and_then(|definition| {
+                                CodedConcept::from_sequence(definition, "ChannelSourceSequence")
+                            })
+                            .map(|source| source.meaning)
+                    })
+                    .unwrap_or_else(|| format!("Channel {}", index + 1));
+                let sensitivity = definition.and_then(|definition| {
+                    attribute_f64(definition, "ChannelSensitivity").map(|sensitivity| {
+                        sensitivity
+                            * attribute_f64(definition, "ChannelSensitivityCorrectionFactor")
+                                .unwrap_or(1.0)
+                    })
+                });
+                let baseline = definition
+                    .and_then(|definition| attribute_f64(definition, "ChannelBaseline"))
+                    .unwrap_or(0.0);
+                let unit = definition
+                    .filter(|_| sensitivity.is_some())
+                    .and_then(|definition| {
+                        CodedConcept::from_sequence(definition, "ChannelSensitivityUnitsSequence")
+                    })
+                    .map(|unit| unit.value);
+                // Samples are interleaved: every channel's first sample, then
+                // every channel's second, and so on.
+                let samples = raw
+                    .iter()
+                    .skip(index)
+                    .step_by(channel_count)
+                    .take(sample_count)
+                    .map(|&value| match (padding, sensitivity) {
+                        (Some(padding), _) if value == padding => f64::NAN,
+                        (_, Some(sensitivity)) => value * sensitivity + baseline,
+                        (_, None) => value,
+                    })
+                    .collect();
+                WaveformChannel {
+                    label,
+                    unit,
+                    samples,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            label: attribute_text(item, "MultiplexGroupLabel"),
+            originality: attribute_text(item, "WaveformOriginality"),
+            sampling_frequency,
+            sample_count,
+            channels,
+        })
+    }
+
+    /// Length of the recording in seconds.
+    pub fn duration(&self) -> f64 {
+        self.sample_count as f64 / self.sampling_frequency
+    }
+
+    /// Group label, or else its channel count and sampling frequency.
+    pub fn name(&self) -> String {
+        let rate = format!(
+            "{} channels at {} Hz",
+            self.channels.len(),
+            self.sampling_frequency
+        );
+        match &self.label {
+            Some(label) => format!("{label} ({rate})"),
+            None => rate,
+        }
+    }
+}
+
+/// The multiplex groups of a Waveform Sequence (PS3.3 C.10.9), such as ECG,
+/// hemodynamic or audio recordings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    pub groups: Vec<MultiplexGroup>,
+}
+
+impl Waveform {
+    /// Decodes the Waveform Sequence; objects without one give `Ok(None)`.
+    pub fn from_object(object: &InMemDicomObject) -> Result<Option<Self>, String> {
+        let items = sequence_items(object, "WaveformSequence");
+        if items.is_empty() {
+            return Ok(None);
+        }
+        let groups = items
+            .iter()
+            .map(MultiplexGroup::from_item)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Self { groups }))
+    }
+}
+
+/// Decodes Waveform Data by Waveform Sample Interpretation (PS3.3 C.10.9.1.5).
+fn decode_samples(data: &[u8], bits: usize, interpretation: &str) -> Result<Vec<f64>, String> {
+    let samples = match (bits, interpretation) {
+        (8, "SB") => data.iter().map(|&byte| byte as i8 as f64).collect(),
+        (8, "UB") => data.iter().map(|&byte| byte as f64).collect(),
+        (8, "MB") => data.iter().map(|&byte| mu_law(byte)).collect(),
+        (8, "AB") => data.iter().map(|&byte| a_law(byte)).collect(),
+        (16, "SS") => data
+            .chunks_exact(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f64)
+            .collect(),
+        (16, "US") => data
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f64)
+            .collect(),
+        (32, "SL") => data
+            .chunks_exact(4)
+            .map(|bytes| i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)
+            .collect(),
+        (32, "UL") => data
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)
+            .collect(),
+        _ => {
+            return Err(format!(
+                "Unsupported waveform samples: {bits} bits, interpretation {interpretation}"
+            ))
+        }
+    };
+    Ok(samples)
+}
+
+/// G.711 µ-law sample as a 16-bit linear value.
+fn mu_law(byte: u8) -> f64 {
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let magnitude = ((((byte & 0x0F) as i32) << 3) + 0x84) << exponent;
+    let value = magnitude - 0x84;
+    if byte & 0x80 != 0 {
+        -value as f64
+    } else {
+        value as f64
+    }
+}
+
+/// G.711 A-law sample as a 16-bit linear value.
+fn a_law(byte: u8) -> f64 {
+    let byte = byte ^ 0x55;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0F) as i32;
+    let magnitude = match exponent {
+        0 => (mantissa << 4) + 8,
+        _ => ((mantissa << 4) + 0x108) << (exponent - 1),
+    };
+    if byte & 0x80 != 0 {
+        magnitude as f64
+    } else {
+        -magnitude as f64
+    }
+}
+
+/// Horizontal scale of the ECG grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaperSpeed {
+    Five,
+    Ten,
+    TwelveAndHalf,
+    #[default]
+    TwentyFive,
+    Fifty,
+    Hundred,
+    TwoHundred,
+}
+
+impl PaperSpeed {
+    pub const ALL: [PaperSpeed; 7] = [
+        PaperSpeed::Five,
+        PaperSpeed::Ten,
+        PaperSpeed::TwelveAndHalf,
+        PaperSpeed::TwentyFive,
+        PaperSpeed::Fifty,
+        PaperSpeed::Hundred,
+        PaperSpeed::TwoHundred,
+    ];
+
+    /// Millimetres of paper per second.
+    pub fn millimetres_per_second(self) -> f32 {
+        match self {
+            PaperSpeed::Five => 5.0,
+            PaperSpeed::Ten => 10.0,
+            PaperSpeed::TwelveAndHalf => 12.5,
+            PaperSpeed::TwentyFive => 25.0,
+            PaperSpeed::Fifty => 50.0,
+            PaperSpeed::Hundred => 100.0,
+            PaperSpeed::TwoHundred => 200.0,
+        }
+    }
+}
+
+impl fmt::Display for PaperSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mm/s", self.millimetres_per_second())
+    }
+}
+
+/// Vertical scale of voltage channels on the ECG grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WaveformGain {
+    Five,
+    #[default]
+    Ten,
+    Twenty,
+}
+
+impl WaveformGain {
+    pub const ALL: [WaveformGain; 3] =
+        [WaveformGain::Five, WaveformGain::Ten, WaveformGain::Twenty];
+
+    /// Millimetres of paper per millivolt.
+    pub fn millimetres_per_millivolt(self) -> f32 {
+        match self {
+            WaveformGain::Five => 5.0,
+            WaveformGain::Ten => 10.0,
+            WaveformGain::Twenty => 20.0,
+        }
+    }
+}
+
+impl fmt::Display for WaveformGain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mm/mV", self.millimetres_per_millivolt())
+    }
+}
@@ -1,3 +1,7 @@
+pub mod csv;
 pub mod formatting;
+pub mod template;
 
+pub use csv::csv_line;
 pub use formatting::{format_tag, value_to_string};
+pub use template::{render_lines, render_template};
@@ -0,0 +1,88 @@
+/// Expands `{Name}` and `{Name:spec}` placeholders using `lookup`.
+///
+/// Supported specs: `.N` for N decimals, `0N` for zero padding to width N,
+/// and `N` for right-aligned padding to width N. Numeric specs are applied to
+/// each backslash-separated value that parses as a number; other values are
+/// left as they are. Unknown names expand to an empty string. `{{` and `}}`
+/// produce literal braces.
+pub fn render_template(template: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let placeholder: String = chars.by_ref().take_while(|&ch| ch != '}').collect();
+                let (name, spec) = match placeholder.split_once(':') {
+                    Some((name, spec)) => (name.trim(), Some(spec.trim())),
+                    None => (placeholder.trim(), None),
+                };
+                if let Some(value) = lookup(name) {
+                    rendered.push_str(&apply_spec(&value, spec));
+                }
+            }
+            other => rendered.push(other),
+        }
+    }
+
+    rendered
+}
+
+/// Renders a multi-line template, dropping lines whose placeholders all
+/// expanded to nothing so that absent attributes leave no stray labels.
+/// Surrounding whitespace left by empty placeholders is trimmed.
+pub fn render_lines(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    template
+        .lines()
+        .filter_map(|line| {
+            if !line.contains('{') {
+                return Some(line.to_string());
+            }
+            let mut any_value = false;
+            let rendered = render_template(line, |name| {
+                let value = lookup(name).filter(|value| !value.is_empty());
+                any_value |= value.is_some();
+                value
+            });
+            any_value.then(|| rendered.trim().to_string())
+        })
+        .collect()
+}
+
+fn apply_spec(value: &str, spec: Option<&str>) -> String {
+    let Some(spec) = spec.filter(|spec| !spec.is_empty()) else {
+        return value.to_string();
+    };
+
+    value
+        .split('\\')
+        .map(|part| format_part(part.trim(), spec))
+        .collect::<Vec<_>>()
+        .join("\\")
+}
+
+fn format_part(part: &str, spec: &str) -> String {
+    if let Some(precision) = spec.strip_prefix('.') {
+        return match (precision.parse::<usize>(), part.parse::<f64>()) {
+            (Ok(precision), Ok(number)) => format!("{number:.precision$}"),
+            _ => part.to_string(),
+        };
+    }
+
+    let zero_pad = spec.starts_with('0');
+    let Ok(width) = spec.parse::<usize>() else {
+        return part.to_string();
+    };
+    match part.parse::<i64>() {
+        Ok(number) if zero_pad => format!("{number:0width$}"),
+        _ => format!("{part:>width$}"),
+    }
+}
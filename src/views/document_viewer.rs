@@ -0,0 +1,51 @@
+use crate::message::Message;
+use crate::model::{DocumentPreview, EncapsulatedDocument};
+use iced::widget::text::Wrapping;
+use iced::widget::{button, column, container, image, row, scrollable, text};
+use iced::{Alignment, ContentFit, Element, Length};
+
+/// An encapsulated document: its type and MIME type, a rendered page, model
+/// view or text when one could be made, and a button to extract it.
+pub fn document_panel(document: &EncapsulatedDocument) -> Element<'_, Message> {
+    let mut details = vec![document.kind.name().to_string()];
+    details.extend(document.mime_type.clone());
+    details.push(format!("{} bytes", document.data.len()));
+
+    let header = row![
+        column![
+            text(document.title.as_deref().unwrap_or(document.kind.name())).size(16),
+            text(details.join(" · ")).size(12),
+        ]
+        .spacing(4)
+        .width(Length::Fill),
+        button(text("Save document").size(13))
+            .style(button::secondary)
+            .on_press(Message::SaveDocument),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    let body: Element<'_, Message> = match &document.preview {
+        DocumentPreview::Image(preview, caption) => column![
+            image(preview.handle.clone())
+                .content_fit(ContentFit::Contain)
+                .width(Length::Fill)
+                .height(Length::Fill),
+            text(caption).size(12),
+        ]
+        .spacing(4)
+        .align_x(Alignment::Center)
+        .into(),
+        DocumentPreview::Text(content) => {
+            scrollable(container(text(content).size(13).wrapping(Wrapping::Word)).padding(8))
+                .height(Length::Fill)
+                .into()
+        }
+        DocumentPreview::Pending => text("Rendering…").size(13).into(),
+        DocumentPreview::Unavailable(reason) => {
+            text(reason).size(13).wrapping(Wrapping::Word).into()
+        }
+    };
+
+    column![header, body].spacing(8).into()
+}
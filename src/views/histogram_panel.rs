@@ -0,0 +1,117 @@
+use crate::components::histogram_chart::HistogramChart;
+use crate::message::Message;
+use crate::model::{Histogram, HistogramScope, Window};
+use iced::widget::text::Wrapping;
+use iced::widget::{button, canvas, checkbox, column, pick_list, row, text};
+use iced::{Alignment, Element, Length};
+use std::fmt;
+
+const CHART_HEIGHT: f32 = 140.0;
+
+pub struct HistogramPanel<'a> {
+    pub visible: bool,
+    pub histogram: Option<&'a Histogram>,
+    pub window: Option<Window>,
+    pub log_scale: bool,
+    pub scope: HistogramScope,
+    /// Ids of the ROI measurements on the displayed instance.
+    pub roi_ids: Vec<usize>,
+    pub unit: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ScopeOption(HistogramScope);
+
+impl fmt::Display for ScopeOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            HistogramScope::Frame => write!(f, "Whole frame"),
+            HistogramScope::Roi(id) => write!(f, "ROI #{id}"),
+        }
+    }
+}
+
+pub fn histogram_panel(panel: HistogramPanel<'_>) -> Element<'_, Message> {
+    let window_label = match panel.window {
+        Some(window) => format!("W {:.0} / L {:.0}", window.width, window.center),
+        None => String::from("No VOI window"),
+    };
+    let toggle_label = if panel.visible {
+        "Hide Histogram"
+    } else {
+        "Histogram"
+    };
+    let header = row![
+        text(window_label).size(14).width(Length::Fill),
+        button(text("Reset W/L").size(13))
+            .style(button::secondary)
+            .on_press_maybe(panel.window.map(|_| Message::ResetWindow)),
+        button(text(toggle_label).size(13))
+            .style(button::secondary)
+            .on_press(Message::ToggleHistogram),
+    ]
+    .spacing(6)
+    .align_y(Alignment::Center);
+
+    if !panel.visible {
+        return header.into();
+    }
+
+    let options = std::iter::once(HistogramScope::Frame)
+        .chain(panel.roi_ids.iter().copied().map(HistogramScope::Roi))
+        .map(ScopeOption)
+        .collect::<Vec<_>>();
+    let controls = row![
+        pick_list(options, Some(ScopeOption(panel.scope)), |option| {
+            Message::SetHistogramScope(option.0)
+        })
+        .text_size(13),
+        checkbox("Log scale", panel.log_scale)
+            .text_size(13)
+            .on_toggle(Message::SetHistogramLogScale),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    let Some(histogram) = panel.histogram else {
+        // ROIs are only offered on monochrome frames, so an ROI without a
+        // histogram covers no pixels; keep the scope selectable to leave it.
+        return match panel.scope {
+            HistogramScope::Roi(_) => column![
+                header,
+                controls,
+                text("The ROI covers no pixels of this frame").size(13)
+            ],
+            HistogramScope::Frame => column![
+                header,
+                text("Histogram is only available for monochrome frames").size(13)
+            ],
+        }
+        .spacing(6)
+        .into();
+    };
+
+    let chart = canvas(HistogramChart::new(
+        histogram,
+        panel.window,
+        panel.log_scale,
+    ))
+    .width(Length::Fill)
+    .height(Length::Fixed(CHART_HEIGHT));
+
+    let unit = panel.unit.unwrap_or("");
+    let stats = &histogram.stats;
+    let summary = format!(
+        "n {} · mean {:.2} ± {:.2} {unit} · median {:.2} · min {:.2} · max {:.2}",
+        stats.pixel_count, stats.mean, stats.std_dev, histogram.median, stats.min, stats.max
+    );
+
+    column![
+        header,
+        controls,
+        chart,
+        text(summary).size(13).wrapping(Wrapping::Word)
+    ]
+    .spacing(6)
+    .into()
+}
@@ -1,15 +1,217 @@
+use crate::components::image_canvas::{
+    ContourOverlay, ImageCanvas, ImageLayer, ShapeOverlay, TextOverlay,
+};
 use crate::message::Message;
-use crate::model::DicomView;
-use iced::widget::{text, Image};
-use iced::{Element, Length};
+use crate::model::{
+    AnnotationContext, ColorMap, CornerTemplates, DicomView, Draft, ExportMode, ExportScale,
+    ExportScope, Fusion, FusionCandidate, Measurement, MeasurementTool, Orientation, OverlayColor,
+    OverlayStyle, PixelProbe, PresentationState, RoiStructure, Segment, ViewTransform,
+};
+use iced::widget::text::Wrapping;
+use iced::widget::{
+    button, canvas, checkbox, column, pick_list, row, scrollable, slider, text, text_input, Column,
+};
+use iced::{Alignment, Element, Length, Point};
+use std::collections::BTreeMap;
+use std::fmt;
 
-pub fn image_panel(view: Option<&DicomView>) -> Element<'static, Message> {
+const MEASUREMENT_LIST_HEIGHT: f32 = 160.0;
+
+pub struct MeasurementPanel<'a> {
+    pub active_tool: Option<MeasurementTool>,
+    pub draft: Option<&'a Draft>,
+    /// Measurements drawn on the displayed instance.
+    pub measurements: Vec<&'a Measurement>,
+    /// Measurements across all instances, for export.
+    pub total: usize,
+}
+
+pub struct OverlayPanel<'a> {
+    pub styles: &'a BTreeMap<u16, OverlayStyle>,
+    /// Rendered overlay planes of the displayed frame.
+    pub layers: &'a [ImageLayer],
+    /// Segments of the loaded segmentations that apply to the displayed image.
+    pub segments: Vec<SegmentLegend<'a>>,
+    /// Visible RT contours, ultrasound regions and presentation state
+    /// graphics on the displayed frame.
+    pub contours: &'a [ContourOverlay],
+    /// Ultrasound region names and presentation state text on the displayed
+    /// frame.
+    pub labels: &'a [TextOverlay],
+    /// Whether the ultrasound regions are outlined.
+    pub ultrasound_regions: bool,
+    /// ROIs of the selected structure set or of those that apply to the
+    /// displayed image.
+    pub rois: Vec<RoiLegend<'a>>,
+    /// Presentation states that reference the displayed image, newest first.
+    pub presentation_states: Vec<&'a PresentationState>,
+    /// SOP Instance UID of the applied presentation state.
+    pub presentation_state: Option<&'a str>,
+}
+
+pub struct SegmentLegend<'a> {
+    /// SOP Instance UID of the segmentation.
+    pub segmentation: &'a str,
+    pub segment: &'a Segment,
+    pub visible: bool,
+    /// Whether the segment has a frame on the displayed image frame.
+    pub on_frame: bool,
+}
+
+pub struct RoiLegend<'a> {
+    /// SOP Instance UID of the structure set.
+    pub structure_set: &'a str,
+    pub roi: &'a RoiStructure,
+    pub visible: bool,
+    /// Number of the ROI's contours on the displayed frame.
+    pub on_frame: usize,
+}
+
+pub struct FusionPanel<'a> {
+    /// PET series that share the displayed image's frame of reference.
+    pub candidates: Vec<FusionCandidate>,
+    /// The fusion shown on the displayed image.
+    pub fusion: Option<&'a Fusion>,
+}
+
+pub struct ExportPanel<'a> {
+    pub mode: ExportMode,
+    /// Output scale of exports rendered as displayed.
+    pub scale: ExportScale,
+    /// File name template for batch exports.
+    pub template: &'a str,
+    /// Template used when `template` is empty.
+    pub placeholder: &'a str,
+}
+
+pub fn image_panel<'a>(
+    view: Option<&'a DicomView>,
+    hovered: Option<Point>,
+    measurements: MeasurementPanel<'a>,
+    overlays: OverlayPanel<'a>,
+    fusion: FusionPanel<'a>,
+    export: ExportPanel<'a>,
+    annotations: Option<&'a CornerTemplates>,
+) -> Element<'a, Message> {
     if let Some(view) = view {
-        if let Some(handle) = &view.image {
-            Image::new(handle.clone())
+        if let Some(preview) = &view.image {
+            let shapes = measurements
+                .measurements
+                .iter()
+                .map(|measurement| ShapeOverlay {
+                    shape: measurement.shape.clone(),
+                    label: Some(measurement.result.summary_short()),
+                })
+                .collect();
+            let mut image_canvas =
+                ImageCanvas::new(preview.handle.clone(), preview.columns, preview.rows)
+                    .layers(overlays.layers.to_vec())
+                    .contours(overlays.contours.to_vec())
+                    .labels(overlays.labels.to_vec())
+                    .displayed_area(view.displayed_area)
+                    .shapes(shapes)
+                    .draft(measurements.draft.and_then(|draft| draft.preview(hovered)))
+                    .interactive(measurements.active_tool.is_some())
+                    .transform(view.transform);
+            if let Some(templates) = annotations {
+                let orientation = match view.frame.as_ref().and_then(|frame| frame.plane) {
+                    Some(plane) => Orientation::from_plane(&plane),
+                    None => Orientation::from_object(&view.source),
+                };
+                image_canvas = image_canvas
+                    .edge_labels(
+                        orientation.map(|orientation| orientation.edge_labels(view.transform)),
+                    )
+                    .scale_bar(
+                        view.frame
+                            .as_ref()
+                            .and_then(|frame| frame.calibration)
+                            .map(|calibration| calibration.spacing),
+                    )
+                    .annotations(
+                        templates,
+                        AnnotationContext {
+                            object: &view.source,
+                            frame_index: view.frame_index,
+                            frame_count: view.frame_count,
+                            window: view.window.or_else(|| view.frame.as_ref()?.default_window),
+                            zoom: 1.0,
+                        },
+                    );
+            }
+            let image = canvas(image_canvas)
                 .width(Length::Fill)
-                .height(Length::Fill)
-                .into()
+                .height(Length::Fill);
+
+            let probe = hovered.and_then(|position| {
+                view.frame
+                    .as_ref()?
+                    .probe(position.y.floor() as u32, position.x.floor() as u32)
+            });
+            let readout = match probe {
+                Some(probe) => probe_text(&probe, view, fusion.fusion),
+                None => String::from("Hover over the image to probe pixel values"),
+            };
+
+            let mut content = column![
+                tool_bar(
+                    measurements.active_tool,
+                    annotations.is_some(),
+                    view.transform
+                ),
+                image
+            ]
+            .spacing(8);
+            if view.frame_count > 1 {
+                content = content.push(frame_slider(view.frame_index, view.frame_count));
+            }
+            // A full Palette Color LUT already colours every pixel.
+            let pseudocolor = view.frame.as_ref().is_some_and(|frame| {
+                frame.is_monochrome()
+                    && frame
+                        .palette
+                        .as_ref()
+                        .is_none_or(|palette| palette.supplemental)
+            });
+            if pseudocolor {
+                content = content.push(color_map_picker(view.color_map));
+            }
+            content = content.push(text(readout).size(14).wrapping(Wrapping::Word));
+            if !view.overlays.is_empty() {
+                content = content.push(overlay_controls(view, overlays.styles));
+            }
+            let regions = view
+                .frame
+                .as_ref()
+                .map_or(0, |frame| frame.ultrasound_regions.len());
+            if regions > 0 {
+                content = content.push(
+                    checkbox(
+                        format!("Show ultrasound regions ({regions})"),
+                        overlays.ultrasound_regions,
+                    )
+                    .text_size(13)
+                    .on_toggle(Message::SetUltrasoundRegionsVisible),
+                );
+            }
+            if !overlays.presentation_states.is_empty() {
+                content = content.push(presentation_state_picker(&overlays));
+            }
+            if !fusion.candidates.is_empty() {
+                content = content.push(fusion_controls(fusion));
+            }
+            if !overlays.segments.is_empty() {
+                content = content.push(segment_legend(&overlays.segments));
+            }
+            if !overlays.rois.is_empty() {
+                content = content.push(roi_table(&overlays.rois));
+            }
+            content = content.push(export_controls(view, &export));
+            content.push(measurement_list(&measurements)).into()
+        } else if !overlays.rois.is_empty() {
+            scrollable(roi_table(&overlays.rois)).into()
+        } else if let Some(error) = &view.pixel_error {
+            text(error.as_str()).wrapping(Wrapping::Word).into()
         } else {
             text("No frame preview available").into()
         }
@@ -17,3 +219,463 @@ pub fn image_panel(view: Option<&DicomView>) -> Element<'static, Message> {
         text("Select an instance to preview its first frame").into()
     }
 }
+
+fn tool_bar(
+    active_tool: Option<MeasurementTool>,
+    annotations: bool,
+    transform: ViewTransform,
+) -> Element<'static, Message> {
+    let tool_button = |label: &'static str, tool: Option<MeasurementTool>| {
+        let style = if active_tool == tool {
+            button::primary
+        } else {
+            button::secondary
+        };
+        button(text(label).size(13))
+            .style(style)
+            .on_press(Message::SelectTool(tool))
+    };
+
+    let mut bar = row![tool_button("Probe", None)].spacing(4);
+    for tool in MeasurementTool::ALL {
+        bar = bar.push(tool_button(tool.label(), Some(tool)));
+    }
+    let annotation_style = if annotations {
+        button::primary
+    } else {
+        button::secondary
+    };
+    bar = bar.push(
+        button(text("Annotations (H)").size(13))
+            .style(annotation_style)
+            .on_press(Message::ToggleAnnotations),
+    );
+    let transform_button = |label: &'static str, transform: ViewTransform| {
+        button(text(label).size(13))
+            .style(button::secondary)
+            .on_press(Message::TransformView(transform))
+    };
+    bar = bar
+        .push(transform_button(
+            "Rotate L",
+            ViewTransform::ROTATE_COUNTER_CLOCKWISE,
+        ))
+        .push(transform_button(
+            "Rotate R",
+            ViewTransform::ROTATE_CLOCKWISE,
+        ))
+        .push(transform_button("Flip H", ViewTransform::FLIP_HORIZONTAL))
+        .push(transform_button("Flip V", ViewTransform::FLIP_VERTICAL))
+        .push(
+            button(text("MPR").size(13))
+                .style(button::secondary)
+                .on_press(Message::OpenMpr),
+        )
+        .push(
+            button(text("Reset view").size(13))
+                .style(button::secondary)
+                .on_press_maybe(
+                    (!transform.is_identity()).then(|| Message::TransformView(transform.inverse())),
+                ),
+        );
+    scrollable(bar)
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::new().width(4).scroller_width(4),
+        ))
+        .into()
+}
+
+fn frame_slider(frame_index: u32, frame_count: u32) -> Element<'static, Message> {
+    row![
+        text(format!("Frame {} / {frame_count}", frame_index + 1)).size(13),
+        slider(0..=frame_count - 1, frame_index, Message::SelectFrame),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn color_map_picker(color_map: ColorMap) -> Element<'static, Message> {
+    row![
+        text("Color map").size(14),
+        pick_list(ColorMap::ALL, Some(color_map), Message::SetColorMap).text_size(14),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn export_controls<'a>(view: &DicomView, export: &ExportPanel<'a>) -> Element<'a, Message> {
+    let export_button = |label: &'static str, message: Message| {
+        button(text(label).size(13))
+            .style(button::secondary)
+            .on_press(message)
+    };
+    let mut buttons = row![export_button(
+        "Export frame",
+        Message::ExportImages(ExportScope::Frame)
+    )]
+    .spacing(4);
+    if view.frame_count > 1 {
+        buttons = buttons
+            .push(export_button(
+                "Export all frames",
+                Message::ExportImages(ExportScope::Instance),
+            ))
+            .push(export_button(
+                "Cine",
+                Message::ExportCine(ExportScope::Instance),
+            ));
+    }
+    buttons = buttons
+        .push(export_button(
+            "Export series",
+            Message::ExportImages(ExportScope::Series),
+        ))
+        .push(export_button(
+            "Series cine",
+            Message::ExportCine(ExportScope::Series),
+        ))
+        .push(export_button(
+            "Save presentation state",
+            Message::ExportPresentationState,
+        ));
+
+    column![
+        row![
+            text("Export").size(14),
+            pick_list(ExportMode::ALL, Some(export.mode), Message::SetExportMode).text_size(13),
+            pick_list(
+                ExportScale::ALL,
+                Some(export.scale),
+                Message::SetExportScale
+            )
+            .text_size(13),
+            text_input(export.placeholder, export.template)
+                .on_input(Message::SetExportTemplate)
+                .size(13),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        scrollable(buttons).direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::new().width(4).scroller_width(4),
+        )),
+    ]
+    .spacing(4)
+    .into()
+}
+
+/// Presentation state in the picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PresentationOption {
+    sop_instance_uid: String,
+    label: String,
+}
+
+impl fmt::Display for PresentationOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+fn presentation_state_picker(overlays: &OverlayPanel<'_>) -> Element<'static, Message> {
+    let options = overlays
+        .presentation_states
+        .iter()
+        .map(|state| PresentationOption {
+            sop_instance_uid: state.sop_instance_uid.clone(),
+            label: state.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let selected = options
+        .iter()
+        .find(|option| Some(option.sop_instance_uid.as_str()) == overlays.presentation_state)
+        .cloned();
+    row![
+        text("Presentation state").size(14),
+        pick_list(options, selected, |option| {
+            Message::SelectPresentationState(Some(option.sop_instance_uid))
+        })
+        .placeholder("None")
+        .text_size(13),
+        button(text("Off").size(13))
+            .style(button::secondary)
+            .on_press_maybe(
+                overlays
+                    .presentation_state
+                    .map(|_| Message::SelectPresentationState(None))
+            ),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn fusion_controls(panel: FusionPanel<'_>) -> Element<'_, Message> {
+    let selected = panel.fusion.and_then(|fusion| {
+        panel
+            .candidates
+            .iter()
+            .find(|candidate| candidate.series_instance_uid == fusion.series_instance_uid)
+            .cloned()
+    });
+    let picker = row![
+        text("PET fusion").size(14),
+        pick_list(panel.candidates, selected, |candidate| {
+            Message::SetFusionSeries(Some(candidate.series_instance_uid))
+        })
+        .placeholder("Choose a PET series")
+        .text_size(13),
+        button(text("Off").size(13))
+            .style(button::secondary)
+            .on_press_maybe(panel.fusion.map(|_| Message::SetFusionSeries(None))),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    let Some(fusion) = panel.fusion else {
+        return picker.into();
+    };
+    let settings: Element<'_, Message> = if fusion.volume.is_none() {
+        text("Resampling PET…").size(13).into()
+    } else {
+        let unit = if fusion.is_suv() { " SUV" } else { "" };
+        row![
+            text(format!("Opacity {:.0}%", fusion.opacity * 100.0)).size(13),
+            slider(0.0..=1.0, fusion.opacity, Message::SetFusionOpacity).step(0.05),
+            pick_list(
+                ColorMap::ALL,
+                Some(fusion.color_map),
+                Message::SetFusionColorMap
+            )
+            .text_size(13),
+            text(format!("Upper {:.1}{unit}", fusion.window.upper())).size(13),
+            slider(
+                0.0..=fusion.max_value.max(1.0),
+                fusion.window.upper(),
+                Message::SetFusionUpper
+            )
+            .step(fusion.max_value.max(1.0) / 200.0),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center)
+        .into()
+    };
+    column![picker, settings].spacing(4).into()
+}
+
+fn overlay_controls<'a>(
+    view: &'a DicomView,
+    styles: &BTreeMap<u16, OverlayStyle>,
+) -> Element<'a, Message> {
+    view.overlays
+        .iter()
+        .fold(
+            column![text("Overlays").size(14)].spacing(4),
+            |list, plane| {
+                let group = plane.group;
+                let style = styles
+                    .get(&group)
+                    .copied()
+                    .unwrap_or_else(|| OverlayStyle::for_group(group));
+                let on_frame = plane.overlay_frame(view.frame_index).is_some();
+                let label = if on_frame {
+                    plane.name()
+                } else {
+                    format!("{} (not on this frame)", plane.name())
+                };
+                list.push(
+                    row![
+                        checkbox(label, style.visible)
+                            .text_size(13)
+                            .on_toggle(move |visible| Message::SetOverlayVisible(group, visible))
+                            .width(Length::Fill),
+                        pick_list(OverlayColor::ALL, Some(style.color), move |color| {
+                            Message::SetOverlayColor(group, color)
+                        })
+                        .text_size(13),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                )
+            },
+        )
+        .into()
+}
+
+fn segment_legend<'a>(segments: &[SegmentLegend<'a>]) -> Element<'a, Message> {
+    segments
+        .iter()
+        .fold(
+            column![text("Segments").size(14)].spacing(4),
+            |list, legend| {
+                let segmentation = legend.segmentation.to_string();
+                let number = legend.segment.number;
+                let label = if legend.on_frame {
+                    legend.segment.label.clone()
+                } else {
+                    format!("{} (not on this frame)", legend.segment.label)
+                };
+                list.push(
+                    row![
+                        text("■").size(16).color(legend.segment.to_color()),
+                        checkbox(label, legend.visible)
+                            .text_size(13)
+                            .on_toggle(move |visible| {
+                                Message::SetSegmentVisible(segmentation.clone(), number, visible)
+                            })
+                            .width(Length::Fill),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                )
+            },
+        )
+        .into()
+}
+
+fn roi_table<'a>(rois: &[RoiLegend<'a>]) -> Element<'a, Message> {
+    let header = row![
+        text("ROI").size(13).width(Length::Fill),
+        text("Type").size(13).width(Length::Fixed(90.0)),
+        text("Contours").size(13).width(Length::Fixed(90.0)),
+    ]
+    .spacing(8);
+    rois.iter()
+        .fold(
+            column![
+                text(format!("Structures ({})", rois.len())).size(14),
+                header
+            ]
+            .spacing(4),
+            |table, legend| {
+                let structure_set = legend.structure_set.to_string();
+                let number = legend.roi.number;
+                let contours = if legend.on_frame > 0 {
+                    format!("{} ({} here)", legend.roi.contours.len(), legend.on_frame)
+                } else {
+                    legend.roi.contours.len().to_string()
+                };
+                table.push(
+                    row![
+                        text("■").size(16).color(legend.roi.to_color()),
+                        checkbox(legend.roi.name.clone(), legend.visible)
+                            .text_size(13)
+                            .on_toggle(move |visible| {
+                                Message::SetRoiVisible(structure_set.clone(), number, visible)
+                            })
+                            .width(Length::Fill),
+                        text(legend.roi.kind.clone().unwrap_or_default())
+                            .size(13)
+                            .width(Length::Fixed(90.0)),
+                        text(contours).size(13).width(Length::Fixed(90.0)),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                )
+            },
+        )
+        .into()
+}
+
+fn measurement_list<'a>(panel: &MeasurementPanel<'a>) -> Element<'a, Message> {
+    let export = button(text("Export CSV").size(13))
+        .on_press_maybe((panel.total > 0).then_some(Message::ExportMeasurements));
+    let header = row![
+        text(format!("Measurements ({})", panel.measurements.len()))
+            .size(14)
+            .width(Length::Fill),
+        export,
+    ]
+    .align_y(Alignment::Center);
+
+    let items = panel
+        .measurements
+        .iter()
+        .fold(Column::new().spacing(4), |list, measurement| {
+            let mut details = column![text(format!(
+                "#{} {}: {}",
+                measurement.id,
+                measurement.shape.tool().label(),
+                measurement.result.summary()
+            ))
+            .size(13)
+            .wrapping(Wrapping::Word)];
+            if let Some(note) = measurement.result.calibration_note() {
+                details = details.push(text(format!("⚠ {note}")).size(12));
+            }
+            list.push(
+                row![
+                    details.width(Length::Fill),
+                    button(text("✕").size(12))
+                        .style(button::danger)
+                        .on_press(Message::DeleteMeasurement(measurement.id)),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            )
+        });
+
+    column![
+        header,
+        scrollable(items).height(Length::Fixed(MEASUREMENT_LIST_HEIGHT))
+    ]
+    .spacing(6)
+    .into()
+}
+
+fn probe_text(probe: &PixelProbe, view: &DicomView, fusion: Option<&Fusion>) -> String {
+    let stored = probe
+        .stored
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut parts = vec![
+        format!("Row {}, Col {}", probe.row, probe.column),
+        format!("Stored {stored}"),
+    ];
+
+    if let Some(value) = probe.modality {
+        let unit = view
+            .frame
+            .as_ref()
+            .and_then(|frame| frame.rescale.unit.as_deref())
+            .unwrap_or("");
+        parts.push(format!("Value {value:.2} {unit}").trim_end().to_string());
+    }
+
+    if let Some(suv) = probe.suv {
+        parts.push(format!("SUVbw {suv:.2}"));
+    }
+
+    let pet =
+        fusion.and_then(|fusion| Some((fusion, fusion.volume.as_ref()?.sample(probe.patient?)?)));
+    if let Some((fusion, value)) = pet {
+        if fusion.is_suv() {
+            parts.push(format!("PET SUVbw {value:.2}"));
+        } else {
+            parts.push(format!("PET {value:.0}"));
+        }
+    }
+
+    if let Some(reading) = &probe.region {
+        let values = reading
+            .values
+            .iter()
+            .flatten()
+            .map(|(value, unit)| format!("{value:.2} {}", unit.symbol()))
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            parts.push(reading.region.clone());
+        } else {
+            parts.push(format!("{} {}", reading.region, values.join(", ")));
+        }
+    }
+
+    if let Some([x, y, z]) = probe.patient {
+        parts.push(format!("Patient ({x:.1}, {y:.1}, {z:.1}) mm"));
+    }
+
+    parts.join("  ·  ")
+}
@@ -1,5 +1,6 @@
+use crate::codecs::transfer_syntax_name;
 use crate::message::Message;
-use crate::model::DicomView;
+use crate::model::{DicomView, MetadataRow};
 use iced::widget::text::Wrapping;
 use iced::widget::{column, row, scrollable, text};
 use iced::{Element, Length};
@@ -18,22 +19,40 @@ pub fn metadata_panel<'a>(
         .spacing(12)];
 
         for row in &view.metadata {
-            table = table.push(
-                row![
-                    text(&row.tag).width(Length::FillPortion(1)),
-                    text(&row.vr).width(Length::FillPortion(1)),
-                    text(&row.alias).width(Length::FillPortion(2)),
-                    text(&row.value)
-                        .width(Length::FillPortion(4))
-                        .wrapping(Wrapping::Word),
-                ]
-                .spacing(12),
-            );
+            table = table.push(metadata_row(row));
         }
 
+        // Functional groups of the displayed frame come first, as the
+        // top-level attributes of enhanced objects describe no single frame.
+        let mut content = column![].spacing(16);
+        if !view.frame_groups.is_empty() {
+            let mut groups = column![text(format!(
+                "Frame {} of {} functional groups",
+                view.frame_index + 1,
+                view.frame_count
+            ))
+            .size(15)]
+            .spacing(8);
+            for group in &view.frame_groups {
+                let source = if group.shared { "shared" } else { "per-frame" };
+                groups = groups.push(text(format!("{} ({source})", group.name)).size(14));
+                for row in &group.rows {
+                    groups = groups.push(metadata_row(row));
+                }
+            }
+            content = content.push(groups);
+        }
+        content = content.push(table.spacing(8));
+
         column![
             text(format!("File: {}", view.file_path.display())).size(16),
-            scrollable(table.spacing(8)),
+            text(format!(
+                "Transfer syntax: {} ({})",
+                transfer_syntax_name(&view.transfer_syntax),
+                view.transfer_syntax
+            ))
+            .size(14),
+            scrollable(content),
         ]
         .spacing(12)
         .into()
@@ -43,3 +62,16 @@ pub fn metadata_panel<'a>(
         text("Select an instance from the tree to inspect metadata").into()
     }
 }
+
+fn metadata_row(row: &MetadataRow) -> Element<'_, Message> {
+    row![
+        text(&row.tag).width(Length::FillPortion(1)),
+        text(&row.vr).width(Length::FillPortion(1)),
+        text(&row.alias).width(Length::FillPortion(2)),
+        text(&row.value)
+            .width(Length::FillPortion(4))
+            .wrapping(Wrapping::Word),
+    ]
+    .spacing(12)
+    .into()
+}
@@ -1,7 +1,21 @@
+pub mod document_viewer;
+pub mod histogram_panel;
 pub mod image_viewer;
 pub mod metadata_panel;
+pub mod mpr_viewer;
+pub mod structured_report;
+pub mod thumbnail_strip;
 pub mod tree_browser;
+pub mod waveform_viewer;
 
-pub use image_viewer::image_panel;
+pub use document_viewer::document_panel;
+pub use histogram_panel::{histogram_panel, HistogramPanel};
+pub use image_viewer::{
+    image_panel, ExportPanel, FusionPanel, MeasurementPanel, OverlayPanel, RoiLegend, SegmentLegend,
+};
 pub use metadata_panel::metadata_panel;
+pub use mpr_viewer::mpr_panel;
+pub use structured_report::{structured_report_panel, DosePanel};
+pub use thumbnail_strip::thumbnail_strip;
 pub use tree_browser::tree_panel;
+pub use waveform_viewer::{waveform_panel, WaveformPanel};
@@ -0,0 +1,180 @@
+use crate::components::mpr_canvas::{CrosshairLine, MprCanvas};
+use crate::message::Message;
+use crate::model::{MprAxis, MprSession, Orientation, Projection};
+use iced::widget::text::Wrapping;
+use iced::widget::{button, canvas, column, container, pick_list, row, slider, text, Column};
+use iced::{Alignment, Color, Element, Length};
+
+const MAX_OBLIQUE_ANGLE: f64 = 90.0;
+
+pub fn mpr_panel(session: &MprSession) -> Element<'_, Message> {
+    let volume = &session.volume;
+    let [columns, rows, slices] = volume.dims;
+    let [column_spacing, row_spacing, slice_spacing] = volume.spacing;
+    let header = row![
+        column![
+            text(format!("MPR · {}", session.title)).size(16),
+            text(format!(
+                "{columns}×{rows}×{slices} voxels · {column_spacing:.2}×{row_spacing:.2}×{slice_spacing:.2} mm"
+            ))
+            .size(13),
+        ]
+        .spacing(2)
+        .width(Length::Fill),
+        button(text("Reset").size(13))
+            .style(button::secondary)
+            .on_press(Message::ResetMpr),
+        button(text("Close MPR").size(13)).on_press(Message::CloseMpr),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    let [first, second, third] = MprAxis::ALL.map(|axis| plane_view(session, axis));
+    let grid = column![
+        row![first, second].spacing(8).height(Length::Fill),
+        row![third, controls(session)]
+            .spacing(8)
+            .height(Length::Fill),
+    ]
+    .spacing(8)
+    .height(Length::Fill);
+
+    column![header, grid].spacing(12).into()
+}
+
+fn plane_view(session: &MprSession, axis: MprAxis) -> Element<'_, Message> {
+    let Some(view) = session.view(axis) else {
+        // Views arrive from a background task once the volume is first resliced.
+        let message = if session.views.is_empty() {
+            "Reformatting…"
+        } else {
+            "No reformat available"
+        };
+        return container(text(message))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into();
+    };
+    let reslice = &view.reslice;
+    let lines = session
+        .views
+        .iter()
+        .filter(|other| other.reslice.plane.axis != axis)
+        .map(|other| CrosshairLine {
+            direction: reslice.intersection_direction(&other.reslice.plane),
+            color: axis_color(other.reslice.plane.axis),
+        })
+        .collect();
+    // Yaw only spins the acquired plane in place; tilt makes every plane oblique.
+    let oblique = match axis {
+        MprAxis::Slice => session.geometry.pitch != 0.0,
+        MprAxis::Column | MprAxis::Row => session.geometry.is_oblique(),
+    };
+    let mut title = reslice.plane.name().to_string();
+    if oblique {
+        title.push_str(" (oblique)");
+    }
+    if session.slab > 0.0 {
+        title.push_str(&format!(
+            " · {} {:.0} mm",
+            session.projection.short_name(),
+            session.slab
+        ));
+    }
+
+    canvas(
+        MprCanvas::new(
+            axis,
+            view.preview.handle.clone(),
+            view.preview.columns,
+            view.preview.rows,
+        )
+        .crosshair(reslice.to_image(session.geometry.center), lines)
+        .edge_labels(
+            Orientation::from_directions(reslice.plane.u, reslice.plane.v)
+                .map(|orientation| orientation.edge_labels(Default::default())),
+        )
+        .title(title, axis_color(axis)),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+fn controls(session: &MprSession) -> Element<'_, Message> {
+    let geometry = &session.geometry;
+    let [x, y, z] = geometry.center;
+    let value = session
+        .volume
+        .sample(geometry.center)
+        .map(|value| {
+            let unit = session.volume.unit.as_deref().unwrap_or("");
+            format!("Value {value:.1} {unit}").trim_end().to_string()
+        })
+        .unwrap_or_else(|| String::from("Outside the volume"));
+
+    let labelled_slider = |label: String, slider| column![text(label).size(13), slider].spacing(4);
+    let content: Column<'_, Message> = column![
+        text(format!("Crosshair ({x:.1}, {y:.1}, {z:.1}) mm"))
+            .size(13)
+            .wrapping(Wrapping::Word),
+        text(value).size(13),
+        labelled_slider(
+            format!("Slab {:.0} mm", session.slab),
+            // Up to the diagonal so a slab can project the whole volume.
+            slider(
+                0.0..=session.volume.diagonal().ceil(),
+                session.slab,
+                Message::SetMprSlab
+            )
+            .step(1.0),
+        ),
+        row![
+            text("Projection").size(13),
+            pick_list(
+                Projection::ALL,
+                Some(session.projection),
+                Message::SetMprProjection
+            )
+            .text_size(13),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        labelled_slider(
+            format!("Oblique rotation {:.0}°", geometry.yaw),
+            slider(
+                -MAX_OBLIQUE_ANGLE..=MAX_OBLIQUE_ANGLE,
+                geometry.yaw,
+                Message::SetMprYaw
+            )
+            .step(1.0),
+        ),
+        labelled_slider(
+            format!("Oblique tilt {:.0}°", geometry.pitch),
+            slider(
+                -MAX_OBLIQUE_ANGLE..=MAX_OBLIQUE_ANGLE,
+                geometry.pitch,
+                Message::SetMprPitch
+            )
+            .step(1.0),
+        ),
+        text("Click or drag to move the crosshair; scroll to page through a plane.")
+            .size(12)
+            .wrapping(Wrapping::Word),
+    ]
+    .spacing(10);
+
+    container(content)
+        .padding(8)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+fn axis_color(axis: MprAxis) -> Color {
+    match axis {
+        MprAxis::Slice => Color::from_rgb(0.95, 0.35, 0.3),
+        MprAxis::Column => Color::from_rgb(0.35, 0.85, 0.4),
+        MprAxis::Row => Color::from_rgb(0.35, 0.6, 1.0),
+    }
+}
@@ -0,0 +1,198 @@
+use crate::message::Message;
+use crate::model::{CtDoseSummary, SrNode, StructuredReport, StudyDoseTotal};
+use iced::widget::text::Wrapping;
+use iced::widget::{button, column, row, scrollable, text, Column, Space};
+use iced::{Alignment, Element, Length};
+
+const INDENT: f32 = 16.0;
+const RELATIONSHIP_WIDTH: f32 = 120.0;
+const VALUE_TYPE_WIDTH: f32 = 80.0;
+const DOSE_COLUMN_WIDTH: f32 = 90.0;
+
+/// CT dose of a dose report and of its study.
+pub struct DosePanel<'a> {
+    pub summary: &'a CtDoseSummary,
+    /// Totals over every dose report loaded for the study.
+    pub study: Option<StudyDoseTotal>,
+}
+
+/// Content tree of a Structured Report. IMAGE items, and SCOORD items
+/// selected from an image, select the referenced instance when pressed.
+pub fn structured_report_panel<'a>(
+    report: &'a StructuredReport,
+    dose: Option<DosePanel<'a>>,
+) -> Element<'a, Message> {
+    let title = report
+        .title()
+        .map_or("Structured Report", |title| title.meaning.as_str());
+    let mut status = vec![format!("{} content items", report.item_count())];
+    status.extend(
+        report
+            .completion
+            .as_ref()
+            .map(|flag| format!("Completion: {flag}")),
+    );
+    status.extend(
+        report
+            .verification
+            .as_ref()
+            .map(|flag| format!("Verification: {flag}")),
+    );
+
+    let header = row![
+        text("Relationship")
+            .size(12)
+            .width(Length::Fixed(RELATIONSHIP_WIDTH)),
+        text("Type").size(12).width(Length::Fixed(VALUE_TYPE_WIDTH)),
+        text("Concept: value").size(12),
+    ]
+    .spacing(8);
+    let items = report
+        .root
+        .children
+        .iter()
+        .fold(Column::new().spacing(2), |items, node| {
+            push_node(items, node, 0)
+        });
+
+    let mut body = Column::new().spacing(8);
+    if let Some(dose) = dose {
+        body = body.push(dose_table(dose));
+    }
+    body = body.push(header).push(items);
+
+    column![
+        text(title).size(16),
+        text(status.join(" · ")).size(12),
+        scrollable(body).height(Length::Fill),
+    ]
+    .spacing(8)
+    .into()
+}
+
+/// Per-acquisition CTDIvol, DLP, phantom and scan length, then the totals.
+fn dose_table(dose: DosePanel<'_>) -> Element<'_, Message> {
+    let number = |value: Option<f64>| {
+        value
+            .map(|value| format!("{value:.2}"))
+            .unwrap_or_else(|| String::from("–"))
+    };
+    let cell = |content: String| {
+        text(content)
+            .size(13)
+            .width(Length::Fixed(DOSE_COLUMN_WIDTH))
+    };
+    let header = row![
+        text("Acquisition").size(12).width(Length::Fill),
+        cell(String::from("CTDIvol (mGy)")).size(12),
+        cell(String::from("DLP (mGy·cm)")).size(12),
+        cell(String::from("Length (mm)")).size(12),
+        text("Phantom").size(12).width(Length::Fill),
+    ]
+    .spacing(8);
+    let summary = dose.summary;
+    let table = summary.acquisitions.iter().enumerate().fold(
+        column![text("CT dose").size(14), header].spacing(4),
+        |table, (index, acquisition)| {
+            let mut name = format!("{}.", index + 1);
+            for part in [
+                &acquisition.protocol,
+                &acquisition.target_region,
+                &acquisition.acquisition_type,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                name.push(' ');
+                name.push_str(part);
+            }
+            table.push(
+                row![
+                    text(name)
+                        .size(13)
+                        .width(Length::Fill)
+                        .wrapping(Wrapping::Word),
+                    cell(number(acquisition.ctdi_vol)),
+                    cell(number(acquisition.dlp)),
+                    cell(number(acquisition.scan_length)),
+                    text(acquisition.phantom.clone().unwrap_or_default())
+                        .size(13)
+                        .width(Length::Fill),
+                ]
+                .spacing(8),
+            )
+        },
+    );
+
+    let mut totals = format!("Report DLP total {:.2} mGy·cm", summary.dlp());
+    if let Some(events) = summary.events {
+        totals.push_str(&format!(" over {events} irradiation events"));
+    }
+    let mut table = table.push(text(totals).size(13));
+    if let Some(study) = dose.study {
+        let mut line = format!(
+            "Study DLP total {:.2} mGy·cm from {} report(s), {} acquisition(s)",
+            study.dlp, study.reports, study.acquisitions
+        );
+        if let Some(max) = study.max_ctdi_vol {
+            line.push_str(&format!(", highest CTDIvol {max:.2} mGy"));
+        }
+        table = table.push(text(line).size(13));
+    }
+    table
+        .push(
+            button(text("Export dose CSV").size(13))
+                .style(button::secondary)
+                .on_press(Message::ExportDoseCsv),
+        )
+        .into()
+}
+
+fn push_node<'a>(
+    items: Column<'a, Message>,
+    node: &'a SrNode,
+    depth: usize,
+) -> Column<'a, Message> {
+    let concept = node
+        .concept
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let value = node.value_text();
+    let label = match (concept.is_empty(), value.is_empty()) {
+        (false, false) => format!("{concept}: {value}"),
+        (false, true) => concept,
+        (true, _) => value,
+    };
+    let size = if node.children.is_empty() { 13 } else { 14 };
+    let label: Element<'a, Message> = match node.image_reference() {
+        Some(reference) => button(text(label).size(size).wrapping(Wrapping::Word))
+            .style(button::text)
+            .padding(0)
+            .on_press(Message::OpenReferencedInstance(
+                reference.sop_instance_uid.clone(),
+                reference
+                    .frames
+                    .first()
+                    .map(|number| number.saturating_sub(1)),
+            ))
+            .into(),
+        None => text(label).size(size).wrapping(Wrapping::Word).into(),
+    };
+
+    let line = row![
+        text(node.relationship.clone().unwrap_or_default())
+            .size(11)
+            .width(Length::Fixed(RELATIONSHIP_WIDTH)),
+        text(node.value_type.clone())
+            .size(11)
+            .width(Length::Fixed(VALUE_TYPE_WIDTH)),
+        Space::with_width(Length::Fixed(INDENT * depth as f32)),
+        label,
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+    node.children.iter().fold(items.push(line), |items, child| {
+        push_node(items, child, depth + 1)
+    })
+}
@@ -0,0 +1,108 @@
+use crate::message::Message;
+use crate::model::attributes::attribute_text;
+use crate::model::thumbnail::{self, SeriesGroup, Thumbnail, THUMBNAIL_SIZE};
+use crate::model::DicomEntry;
+use iced::widget::{button, center, column, image, scrollable, text, Row};
+use iced::{Element, Length};
+use std::collections::BTreeMap;
+
+const LABEL_WIDTH: f32 = THUMBNAIL_SIZE as f32 + 8.0;
+
+/// One thumbnail per loaded series and, for the series of the selected
+/// instance, one per instance.
+pub fn thumbnail_strip<'a>(
+    entries: &'a [DicomEntry],
+    groups: &[SeriesGroup],
+    thumbnails: &'a BTreeMap<String, Thumbnail>,
+    selected_instance: Option<usize>,
+) -> Element<'a, Message> {
+    let selected_series = selected_instance
+        .and_then(|index| entries.get(index))
+        .map(|entry| entry.series_instance_uid.as_str());
+
+    let series = groups.iter().fold(Row::new().spacing(6), |strip, group| {
+        let index = group.representative();
+        let count = group.instances.len();
+        let caption = if count == 1 {
+            group.label.clone()
+        } else {
+            format!("{} ({count})", group.label)
+        };
+        strip.push(tile(
+            &entries[index],
+            thumbnails,
+            caption,
+            selected_series == Some(group.series_instance_uid.as_str()),
+            index,
+        ))
+    });
+    let mut strip = column![horizontal(series)].spacing(6);
+
+    let instances = groups
+        .iter()
+        .find(|group| Some(group.series_instance_uid.as_str()) == selected_series)
+        .filter(|group| group.instances.len() > 1);
+    if let Some(group) = instances {
+        let row = group
+            .instances
+            .iter()
+            .fold(Row::new().spacing(6), |row, &index| {
+                let entry = &entries[index];
+                let mut caption = attribute_text(&entry.view.source, "InstanceNumber")
+                    .map(|number| format!("Im {number}"))
+                    .unwrap_or_else(|| String::from("Instance"));
+                if entry.view.frame_count > 1 {
+                    caption.push_str(&format!(" · {} frames", entry.view.frame_count));
+                }
+                row.push(tile(
+                    entry,
+                    thumbnails,
+                    caption,
+                    selected_instance == Some(index),
+                    index,
+                ))
+            });
+        strip = strip.push(horizontal(row));
+    }
+    strip.into()
+}
+
+fn tile<'a>(
+    entry: &DicomEntry,
+    thumbnails: &'a BTreeMap<String, Thumbnail>,
+    caption: String,
+    selected: bool,
+    index: usize,
+) -> Element<'a, Message> {
+    let size = Length::Fixed(THUMBNAIL_SIZE as f32);
+    let picture: Element<'a, Message> = match thumbnails.get(&thumbnail::key(entry)) {
+        Some(Thumbnail::Ready(handle)) => image(handle.clone()).width(size).height(size).into(),
+        Some(Thumbnail::Pending) => center(text("…").size(13)).width(size).height(size).into(),
+        Some(Thumbnail::Failed) | None => center(text("No image").size(11))
+            .width(size)
+            .height(size)
+            .into(),
+    };
+    let style = if selected {
+        button::primary
+    } else {
+        button::secondary
+    };
+    button(
+        column![picture, text(caption).size(11).width(LABEL_WIDTH)]
+            .spacing(2)
+            .width(LABEL_WIDTH),
+    )
+    .style(style)
+    .padding(4)
+    .on_press(Message::SelectInstance(index))
+    .into()
+}
+
+fn horizontal<'a>(row: Row<'a, Message>) -> Element<'a, Message> {
+    scrollable(row)
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::new().width(4).scroller_width(4),
+        ))
+        .into()
+}
@@ -0,0 +1,80 @@
+use crate::components::waveform_chart::WaveformChart;
+use crate::message::Message;
+use crate::model::{PaperSpeed, Waveform, WaveformGain};
+use iced::widget::{canvas, column, pick_list, row, scrollable, text};
+use iced::{Alignment, Element, Length};
+use std::fmt;
+
+pub struct WaveformPanel<'a> {
+    pub waveform: &'a Waveform,
+    /// Index of the multiplex group shown.
+    pub group: usize,
+    pub speed: PaperSpeed,
+    pub gain: WaveformGain,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupOption(usize, String);
+
+impl fmt::Display for GroupOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.0 + 1, self.1)
+    }
+}
+
+/// One multiplex group of a waveform on an ECG grid, scrolled along time,
+/// with pickers for the group, paper speed and gain.
+pub fn waveform_panel(panel: WaveformPanel<'_>) -> Element<'_, Message> {
+    let groups = &panel.waveform.groups;
+    let Some(group) = groups.get(panel.group).or(groups.first()) else {
+        return text("The waveform has no multiplex groups").size(13).into();
+    };
+    let options = groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| GroupOption(index, group.name()))
+        .collect::<Vec<_>>();
+    let selected = options.get(panel.group).cloned();
+
+    let mut details = vec![
+        format!("{:.2} s", group.duration()),
+        format!("{} samples", group.sample_count),
+    ];
+    details.extend(group.originality.clone());
+    let controls = row![
+        pick_list(options, selected, |option| Message::SelectWaveformGroup(
+            option.0
+        ))
+        .text_size(13)
+        .width(Length::Fill),
+        pick_list(PaperSpeed::ALL, Some(panel.speed), Message::SetPaperSpeed).text_size(13),
+        pick_list(
+            WaveformGain::ALL,
+            Some(panel.gain),
+            Message::SetWaveformGain
+        )
+        .text_size(13),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    let size = WaveformChart::size(group, panel.speed);
+    let chart = canvas(WaveformChart::new(group, panel.speed, panel.gain))
+        .width(Length::Fixed(size.width))
+        .height(Length::Fixed(size.height));
+
+    column![
+        text("Waveform").size(16),
+        text(details.join(" · ")).size(12),
+        controls,
+        scrollable(chart)
+            .direction(scrollable::Direction::Both {
+                vertical: scrollable::Scrollbar::new(),
+                horizontal: scrollable::Scrollbar::new(),
+            })
+            .width(Length::Fill)
+            .height(Length::Fill),
+    ]
+    .spacing(8)
+    .into()
+}